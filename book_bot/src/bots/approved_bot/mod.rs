@@ -9,15 +9,21 @@ use teloxide::{
 };
 
 use crate::{
-    bots::approved_bot::services::user_settings::{get_user_settings, save_user_settings},
+    bots::approved_bot::services::user_settings::{
+        get_user_settings, save_user_settings, UserPreferences,
+    },
     bots_manager::USER_ACTIVITY_CACHE,
 };
 
 use self::{
     modules::{
         annotations::get_annotations_handler, book::get_book_handler,
-        download::get_download_handler, help::get_help_handler, random::get_random_handler,
-        search::get_search_handler, settings::get_settings_handler, support::get_support_handler,
+        book_card::get_book_card_handler, download::get_download_handler,
+        genres::get_genres_handler, help::get_help_handler, history::get_history_handler,
+        inline::get_inline_handler, page_input::get_page_input_handler, random::get_random_handler,
+        search::get_search_handler, selection::get_selection_handler,
+        settings::get_settings_handler, shelf::get_shelf_handler,
+        subscriptions::get_subscriptions_handler, support::get_support_handler,
         update_history::get_update_log_handler,
     },
     services::user_settings::{get_user_or_default_lang_codes, update_user_activity},
@@ -39,13 +45,9 @@ async fn _update_activity(me: teloxide::types::Me, user: teloxide::types::User)
         if update_result.is_err() {
             let allowed_langs = get_user_or_default_lang_codes(user.id).await;
             let current = get_user_settings(user.id).await.ok().flatten();
-            let default_search = current.as_ref().and_then(|s| s.default_search);
-            let file_name_lang = current
-                .as_ref()
-                .map(|s| s.file_name_lang)
-                .unwrap_or_default();
+            let preferences = UserPreferences::from_settings(current.as_ref());
 
-            if save_user_settings(&user, &me, allowed_langs, default_search, file_name_lang)
+            if save_user_settings(&user, &me, allowed_langs, preferences)
                 .await
                 .is_ok()
             {
@@ -70,6 +72,13 @@ fn update_user_activity_handler() -> BotHandler {
                 }
             },
         ))
+        .branch(Update::filter_inline_query().inspect_async(
+            |inline_query: InlineQuery, bot: CacheMe<Throttle<Bot>>| async move {
+                if let Ok(me) = bot.get_me().await {
+                    _update_activity(me, inline_query.from).await;
+                }
+            },
+        ))
         .branch(Update::filter_message().inspect_async(
             |message: Message, bot: CacheMe<Throttle<Bot>>| async move {
                 if let Some(user) = message.from {
@@ -89,14 +98,22 @@ pub fn get_approved_handler() -> (BotHandler, BotCommands) {
             .branch(ignore_user_edited_message())
             .branch(ignore_chat_join_request())
             .branch(update_user_activity_handler())
+            .branch(get_inline_handler())
             .branch(get_help_handler())
             .branch(get_settings_handler())
             .branch(get_support_handler())
             .branch(get_random_handler())
+            .branch(get_genres_handler())
             .branch(get_download_handler())
             .branch(get_annotations_handler())
+            .branch(get_book_card_handler())
             .branch(get_book_handler())
+            .branch(get_subscriptions_handler())
+            .branch(get_shelf_handler())
+            .branch(get_history_handler())
+            .branch(get_selection_handler())
             .branch(get_update_log_handler())
+            .branch(get_page_input_handler())
             .branch(get_manager_handler())
             .branch(get_search_handler()),
         Some(vec![
@@ -104,10 +121,22 @@ pub fn get_approved_handler() -> (BotHandler, BotCommands) {
                 command: String::from("random"),
                 description: String::from("🎲 Попытать удачу"),
             },
+            BotCommand {
+                command: String::from("genres"),
+                description: String::from("📚 Каталог по жанрам"),
+            },
             BotCommand {
                 command: String::from("update_log"),
                 description: String::from("🔄 Обновления каталога"),
             },
+            BotCommand {
+                command: String::from("shelf"),
+                description: String::from("⭐ Книжная полка"),
+            },
+            BotCommand {
+                command: String::from("history"),
+                description: String::from("🕓 История загрузок"),
+            },
             BotCommand {
                 command: String::from("settings"),
                 description: String::from("⚙️ Настройки"),
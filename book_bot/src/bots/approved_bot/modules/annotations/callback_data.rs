@@ -7,13 +7,52 @@ use crate::bots::approved_bot::modules::utils::{
     errors::CallbackQueryParseError, pagination::GetPaginationCallbackData,
 };
 
+use super::commands::AnnotationCommand;
+
 static RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^(?P<an_type>[ab])_an_(?P<id>\d+)_(?P<page>\d+)$").unwrap());
+    LazyLock::new(|| Regex::new(r"^(?P<an_type>[abts])_an_(?P<id>\d+)_(?P<page>\d+)$").unwrap());
+
+static FULL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?P<an_type>[abts])_an_full_(?P<id>\d+)$").unwrap());
 
 #[derive(Debug, Clone)]
 pub enum AnnotationCallbackData {
     Book { id: u32, page: u32 },
     Author { id: u32, page: u32 },
+    Translator { id: u32, page: u32 },
+    Sequence { id: u32, page: u32 },
+}
+
+impl AnnotationCallbackData {
+    /// First page of the annotation the command asks for.
+    pub fn from_command(command: &AnnotationCommand) -> Self {
+        let page = 1;
+
+        match *command {
+            AnnotationCommand::Book { id } => AnnotationCallbackData::Book { id, page },
+            AnnotationCommand::Author { id } => AnnotationCallbackData::Author { id, page },
+            AnnotationCommand::Translator { id } => AnnotationCallbackData::Translator { id, page },
+            AnnotationCommand::Sequence { id } => AnnotationCallbackData::Sequence { id, page },
+        }
+    }
+
+    pub fn to_command(&self) -> AnnotationCommand {
+        match *self {
+            AnnotationCallbackData::Book { id, .. } => AnnotationCommand::Book { id },
+            AnnotationCallbackData::Author { id, .. } => AnnotationCommand::Author { id },
+            AnnotationCallbackData::Translator { id, .. } => AnnotationCommand::Translator { id },
+            AnnotationCallbackData::Sequence { id, .. } => AnnotationCommand::Sequence { id },
+        }
+    }
+
+    pub fn id_and_page(&self) -> (u32, u32) {
+        match *self {
+            AnnotationCallbackData::Book { id, page }
+            | AnnotationCallbackData::Author { id, page }
+            | AnnotationCallbackData::Translator { id, page }
+            | AnnotationCallbackData::Sequence { id, page } => (id, page),
+        }
+    }
 }
 
 impl FromStr for AnnotationCallbackData {
@@ -34,6 +73,8 @@ impl FromStr for AnnotationCallbackData {
         match an_type {
             "a" => Ok(AnnotationCallbackData::Author { id, page }),
             "b" => Ok(AnnotationCallbackData::Book { id, page }),
+            "t" => Ok(AnnotationCallbackData::Translator { id, page }),
+            "s" => Ok(AnnotationCallbackData::Sequence { id, page }),
             _ => Err(CallbackQueryParseError),
         }
     }
@@ -44,6 +85,8 @@ impl Display for AnnotationCallbackData {
         match self {
             AnnotationCallbackData::Book { id, page } => write!(f, "b_an_{id}_{page}"),
             AnnotationCallbackData::Author { id, page } => write!(f, "a_an_{id}_{page}"),
+            AnnotationCallbackData::Translator { id, page } => write!(f, "t_an_{id}_{page}"),
+            AnnotationCallbackData::Sequence { id, page } => write!(f, "s_an_{id}_{page}"),
         }
     }
 }
@@ -59,14 +102,48 @@ impl GetPaginationCallbackData for AnnotationCallbackData {
                 id: *id,
                 page: target_page,
             },
+            AnnotationCallbackData::Translator { id, .. } => AnnotationCallbackData::Translator {
+                id: *id,
+                page: target_page,
+            },
+            AnnotationCallbackData::Sequence { id, .. } => AnnotationCallbackData::Sequence {
+                id: *id,
+                page: target_page,
+            },
         }
         .to_string()
     }
 }
 
+/// "Read full" button under a long annotation.
+#[derive(Debug, Clone)]
+pub struct AnnotationFullCallbackData {
+    pub command: AnnotationCommand,
+}
+
+impl FromStr for AnnotationFullCallbackData {
+    type Err = CallbackQueryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let caps = FULL_RE.captures(s).ok_or(CallbackQueryParseError)?;
+        let id: u32 = caps["id"].parse().map_err(|_| CallbackQueryParseError)?;
+
+        AnnotationCommand::from_kind(&caps["an_type"], id)
+            .map(|command| AnnotationFullCallbackData { command })
+            .ok_or(CallbackQueryParseError)
+    }
+}
+
+impl Display for AnnotationFullCallbackData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}_an_full_{}", self.command.kind(), self.command.id())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::AnnotationCallbackData;
+    use super::{AnnotationCallbackData, AnnotationFullCallbackData};
+    use crate::bots::approved_bot::modules::annotations::commands::AnnotationCommand;
     use std::str::FromStr;
 
     #[test]
@@ -123,6 +200,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn round_trip_translator_and_sequence() {
+        for cd in [
+            AnnotationCallbackData::Translator { id: 12, page: 2 },
+            AnnotationCallbackData::Sequence { id: 13, page: 4 },
+        ] {
+            let parsed = AnnotationCallbackData::from_str(&cd.to_string()).unwrap();
+            assert_eq!(parsed.to_string(), cd.to_string());
+            assert_eq!(parsed.id_and_page(), cd.id_and_page());
+        }
+    }
+
     #[test]
     fn rejects_foreign_prefix() {
         assert!(AnnotationCallbackData::from_str("x_an_5_1").is_err());
@@ -132,4 +221,18 @@ mod tests {
     fn rejects_non_numeric_id() {
         assert!(AnnotationCallbackData::from_str("b_an_abc_1").is_err());
     }
+
+    #[test]
+    fn full_round_trip_and_no_clash_with_pages() {
+        let cd = AnnotationFullCallbackData {
+            command: AnnotationCommand::Sequence { id: 7 },
+        };
+        assert_eq!(cd.to_string(), "s_an_full_7");
+
+        let parsed = AnnotationFullCallbackData::from_str("s_an_full_7").unwrap();
+        assert_eq!(parsed.to_string(), cd.to_string());
+
+        assert!(AnnotationCallbackData::from_str("s_an_full_7").is_err());
+        assert!(AnnotationFullCallbackData::from_str("s_an_7_1").is_err());
+    }
 }
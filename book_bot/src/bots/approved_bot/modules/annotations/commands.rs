@@ -6,12 +6,45 @@ use crate::bots::approved_bot::modules::utils::{
 };
 
 static RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^/(?P<an_type>[ab])_an_(?P<id>\d+)$").unwrap());
+    LazyLock::new(|| Regex::new(r"^/(?P<an_type>[abts])_an_(?P<id>\d+)$").unwrap());
 
 #[derive(Debug, Clone)]
 pub enum AnnotationCommand {
     Book { id: u32 },
     Author { id: u32 },
+    Translator { id: u32 },
+    Sequence { id: u32 },
+}
+
+impl AnnotationCommand {
+    pub fn id(&self) -> u32 {
+        match *self {
+            AnnotationCommand::Book { id }
+            | AnnotationCommand::Author { id }
+            | AnnotationCommand::Translator { id }
+            | AnnotationCommand::Sequence { id } => id,
+        }
+    }
+
+    /// The letter in front of `_an_`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AnnotationCommand::Book { .. } => "b",
+            AnnotationCommand::Author { .. } => "a",
+            AnnotationCommand::Translator { .. } => "t",
+            AnnotationCommand::Sequence { .. } => "s",
+        }
+    }
+
+    pub fn from_kind(kind: &str, id: u32) -> Option<Self> {
+        match kind {
+            "a" => Some(AnnotationCommand::Author { id }),
+            "b" => Some(AnnotationCommand::Book { id }),
+            "t" => Some(AnnotationCommand::Translator { id }),
+            "s" => Some(AnnotationCommand::Sequence { id }),
+            _ => None,
+        }
+    }
 }
 
 impl CommandParse<Self> for AnnotationCommand {
@@ -21,11 +54,7 @@ impl CommandParse<Self> for AnnotationCommand {
         let an_type = &caps["an_type"];
         let id: u32 = caps["id"].parse().map_err(|_| CommandParseError)?;
 
-        match an_type {
-            "a" => Ok(AnnotationCommand::Author { id }),
-            "b" => Ok(AnnotationCommand::Book { id }),
-            _ => Err(CommandParseError),
-        }
+        AnnotationCommand::from_kind(an_type, id).ok_or(CommandParseError)
     }
 }
 
@@ -50,6 +79,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parses_translator_and_sequence() {
+        match AnnotationCommand::parse("/t_an_8").unwrap() {
+            AnnotationCommand::Translator { id } => assert_eq!(id, 8),
+            _ => panic!("wrong variant"),
+        }
+        match AnnotationCommand::parse("/s_an_9").unwrap() {
+            AnnotationCommand::Sequence { id } => assert_eq!(id, 9),
+            _ => panic!("wrong variant"),
+        }
+    }
+
     #[test]
     fn rejects_foreign_prefix() {
         assert!(AnnotationCommand::parse("/x_an_5").is_err());
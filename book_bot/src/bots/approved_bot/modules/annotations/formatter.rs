@@ -1,10 +1,18 @@
-use crate::bots::approved_bot::services::book_library::types::{AuthorAnnotation, BookAnnotation};
+use crate::bots::approved_bot::services::book_library::types::{
+    AuthorAnnotation, BookAnnotation, SequenceAnnotation, TranslatorAnnotation,
+};
+
+use super::html::annotation_plain_text;
 
 pub trait AnnotationFormat {
     fn get_file(&self) -> Option<&String>;
     fn get_text(&self) -> &str;
+    fn get_title(&self) -> &str;
 
-    fn is_normal_text(&self) -> bool;
+    /// Whether anything is left to show once the markup is stripped.
+    fn is_normal_text(&self) -> bool {
+        !annotation_plain_text(self.get_text()).is_empty()
+    }
 }
 
 impl AnnotationFormat for BookAnnotation {
@@ -16,8 +24,8 @@ impl AnnotationFormat for BookAnnotation {
         self.text.as_str()
     }
 
-    fn is_normal_text(&self) -> bool {
-        !self.text.replace(['\n', ' '], "").is_empty()
+    fn get_title(&self) -> &str {
+        self.title.as_str()
     }
 }
 
@@ -30,7 +38,35 @@ impl AnnotationFormat for AuthorAnnotation {
         self.text.as_str()
     }
 
-    fn is_normal_text(&self) -> bool {
-        !self.text.replace(['\n', ' '], "").is_empty()
+    fn get_title(&self) -> &str {
+        self.title.as_str()
+    }
+}
+
+impl AnnotationFormat for TranslatorAnnotation {
+    fn get_file(&self) -> Option<&String> {
+        self.file.as_ref()
+    }
+
+    fn get_text(&self) -> &str {
+        self.text.as_str()
+    }
+
+    fn get_title(&self) -> &str {
+        self.title.as_str()
+    }
+}
+
+impl AnnotationFormat for SequenceAnnotation {
+    fn get_file(&self) -> Option<&String> {
+        self.file.as_ref()
+    }
+
+    fn get_text(&self) -> &str {
+        self.text.as_str()
+    }
+
+    fn get_title(&self) -> &str {
+        self.title.as_str()
     }
 }
@@ -0,0 +1,592 @@
+//! Library annotations come as loose HTML. This turns them into the subset
+//! Telegram understands (`b`, `i`, `u`, `s`, `a`) and cuts them into pages
+//! that are valid markup on their own.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::bots::approved_bot::services::telegraph::Node;
+
+static TAG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?P<close>/)?(?P<name>[a-zA-Z][a-zA-Z0-9]*)(?P<attrs>\s[^>]*)?/?$").unwrap()
+});
+
+static HREF_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\bhref\s*=\s*(?:"(?P<dq>[^"]*)"|'(?P<sq>[^']*)'|(?P<bare>[^\s"'>]+))"#)
+        .unwrap()
+});
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Tag {
+    Bold,
+    Italic,
+    Underline,
+    Strike,
+    Link { href: String },
+}
+
+impl Tag {
+    fn name(&self) -> &'static str {
+        match self {
+            Tag::Bold => "b",
+            Tag::Italic => "i",
+            Tag::Underline => "u",
+            Tag::Strike => "s",
+            Tag::Link { .. } => "a",
+        }
+    }
+
+    fn open(&self) -> String {
+        match self {
+            Tag::Link { href } => format!("<a href=\"{}\">", escape(href)),
+            tag => format!("<{}>", tag.name()),
+        }
+    }
+
+    fn close(&self) -> String {
+        format!("</{}>", self.name())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Text(String),
+    Break,
+    Open(Tag),
+    Close(&'static str),
+}
+
+/// Telegram counterpart of an opening library tag. Links are kept only
+/// when they point to the web.
+fn to_tag(name: &str, attrs: &str) -> Option<Tag> {
+    match name {
+        "b" | "strong" => Some(Tag::Bold),
+        "i" | "em" | "cite" => Some(Tag::Italic),
+        "u" | "ins" => Some(Tag::Underline),
+        "s" | "strike" | "del" => Some(Tag::Strike),
+        "a" => {
+            let caps = HREF_RE.captures(attrs)?;
+            let href = ["dq", "sq", "bare"]
+                .iter()
+                .find_map(|group| caps.name(group))?
+                .as_str();
+            let href = decode_entities(href.trim());
+
+            (href.starts_with("http://") || href.starts_with("https://"))
+                .then_some(Tag::Link { href })
+        }
+        _ => None,
+    }
+}
+
+fn close_name(name: &str) -> Option<&'static str> {
+    match name {
+        "b" | "strong" => Some("b"),
+        "i" | "em" | "cite" => Some("i"),
+        "u" | "ins" => Some("u"),
+        "s" | "strike" | "del" => Some("s"),
+        "a" => Some("a"),
+        _ => None,
+    }
+}
+
+fn is_block(name: &str) -> bool {
+    matches!(
+        name,
+        "p" | "div" | "br" | "li" | "tr" | "blockquote" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+    )
+}
+
+fn decode_entity(entity: &str) -> Option<String> {
+    let named = match entity {
+        "nbsp" | "ensp" | "emsp" | "thinsp" => Some(" "),
+        "amp" => Some("&"),
+        "lt" => Some("<"),
+        "gt" => Some(">"),
+        "quot" => Some("\""),
+        "apos" => Some("'"),
+        "laquo" => Some("«"),
+        "raquo" => Some("»"),
+        "bdquo" => Some("„"),
+        "ldquo" => Some("“"),
+        "rdquo" => Some("”"),
+        "mdash" => Some("—"),
+        "ndash" => Some("–"),
+        "hellip" => Some("…"),
+        "copy" => Some("©"),
+        "shy" => Some(""),
+        _ => None,
+    };
+    if let Some(named) = named {
+        return Some(named.to_string());
+    }
+
+    let code = match entity.strip_prefix('#')? {
+        hex if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok()?,
+        dec => dec.parse().ok()?,
+    };
+
+    // Non-breaking spaces only keep words from wrapping, which Telegram
+    // does on its own.
+    match char::from_u32(code)? {
+        '\u{a0}' => Some(" ".to_string()),
+        c => Some(c.to_string()),
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(pos) = rest.find('&') {
+        result.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((decode_entity(&rest[1..=end])?, end + 2)));
+
+        match decoded {
+            Some((decoded, length)) => {
+                result.push_str(&decoded);
+                rest = &rest[length..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result.replace('\u{a0}', " ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn push_text(tokens: &mut Vec<Token>, text: &str) {
+    for (index, line) in decode_entities(text).split('\n').enumerate() {
+        if index > 0 {
+            tokens.push(Token::Break);
+        }
+        if !line.is_empty() {
+            tokens.push(Token::Text(line.to_string()));
+        }
+    }
+}
+
+/// Anything between `<` and `>` that does not look like a tag stays text,
+/// so "1 < 2" survives.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>').map(|end| start + end) else {
+            break;
+        };
+        let inner = &rest[start + 1..end];
+
+        if inner.starts_with('!') {
+            push_text(&mut tokens, &rest[..start]);
+            rest = &rest[end + 1..];
+            continue;
+        }
+
+        let Some(caps) = TAG_RE.captures(inner) else {
+            push_text(&mut tokens, &rest[..=end]);
+            rest = &rest[end + 1..];
+            continue;
+        };
+
+        push_text(&mut tokens, &rest[..start]);
+        rest = &rest[end + 1..];
+
+        let name = caps["name"].to_lowercase();
+        let is_close = caps.name("close").is_some();
+
+        if !is_close && (name == "script" || name == "style") {
+            let closing = format!("</{name}");
+            rest = match rest.to_ascii_lowercase().find(&closing) {
+                Some(pos) => rest[pos..]
+                    .find('>')
+                    .map_or("", |end| &rest[pos + end + 1..]),
+                None => "",
+            };
+            continue;
+        }
+
+        if is_block(&name) {
+            tokens.push(Token::Break);
+        } else if is_close {
+            if let Some(name) = close_name(&name) {
+                tokens.push(Token::Close(name));
+            }
+        } else if let Some(tag) = to_tag(&name, caps.name("attrs").map_or("", |m| m.as_str())) {
+            tokens.push(Token::Open(tag));
+        }
+    }
+
+    push_text(&mut tokens, rest);
+
+    tokens
+}
+
+/// Lays words out into pages of at most `width` visible characters. Tags
+/// are opened lazily right before the next word, so none is left empty at
+/// a page end, and the ones spanning a page break are closed and reopened.
+struct Pager {
+    width: usize,
+    markup: bool,
+    pages: Vec<String>,
+    page: String,
+    length: usize,
+    stack: Vec<Tag>,
+    pending: usize,
+    space: bool,
+    line_break: bool,
+}
+
+impl Pager {
+    fn new(width: usize, markup: bool) -> Self {
+        Self {
+            width,
+            markup,
+            pages: vec![],
+            page: String::new(),
+            length: 0,
+            stack: vec![],
+            pending: 0,
+            space: false,
+            line_break: false,
+        }
+    }
+
+    fn written(&self) -> usize {
+        self.stack.len() - self.pending
+    }
+
+    fn open(&mut self, tag: Tag) {
+        self.stack.push(tag);
+        self.pending += 1;
+    }
+
+    /// Closes the innermost `name` and whatever was opened inside it.
+    fn close(&mut self, name: &str) {
+        let Some(position) = self.stack.iter().rposition(|tag| tag.name() == name) else {
+            return;
+        };
+
+        while self.stack.len() > position {
+            let tag = self.stack.pop().unwrap();
+
+            if self.pending > 0 {
+                self.pending -= 1;
+            } else if self.markup {
+                self.page.push_str(&tag.close());
+            }
+        }
+    }
+
+    fn line_break(&mut self) {
+        if self.length > 0 {
+            self.line_break = true;
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if text.starts_with(char::is_whitespace) {
+            self.space = true;
+        }
+
+        for word in text.split_whitespace() {
+            self.word(word);
+            self.space = true;
+        }
+
+        if !text.ends_with(char::is_whitespace) {
+            self.space = false;
+        }
+    }
+
+    fn word(&mut self, word: &str) {
+        let mut separator = match (self.length, self.line_break, self.space) {
+            (0, _, _) => "",
+            (_, true, _) => "\n",
+            (_, false, true) => " ",
+            _ => "",
+        };
+        let word_length = word.chars().count();
+
+        if self.length > 0 && self.length + separator.len() + word_length > self.width {
+            self.break_page();
+            separator = "";
+        }
+
+        self.page.push_str(separator);
+
+        if self.markup {
+            let written = self.written();
+            for tag in &self.stack[written..] {
+                self.page.push_str(&tag.open());
+            }
+            self.page.push_str(&escape(word));
+        } else {
+            self.page.push_str(word);
+        }
+
+        self.pending = 0;
+        self.length += separator.len() + word_length;
+        self.line_break = false;
+    }
+
+    fn break_page(&mut self) {
+        let written = self.written();
+
+        if self.markup {
+            for tag in self.stack[..written].iter().rev() {
+                self.page.push_str(&tag.close());
+            }
+        }
+
+        self.pages.push(std::mem::take(&mut self.page));
+        self.length = 0;
+
+        if self.markup {
+            for tag in &self.stack[..written] {
+                self.page.push_str(&tag.open());
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        if self.length > 0 {
+            self.stack.truncate(self.written());
+            self.pending = 0;
+            self.break_page();
+        }
+
+        self.pages
+    }
+}
+
+fn layout(text: &str, width: usize, markup: bool) -> Vec<String> {
+    let mut pager = Pager::new(width, markup);
+
+    for token in tokenize(text) {
+        match token {
+            Token::Text(text) => pager.text(&text),
+            Token::Break => pager.line_break(),
+            Token::Open(tag) => pager.open(tag),
+            Token::Close(name) => pager.close(name),
+        }
+    }
+
+    pager.finish()
+}
+
+/// Pages of Telegram HTML with at most `width` visible characters each.
+pub fn render_annotation_pages(text: &str, width: usize) -> Vec<String> {
+    layout(text, width, true)
+}
+
+/// The annotation without markup, with entities decoded.
+pub fn annotation_plain_text(text: &str) -> String {
+    layout(text, usize::MAX, false).concat()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut space = false;
+
+    for c in text.chars() {
+        if c.is_whitespace() {
+            space = true;
+            continue;
+        }
+        if space {
+            result.push(' ');
+            space = false;
+        }
+        result.push(c);
+    }
+    if space {
+        result.push(' ');
+    }
+
+    result
+}
+
+fn paragraph_node(mut runs: Vec<(String, Vec<Tag>)>) -> Option<Node> {
+    if let Some((text, _)) = runs.last_mut() {
+        text.truncate(text.trim_end().len());
+    }
+    runs.retain(|(text, _)| !text.is_empty());
+
+    if runs.is_empty() {
+        return None;
+    }
+
+    let children = runs
+        .into_iter()
+        .map(|(text, tags)| {
+            tags.into_iter()
+                .rev()
+                .fold(Node::Text(text), |child, tag| match tag {
+                    Tag::Link { href } => Node::link(href, vec![child]),
+                    tag => Node::element(tag.name(), vec![child]),
+                })
+        })
+        .collect();
+
+    Some(Node::element("p", children))
+}
+
+/// The annotation as Telegraph paragraphs, one per line of the text.
+pub fn annotation_nodes(text: &str) -> Vec<Node> {
+    let mut paragraphs = vec![];
+    let mut runs: Vec<(String, Vec<Tag>)> = vec![];
+    let mut stack: Vec<Tag> = vec![];
+
+    for token in tokenize(text) {
+        match token {
+            Token::Text(text) => {
+                let mut text = collapse_whitespace(&text);
+
+                if runs.last().is_none_or(|(last, _)| last.ends_with(' ')) {
+                    text = text.trim_start().to_string();
+                }
+                if text.is_empty() {
+                    continue;
+                }
+
+                match runs.last_mut() {
+                    Some((last, tags)) if *tags == stack => last.push_str(&text),
+                    _ => runs.push((text, stack.clone())),
+                }
+            }
+            Token::Break => paragraphs.extend(paragraph_node(std::mem::take(&mut runs))),
+            Token::Open(tag) => stack.push(tag),
+            Token::Close(name) => {
+                if let Some(position) = stack.iter().rposition(|tag| tag.name() == name) {
+                    stack.truncate(position);
+                }
+            }
+        }
+    }
+
+    paragraphs.extend(paragraph_node(runs));
+
+    paragraphs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_supported_tags_and_drops_the_rest() {
+        assert_eq!(
+            render_annotation_pages(
+                "<p>Роман <strong>о&nbsp;войне</strong> и <font color=red>мире</font>.</p>\
+                 <p><em>Том&nbsp;1</em></p>",
+                512
+            ),
+            vec!["Роман <b>о войне</b> и мире.\n<i>Том 1</i>"]
+        );
+    }
+
+    #[test]
+    fn escapes_text_and_keeps_only_web_links() {
+        assert_eq!(
+            render_annotation_pages(
+                "A &lt;B&gt; & C <a href='https://ex.com/?a=1&amp;b=2'>сайт</a> \
+                 <a href=\"javascript:alert(1)\">x</a>",
+                512
+            ),
+            vec!["A &lt;B&gt; &amp; C <a href=\"https://ex.com/?a=1&amp;b=2\">сайт</a> x"]
+        );
+    }
+
+    #[test]
+    fn text_that_only_looks_like_tags_survives() {
+        assert_eq!(
+            annotation_plain_text("1 < 2 и 3 > 2<!-- comment --><script>alert(1)</script>"),
+            "1 < 2 и 3 > 2"
+        );
+    }
+
+    #[test]
+    fn pages_close_and_reopen_tags() {
+        let pages = render_annotation_pages("<b>one two <i>three four</i></b> five", 9);
+
+        assert_eq!(
+            pages,
+            vec![
+                "<b>one two</b>",
+                "<b><i>three</i></b>",
+                "<b><i>four</i></b> five",
+            ]
+        );
+    }
+
+    #[test]
+    fn unbalanced_markup_is_balanced() {
+        assert_eq!(
+            render_annotation_pages("<b>bold <i>both</b> plain</i> <u>open", 512),
+            vec!["<b>bold <i>both</i></b> plain <u>open</u>"]
+        );
+    }
+
+    #[test]
+    fn words_stay_together() {
+        assert_eq!(
+            render_annotation_pages("<i>Сло</i>во и&nbsp;дело", 512),
+            vec!["<i>Сло</i>во и дело"]
+        );
+    }
+
+    #[test]
+    fn plain_annotation_keeps_lines_and_fits_pages() {
+        let text = " Содержание:\n РОМАН И ПОВЕСТИ:\n Разбивая стеклянные двери… Предисловие В. Ревича\n\n"
+            .repeat(20);
+        let pages = render_annotation_pages(&text, 512);
+
+        assert!(pages.len() > 1);
+        assert!(pages[0].starts_with("Содержание:\nРОМАН И ПОВЕСТИ:\nРазбивая"));
+        assert!(pages.iter().all(|page| page.chars().count() <= 512));
+        assert!(pages.iter().all(|page| !page.contains("\n\n")));
+    }
+
+    #[test]
+    fn telegraph_nodes_keep_paragraphs_and_markup() {
+        let text = "<p> Роман <b>о <i>войне</i></b> </p><p></p>\
+                    <p><a href=\"https://ex.com\">сайт</a></p>";
+
+        assert_eq!(
+            serde_json::to_value(annotation_nodes(text)).unwrap(),
+            serde_json::json!([
+                {"tag": "p", "children": [
+                    "Роман ",
+                    {"tag": "b", "children": ["о "]},
+                    {"tag": "b", "children": [{"tag": "i", "children": ["войне"]}]},
+                ]},
+                {"tag": "p", "children": [
+                    {"tag": "a", "attrs": {"href": "https://ex.com"}, "children": ["сайт"]},
+                ]},
+            ])
+        );
+    }
+
+    #[test]
+    fn blank_markup_has_no_text() {
+        assert_eq!(annotation_plain_text("<p> &nbsp; </p><br/>"), "");
+        assert!(render_annotation_pages("<p></p>", 512).is_empty());
+    }
+}
@@ -2,13 +2,12 @@ pub mod callback_data;
 pub mod commands;
 pub mod errors;
 pub mod formatter;
+pub mod html;
 
 use book_bot_macros::log_handler;
 
 use std::convert::TryInto;
 
-use futures::TryStreamExt;
-
 use teloxide::{
     adaptors::{CacheMe, Throttle},
     dispatching::UpdateFilterExt,
@@ -16,33 +15,145 @@ use teloxide::{
     prelude::*,
     types::*,
 };
+use tracing::log;
 
 use crate::bots::{
     approved_bot::{
         modules::utils::{
-            message_text::is_message_text_equals,
+            constants::ERROR_TRY_LATER,
             pagination::generic_get_pagination_keyboard,
             telegram_utils::{
-                safe_edit_message_text, safe_send_message_with_reply, safe_send_photo,
+                safe_answer_callback_query, safe_answer_callback_query_with_text,
+                safe_edit_message_text_html, safe_send_message, safe_send_message_html_with_reply,
+                safe_send_message_with_reply, safe_send_photo,
+            },
+        },
+        services::{
+            book_library::{
+                get_author_annotation, get_book_annotation, get_sequence_annotation,
+                get_translator_annotation,
+            },
+            images::{fetch_telegram_photo, file_ids::PHOTO_FILE_IDS},
+            telegraph::{
+                published_pages::{content_hash, PublishedPage, PUBLISHED_PAGES},
+                PAGE_PUBLISHER,
             },
         },
-        services::book_library::{get_author_annotation, get_book_annotation},
         tools::filter_callback_query,
     },
     BotHandlerInternal,
 };
 
 use self::{
-    callback_data::AnnotationCallbackData, commands::AnnotationCommand,
-    errors::AnnotationFormatError, formatter::AnnotationFormat,
+    callback_data::{AnnotationCallbackData, AnnotationFullCallbackData},
+    commands::AnnotationCommand,
+    errors::AnnotationFormatError,
+    formatter::AnnotationFormat,
+    html::{annotation_nodes, render_annotation_pages},
 };
 
-use super::utils::{filter_command::filter_command, split_text::split_text_to_chunks};
+use super::utils::filter_command::filter_command;
+
+/// Visible characters per annotation page.
+const ANNOTATION_PAGE_LENGTH: usize = 512;
+
+/// Annotations longer than this many pages can also be read as one page.
+const READ_FULL_MIN_PAGES: usize = 3;
+
+fn get_annotation_keyboard(
+    page: u32,
+    pages: usize,
+    callback_data: AnnotationCallbackData,
+) -> anyhow::Result<InlineKeyboardMarkup> {
+    let command = callback_data.to_command();
+    let mut keyboard = generic_get_pagination_keyboard(page, pages.try_into()?, callback_data);
+
+    if pages > READ_FULL_MIN_PAGES && PAGE_PUBLISHER.is_some() {
+        keyboard.inline_keyboard.push(vec![InlineKeyboardButton {
+            text: "📖 Читать целиком".to_string(),
+            kind: InlineKeyboardButtonKind::CallbackData(
+                AnnotationFullCallbackData { command }.to_string(),
+            ),
+        }]);
+    }
+
+    Ok(keyboard)
+}
+
+/// Sends a library image as a photo. The first time it is fetched and
+/// re-encoded, afterwards the `file_id` Telegram gave this bot is reused.
+/// Images that cannot be fetched are skipped: they only decorate the text.
+pub async fn send_annotation_photo(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    url: &str,
+) -> BotHandlerInternal {
+    send_stored_photo(bot, chat_id, url, || async {
+        fetch_telegram_photo(url).await.unwrap_or_else(|err| {
+            log::warn!("Failed to fetch image {url}: {err:?}");
+            None
+        })
+    })
+    .await
+}
+
+/// Sends the photo stored in `PHOTO_FILE_IDS` under `key`, or uploads the
+/// JPEG `photo` gives and stores its `file_id` for next time. Nothing is
+/// sent when neither has a photo.
+pub async fn send_stored_photo<F, Fut>(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    key: &str,
+    photo: F,
+) -> BotHandlerInternal
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Option<Vec<u8>>>,
+{
+    let bot_id = bot.get_me().await?.id.0;
+
+    // The file id store only saves uploads, so its failures are logged and
+    // the photo is uploaded as if nothing was stored.
+    let file_id = PHOTO_FILE_IDS
+        .get(bot_id, key.to_string())
+        .await
+        .unwrap_or_else(|err| {
+            log::warn!("Failed to get photo file id for {key}: {err:?}");
+            None
+        });
+
+    if let Some(file_id) = file_id {
+        match safe_send_photo(bot, chat_id, InputFile::file_id(FileId(file_id))).await {
+            Ok(_) => return Ok(()),
+            Err(err) => {
+                log::warn!("Cached photo for {key} failed, uploading again: {err:?}");
+                if let Err(err) = PHOTO_FILE_IDS.remove(bot_id, key.to_string()).await {
+                    log::warn!("Failed to remove photo file id for {key}: {err:?}");
+                }
+            }
+        }
+    }
+
+    let Some(photo) = photo().await else {
+        return Ok(());
+    };
+
+    let photo = InputFile::memory(photo).file_name("cover.jpg");
+    let Some(message) = safe_send_photo(bot, chat_id, photo).await? else {
+        return Ok(());
+    };
+
+    // Sizes go from the smallest to the original one.
+    if let Some(size) = message.photo().and_then(|sizes| sizes.last()) {
+        if let Err(err) = PHOTO_FILE_IDS
+            .save(bot_id, key.to_string(), size.file.id.0.clone())
+            .await
+        {
+            log::warn!("Failed to save photo file id for {key}: {err:?}");
+        }
+    }
 
-async fn download_image(
-    file: &String,
-) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>> {
-    Ok(reqwest::get(file).await?.error_for_status()?)
+    Ok(())
 }
 
 #[log_handler("annotations")]
@@ -56,10 +167,7 @@ where
     T: AnnotationFormat,
     Fut: std::future::Future<Output = anyhow::Result<Option<T>>>,
 {
-    let id = match command {
-        AnnotationCommand::Book { id } => id,
-        AnnotationCommand::Author { id } => id,
-    };
+    let id = command.id();
 
     let annotation = match annotation_getter(id).await {
         Ok(Some(v)) => v,
@@ -88,14 +196,7 @@ where
     };
 
     if let Some(file) = annotation.get_file() {
-        let image_response = download_image(file).await;
-
-        if let Ok(v) = image_response {
-            let stream = v.bytes_stream().map_err(std::io::Error::other);
-            let data = tokio_util::io::StreamReader::new(stream);
-
-            safe_send_photo(&bot, message.chat.id, InputFile::read(data)).await?;
-        }
+        send_annotation_photo(&bot, message.chat.id, file).await?;
     };
 
     if !annotation.is_normal_text() {
@@ -106,30 +207,23 @@ where
         .into());
     }
 
-    let annotation_text = annotation.get_text();
-    let chunked_text = split_text_to_chunks(annotation_text, 512);
-    let current_text = match chunked_text.first() {
+    let pages = render_annotation_pages(annotation.get_text(), ANNOTATION_PAGE_LENGTH);
+    let current_text = match pages.first() {
         Some(t) => t,
         None => return Ok(()),
     };
 
-    let callback_data = match command {
-        AnnotationCommand::Book { id } => AnnotationCallbackData::Book { id, page: 1 },
-        AnnotationCommand::Author { id } => AnnotationCallbackData::Author { id, page: 1 },
-    };
-    let keyboard =
-        generic_get_pagination_keyboard(1, chunked_text.len().try_into()?, callback_data, false);
+    let callback_data = AnnotationCallbackData::from_command(&command);
+    let keyboard = get_annotation_keyboard(1, pages.len(), callback_data)?;
 
-    safe_send_message_with_reply(
+    safe_send_message_html_with_reply(
         &bot,
         message.chat.id,
         current_text,
         ReplyParameters::new(message.id),
         Some(keyboard),
     )
-    .await?;
-
-    Ok(())
+    .await
 }
 
 #[log_handler("annotations")]
@@ -143,10 +237,7 @@ where
     T: AnnotationFormat,
     Fut: std::future::Future<Output = anyhow::Result<Option<T>>>,
 {
-    let (id, page) = match callback_data {
-        AnnotationCallbackData::Book { id, page } => (id, page),
-        AnnotationCallbackData::Author { id, page } => (id, page),
-    };
+    let (id, page) = callback_data.id_and_page();
 
     let annotation = match annotation_getter(id).await {
         Ok(Some(v)) => v,
@@ -163,28 +254,24 @@ where
 
     let request_page: usize = page.try_into().unwrap_or(1);
 
-    let annotation_text = annotation.get_text();
-    let chunked_text = split_text_to_chunks(annotation_text, 512);
+    let pages = render_annotation_pages(annotation.get_text(), ANNOTATION_PAGE_LENGTH);
 
-    let page_index = if request_page <= chunked_text.len() {
+    let page_index = if request_page <= pages.len() {
         request_page
     } else {
-        chunked_text.len()
+        pages.len()
     };
 
-    let new_text = match chunked_text.get(page_index.saturating_sub(1)) {
+    let new_text = match pages.get(page_index.saturating_sub(1)) {
         Some(t) => t,
         None => return Ok(()),
     };
 
-    let keyboard =
-        generic_get_pagination_keyboard(page, chunked_text.len().try_into()?, callback_data, false);
-
-    if is_message_text_equals(Some(message.clone()), new_text) {
-        return Ok(());
-    }
+    let keyboard = get_annotation_keyboard(page, pages.len(), callback_data)?;
 
-    safe_edit_message_text(
+    // The message text has no markup left, so an unchanged page is only
+    // noticed by Telegram, which the helper tolerates.
+    safe_edit_message_text_html(
         &bot,
         message.chat().id,
         message.id(),
@@ -194,6 +281,95 @@ where
     .await
 }
 
+/// Publishes the whole annotation as a page and sends its link, which
+/// Telegram opens with Instant View. The page is published again only when
+/// the annotation has changed since.
+#[log_handler("annotations")]
+pub async fn read_full_annotation_handler<T, Fut>(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    command: AnnotationCommand,
+    annotation_getter: fn(id: u32) -> Fut,
+) -> BotHandlerInternal
+where
+    T: AnnotationFormat,
+    Fut: std::future::Future<Output = anyhow::Result<Option<T>>>,
+{
+    let (Some(message), Some(publisher)) = (cq.message, PAGE_PUBLISHER.as_ref()) else {
+        return safe_answer_callback_query(&bot, cq.id).await;
+    };
+    let (kind, id) = (command.kind(), command.id());
+
+    let annotation = match annotation_getter(id).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            return safe_answer_callback_query_with_text(
+                &bot,
+                cq.id,
+                "Аннотация недоступна :(",
+                true,
+            )
+            .await;
+        }
+        Err(err) => {
+            safe_answer_callback_query_with_text(&bot, cq.id, ERROR_TRY_LATER, true).await?;
+            return Err(err);
+        }
+    };
+
+    let title = match annotation.get_title().trim() {
+        "" => "Аннотация",
+        title => title,
+    };
+    let content_hash = content_hash(&[title, annotation.get_text()]);
+
+    let published = match PUBLISHED_PAGES.get(kind, id).await {
+        Ok(v) => v,
+        Err(err) => {
+            safe_answer_callback_query_with_text(&bot, cq.id, ERROR_TRY_LATER, true).await?;
+            return Err(err);
+        }
+    };
+
+    let url = match published {
+        Some(page) if page.content_hash == content_hash => page.url,
+        _ => {
+            let url = match publisher
+                .publish(title, &annotation_nodes(annotation.get_text()))
+                .await
+            {
+                Ok(v) => v,
+                Err(err) => {
+                    safe_answer_callback_query_with_text(&bot, cq.id, ERROR_TRY_LATER, true)
+                        .await?;
+                    return Err(err);
+                }
+            };
+
+            // The page is up either way; without it saved, the next click
+            // only publishes it again.
+            if let Err(err) = PUBLISHED_PAGES
+                .save(
+                    kind,
+                    id,
+                    PublishedPage {
+                        url: url.clone(),
+                        content_hash,
+                    },
+                )
+                .await
+            {
+                log::warn!("Failed to save published page: {err:?}");
+            }
+
+            url
+        }
+    };
+
+    safe_send_message(&bot, message.chat().id, url, None).await?;
+    safe_answer_callback_query(&bot, cq.id).await
+}
+
 pub fn get_annotations_handler() -> crate::bots::BotHandler {
     dptree::entry()
         .branch(
@@ -215,6 +391,24 @@ pub fn get_annotations_handler() -> crate::bots::BotHandler {
                                 )
                                 .await
                             }
+                            AnnotationCommand::Translator { .. } => {
+                                send_annotation_handler(
+                                    message,
+                                    bot,
+                                    command,
+                                    get_translator_annotation,
+                                )
+                                .await
+                            }
+                            AnnotationCommand::Sequence { .. } => {
+                                send_annotation_handler(
+                                    message,
+                                    bot,
+                                    command,
+                                    get_sequence_annotation,
+                                )
+                                .await
+                            }
                         }
                     },
                 ),
@@ -245,6 +439,67 @@ pub fn get_annotations_handler() -> crate::bots::BotHandler {
                                 )
                                 .await
                             }
+                            AnnotationCallbackData::Translator { .. } => {
+                                annotation_pagination_handler(
+                                    cq,
+                                    bot,
+                                    callback_data,
+                                    get_translator_annotation,
+                                )
+                                .await
+                            }
+                            AnnotationCallbackData::Sequence { .. } => {
+                                annotation_pagination_handler(
+                                    cq,
+                                    bot,
+                                    callback_data,
+                                    get_sequence_annotation,
+                                )
+                                .await
+                            }
+                        }
+                    },
+                ),
+        )
+        .branch(
+            Update::filter_callback_query()
+                .chain(filter_callback_query::<AnnotationFullCallbackData>())
+                .endpoint(
+                    |cq: CallbackQuery,
+                     bot: CacheMe<Throttle<Bot>>,
+                     AnnotationFullCallbackData { command }: AnnotationFullCallbackData| async move {
+                        match command {
+                            AnnotationCommand::Book { .. } => {
+                                read_full_annotation_handler(cq, bot, command, get_book_annotation)
+                                    .await
+                            }
+                            AnnotationCommand::Author { .. } => {
+                                read_full_annotation_handler(
+                                    cq,
+                                    bot,
+                                    command,
+                                    get_author_annotation,
+                                )
+                                .await
+                            }
+                            AnnotationCommand::Translator { .. } => {
+                                read_full_annotation_handler(
+                                    cq,
+                                    bot,
+                                    command,
+                                    get_translator_annotation,
+                                )
+                                .await
+                            }
+                            AnnotationCommand::Sequence { .. } => {
+                                read_full_annotation_handler(
+                                    cq,
+                                    bot,
+                                    command,
+                                    get_sequence_annotation,
+                                )
+                                .await
+                            }
                         }
                     },
                 ),
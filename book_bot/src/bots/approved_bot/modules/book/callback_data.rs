@@ -3,18 +3,79 @@ use std::{fmt::Display, str::FromStr};
 use regex::Regex;
 use std::sync::LazyLock;
 
-use crate::bots::approved_bot::modules::utils::{
-    errors::CallbackQueryParseError, pagination::GetPaginationCallbackData,
+use crate::bots::approved_bot::{
+    modules::utils::{
+        deep_link::DeepLink, errors::CallbackQueryParseError, pagination::GetPaginationCallbackData,
+    },
+    services::{book_library::BookSort, subscriptions::SubscriptionTarget},
 };
 
-static RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^b(?P<an_type>[ats])_(?P<id>\d+)_(?P<page>\d+)$").unwrap());
+static RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:b(?P<an_type>[ats])_(?P<id>\d+)_(?P<page>\d+)|bl_(?P<list_type>[ats])_(?P<list_id>\d+)_(?P<sort>[ntyp])_(?:(?P<qid>[0-9a-z]+)_)?(?P<list_page>\d+)|bf_(?P<filter_type>[ats])_(?P<filter_id>\d+)|bo_(?P<overview_id>\d+)_(?P<overview_page>\d+))$",
+    )
+    .unwrap()
+});
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BookListKind {
+    Author,
+    Translator,
+    Sequence,
+}
+
+impl BookListKind {
+    fn code(self) -> char {
+        match self {
+            BookListKind::Author => 'a',
+            BookListKind::Translator => 't',
+            BookListKind::Sequence => 's',
+        }
+    }
+
+    fn parse(code: &str) -> Result<Self, CallbackQueryParseError> {
+        match code {
+            "a" => Ok(BookListKind::Author),
+            "t" => Ok(BookListKind::Translator),
+            "s" => Ok(BookListKind::Sequence),
+            _ => Err(CallbackQueryParseError),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum BookCallbackData {
-    Author { id: u32, page: u32 },
-    Translator { id: u32, page: u32 },
-    Sequence { id: u32, page: u32 },
+    Author {
+        id: u32,
+        page: u32,
+    },
+    Translator {
+        id: u32,
+        page: u32,
+    },
+    Sequence {
+        id: u32,
+        page: u32,
+    },
+    /// The list in another order and/or narrowed to titles matching the
+    /// text stored as `qid` in `services::search_queries`.
+    Filtered {
+        kind: BookListKind,
+        id: u32,
+        sort: BookSort,
+        qid: Option<String>,
+        page: u32,
+    },
+    /// "🔎 Фильтр": asks for a part of the title.
+    FilterPrompt {
+        kind: BookListKind,
+        id: u32,
+    },
+    /// The author's books grouped by series, see `overview`.
+    Overview {
+        id: u32,
+        page: u32,
+    },
 }
 
 impl FromStr for BookCallbackData {
@@ -23,21 +84,42 @@ impl FromStr for BookCallbackData {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let caps = RE.captures(s).ok_or(CallbackQueryParseError)?;
 
-        let an_type = &caps["an_type"];
-        let id: u32 = caps["id"].parse().map_err(|_| CallbackQueryParseError)?;
-        let page: u32 = std::cmp::max(
-            1,
-            caps["page"]
-                .parse::<u32>()
-                .map_err(|_| CallbackQueryParseError)?,
-        );
+        let number = |name: &str| -> Result<u32, CallbackQueryParseError> {
+            caps[name].parse().map_err(|_| CallbackQueryParseError)
+        };
 
-        match an_type {
-            "a" => Ok(BookCallbackData::Author { id, page }),
-            "t" => Ok(BookCallbackData::Translator { id, page }),
-            "s" => Ok(BookCallbackData::Sequence { id, page }),
-            _ => Err(CallbackQueryParseError),
+        if let Some(filter_type) = caps.name("filter_type") {
+            return Ok(BookCallbackData::FilterPrompt {
+                kind: BookListKind::parse(filter_type.as_str())?,
+                id: number("filter_id")?,
+            });
+        }
+
+        if caps.name("overview_id").is_some() {
+            return Ok(BookCallbackData::Overview {
+                id: number("overview_id")?,
+                page: std::cmp::max(1, number("overview_page")?),
+            });
         }
+
+        if let Some(list_type) = caps.name("list_type") {
+            return Ok(BookCallbackData::Filtered {
+                kind: BookListKind::parse(list_type.as_str())?,
+                id: number("list_id")?,
+                sort: BookSort::from_code(&caps["sort"]).ok_or(CallbackQueryParseError)?,
+                qid: caps.name("qid").map(|qid| qid.as_str().to_string()),
+                page: std::cmp::max(1, number("list_page")?),
+            });
+        }
+
+        let id = number("id")?;
+        let page: u32 = std::cmp::max(1, number("page")?);
+
+        Ok(BookCallbackData::list(
+            BookListKind::parse(&caps["an_type"])?,
+            id,
+            page,
+        ))
     }
 }
 
@@ -47,33 +129,155 @@ impl Display for BookCallbackData {
             BookCallbackData::Author { id, page } => write!(f, "ba_{id}_{page}"),
             BookCallbackData::Translator { id, page } => write!(f, "bt_{id}_{page}"),
             BookCallbackData::Sequence { id, page } => write!(f, "bs_{id}_{page}"),
+            BookCallbackData::Filtered {
+                kind,
+                id,
+                sort,
+                qid,
+                page,
+            } => {
+                write!(f, "bl_{}_{id}_{}_", kind.code(), sort.code())?;
+
+                if let Some(qid) = qid {
+                    write!(f, "{qid}_")?;
+                }
+
+                write!(f, "{page}")
+            }
+            BookCallbackData::FilterPrompt { kind, id } => write!(f, "bf_{}_{id}", kind.code()),
+            BookCallbackData::Overview { id, page } => write!(f, "bo_{id}_{page}"),
+        }
+    }
+}
+
+impl BookCallbackData {
+    /// The unfiltered list in the default order.
+    pub fn list(kind: BookListKind, id: u32, page: u32) -> Self {
+        match kind {
+            BookListKind::Author => BookCallbackData::Author { id, page },
+            BookListKind::Translator => BookCallbackData::Translator { id, page },
+            BookListKind::Sequence => BookCallbackData::Sequence { id, page },
+        }
+    }
+
+    /// Same as `list` when neither a filter nor an order is set, so the
+    /// plain list keeps its plain callbacks.
+    pub fn filtered(
+        kind: BookListKind,
+        id: u32,
+        sort: BookSort,
+        qid: Option<String>,
+        page: u32,
+    ) -> Self {
+        if sort == BookSort::default() && qid.is_none() {
+            return BookCallbackData::list(kind, id, page);
+        }
+
+        BookCallbackData::Filtered {
+            kind,
+            id,
+            sort,
+            qid,
+            page,
+        }
+    }
+
+    pub fn kind(&self) -> BookListKind {
+        match self {
+            BookCallbackData::Author { .. } | BookCallbackData::Overview { .. } => {
+                BookListKind::Author
+            }
+            BookCallbackData::Translator { .. } => BookListKind::Translator,
+            BookCallbackData::Sequence { .. } => BookListKind::Sequence,
+            BookCallbackData::Filtered { kind, .. }
+            | BookCallbackData::FilterPrompt { kind, .. } => *kind,
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        match *self {
+            BookCallbackData::Author { id, .. }
+            | BookCallbackData::Translator { id, .. }
+            | BookCallbackData::Sequence { id, .. }
+            | BookCallbackData::Filtered { id, .. }
+            | BookCallbackData::FilterPrompt { id, .. }
+            | BookCallbackData::Overview { id, .. } => id,
+        }
+    }
+
+    pub fn page(&self) -> u32 {
+        match *self {
+            BookCallbackData::Author { page, .. }
+            | BookCallbackData::Translator { page, .. }
+            | BookCallbackData::Sequence { page, .. }
+            | BookCallbackData::Filtered { page, .. }
+            | BookCallbackData::Overview { page, .. } => page,
+            BookCallbackData::FilterPrompt { .. } => 1,
+        }
+    }
+
+    pub fn sort(&self) -> BookSort {
+        match self {
+            BookCallbackData::Filtered { sort, .. } => *sort,
+            _ => BookSort::default(),
+        }
+    }
+
+    pub fn query_id(&self) -> Option<&str> {
+        match self {
+            BookCallbackData::Filtered { qid, .. } => qid.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn deep_link(&self) -> DeepLink {
+        let id = self.id();
+
+        match self.kind() {
+            BookListKind::Author => DeepLink::Author { id },
+            BookListKind::Translator => DeepLink::Translator { id },
+            BookListKind::Sequence => DeepLink::Sequence { id },
+        }
+    }
+
+    pub fn subscription_target(&self) -> SubscriptionTarget {
+        let id = self.id();
+
+        match self.kind() {
+            BookListKind::Author => SubscriptionTarget::Author { id },
+            BookListKind::Translator => SubscriptionTarget::Translator { id },
+            BookListKind::Sequence => SubscriptionTarget::Sequence { id },
         }
     }
 }
 
 impl GetPaginationCallbackData for BookCallbackData {
     fn get_pagination_callback_data(&self, target_page: u32) -> String {
-        match self {
-            BookCallbackData::Author { id, .. } => BookCallbackData::Author {
-                id: *id,
-                page: target_page,
-            },
-            BookCallbackData::Translator { id, .. } => BookCallbackData::Translator {
-                id: *id,
+        if let BookCallbackData::Overview { id, .. } = *self {
+            return BookCallbackData::Overview {
+                id,
                 page: target_page,
-            },
-            BookCallbackData::Sequence { id, .. } => BookCallbackData::Sequence {
-                id: *id,
-                page: target_page,
-            },
+            }
+            .to_string();
         }
+
+        BookCallbackData::filtered(
+            self.kind(),
+            self.id(),
+            self.sort(),
+            self.query_id().map(str::to_string),
+            target_page,
+        )
         .to_string()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::BookCallbackData;
+    use super::{BookCallbackData, BookListKind};
+    use crate::bots::approved_bot::{
+        modules::utils::pagination::GetPaginationCallbackData, services::book_library::BookSort,
+    };
     use std::str::FromStr;
 
     #[test]
@@ -133,6 +337,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn round_trip_filtered() {
+        for data in [
+            BookCallbackData::Filtered {
+                kind: BookListKind::Author,
+                id: 12,
+                sort: BookSort::Year,
+                qid: None,
+                page: 2,
+            },
+            BookCallbackData::Filtered {
+                kind: BookListKind::Sequence,
+                id: 7,
+                sort: BookSort::Position,
+                qid: Some("123".to_string()),
+                page: 3,
+            },
+            BookCallbackData::FilterPrompt {
+                kind: BookListKind::Translator,
+                id: 5,
+            },
+            BookCallbackData::Overview { id: 9, page: 2 },
+        ] {
+            assert_eq!(BookCallbackData::from_str(&data.to_string()).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn default_view_keeps_plain_callbacks() {
+        let data = BookCallbackData::filtered(BookListKind::Author, 5, BookSort::Newest, None, 2);
+
+        assert_eq!(data.to_string(), "ba_5_2");
+
+        let data = BookCallbackData::filtered(
+            BookListKind::Author,
+            5,
+            BookSort::Title,
+            Some("k2x9a1".to_string()),
+            1,
+        );
+
+        assert_eq!(data.to_string(), "bl_a_5_t_k2x9a1_1");
+        assert_eq!(data.get_pagination_callback_data(4), "bl_a_5_t_k2x9a1_4");
+    }
+
     #[test]
     fn rejects_foreign_prefix() {
         assert!(BookCallbackData::from_str("bx_5_1").is_err());
@@ -5,6 +5,8 @@ use crate::bots::approved_bot::modules::utils::{
     errors::CommandParseError, filter_command::CommandParse,
 };
 
+use super::callback_data::BookCallbackData;
+
 static RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^/(?P<an_type>[ats])_(?P<id>\d+)$").unwrap());
 
@@ -31,6 +33,17 @@ impl CommandParse<Self> for BookCommand {
     }
 }
 
+impl BookCommand {
+    /// First page of the command's book list.
+    pub fn callback_data(&self) -> BookCallbackData {
+        match *self {
+            BookCommand::Author { id } => BookCallbackData::Author { id, page: 1 },
+            BookCommand::Translator { id } => BookCallbackData::Translator { id, page: 1 },
+            BookCommand::Sequence { id } => BookCallbackData::Sequence { id, page: 1 },
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::BookCommand;
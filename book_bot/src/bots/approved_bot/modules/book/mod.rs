@@ -1,5 +1,6 @@
 pub mod callback_data;
 pub mod commands;
+pub mod overview;
 
 use book_bot_macros::log_handler;
 
@@ -15,7 +16,7 @@ use teloxide::{
     dispatching::UpdateFilterExt,
     dptree,
     prelude::*,
-    types::ReplyParameters,
+    types::{InlineKeyboardButton, InlineKeyboardButtonKind, Me, ReplyParameters},
 };
 
 use crate::bots::approved_bot::{
@@ -24,37 +25,175 @@ use crate::bots::approved_bot::{
             formatters::{Format, FormatTitle},
             get_author_books, get_sequence_books, get_translator_books,
             types::Page,
+            BookListFilter, BookSort,
         },
-        user_settings::get_user_or_default_lang_codes,
+        search_queries::{get_search_query, save_search_query},
+        user_settings::{get_user_or_default_lang_codes, get_user_page_size},
     },
     tools::filter_callback_query,
 };
 
-use self::{callback_data::BookCallbackData, commands::BookCommand};
+use self::{
+    callback_data::{BookCallbackData, BookListKind},
+    commands::BookCommand,
+    overview::get_author_overview_page,
+};
 
+use super::genres::keyboards::get_sort_row;
+use super::selection::{callback_data::SelectionList, get_select_button};
+use super::subscriptions::get_user_follow_button;
 use super::utils::{
-    filter_command::filter_command,
+    deep_link::get_share_button,
+    filter_command::{filter_command, CommandParse},
     pagination::{generic_get_pagination_keyboard, paginate, PaginationTexts},
-    telegram_utils::{safe_send_message, safe_send_message_with_reply},
+    telegram_utils::{
+        safe_answer_callback_query, safe_answer_callback_query_with_text, safe_send_force_reply,
+        safe_send_message, safe_send_message_with_reply,
+    },
 };
 
+/// First line of the filter prompt, followed by the list's command. The
+/// reply to the prompt is matched by it, see `parse_filter_reply`.
+const FILTER_PROMPT_PREFIX: &str = "🔎 Фильтр книг ";
+const FILTER_PROMPT: &str = "Напишите в ответ на это сообщение часть названия книги.";
+const FILTER_EXPIRED: &str = "Фильтр устарел, задайте его заново";
+const FILTERED_BOOKS_NOT_FOUND: &str = "Книги с таким названием не найдены!";
+
+const BOOK_LIST_SORTS: [(BookSort, &str); 3] = [
+    (BookSort::Year, "📅 По году"),
+    (BookSort::Title, "🔤 По названию"),
+    (BookSort::Position, "🔢 По серии"),
+];
+
+type BooksGetter<Fut> = fn(
+    id: u32,
+    page: u32,
+    page_size: u32,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+    filter: BookListFilter,
+) -> Fut;
+
+/// Reply with a part of the title to the filter prompt of a list.
+#[derive(Clone)]
+struct FilterReply {
+    kind: BookListKind,
+    id: u32,
+    title: String,
+}
+
+fn callback_button(text: &str, data: BookCallbackData) -> InlineKeyboardButton {
+    InlineKeyboardButton {
+        text: text.to_string(),
+        kind: InlineKeyboardButtonKind::CallbackData(data.to_string()),
+    }
+}
+
+fn get_filter_header(title: Option<&str>) -> String {
+    match title {
+        Some(title) => format!("🔎 «{title}»\n\n"),
+        None => String::new(),
+    }
+}
+
+/// "Filter" (and "Reset filter" for a filtered list), the series overview
+/// of an author and the sort toggles; tapping the current order again
+/// returns to the default one.
+fn get_filter_rows(callback_data: &BookCallbackData) -> Vec<Vec<InlineKeyboardButton>> {
+    let (kind, id) = (callback_data.kind(), callback_data.id());
+    let current = callback_data.sort();
+    let qid = callback_data.query_id().map(str::to_string);
+
+    let mut filter_row = vec![callback_button(
+        "🔎 Фильтр",
+        BookCallbackData::FilterPrompt { kind, id },
+    )];
+
+    if qid.is_some() {
+        filter_row.push(callback_button(
+            "❌ Сбросить фильтр",
+            BookCallbackData::filtered(kind, id, current, None, 1),
+        ));
+    }
+
+    if kind == BookListKind::Author {
+        filter_row.push(callback_button(
+            "🗂 По сериям",
+            BookCallbackData::Overview { id, page: 1 },
+        ));
+    }
+
+    let sort_row = get_sort_row(&BOOK_LIST_SORTS, current, |sort| {
+        let sort = if sort == current {
+            BookSort::default()
+        } else {
+            sort
+        };
+
+        BookCallbackData::filtered(kind, id, sort, qid.clone(), 1).to_string()
+    });
+
+    vec![filter_row, sort_row]
+}
+
+/// Filter and sort rows, "Follow" and "Share" buttons shown under every
+/// page of the list, and "Select" opening the page in select mode. Select
+/// mode walks the plain list, so filtered or sorted lists go without it.
+async fn get_extra_rows(
+    bot: &CacheMe<Throttle<Bot>>,
+    user_id: UserId,
+    callback_data: &BookCallbackData,
+) -> Vec<Vec<InlineKeyboardButton>> {
+    let mut rows = get_filter_rows(callback_data);
+
+    let select_row = match callback_data {
+        BookCallbackData::Filtered { .. }
+        | BookCallbackData::FilterPrompt { .. }
+        | BookCallbackData::Overview { .. } => None,
+        _ => {
+            let id = callback_data.id();
+            let list = match callback_data.kind() {
+                BookListKind::Author => SelectionList::Author { id },
+                BookListKind::Translator => SelectionList::Translator { id },
+                BookListKind::Sequence => SelectionList::Sequence { id },
+            };
+
+            Some(vec![get_select_button(list, callback_data.page())])
+        }
+    };
+
+    if let Ok(me) = bot.get_me().await {
+        let row: Vec<InlineKeyboardButton> =
+            get_user_follow_button(bot, user_id, callback_data.subscription_target())
+                .await
+                .into_iter()
+                .chain(get_share_button(&callback_data.deep_link(), me.username()))
+                .collect();
+
+        if !row.is_empty() {
+            rows.push(row);
+        }
+    }
+
+    rows.extend(select_row);
+
+    rows
+}
+
+/// Sends the first page of the list as a reply to `message`.
 #[log_handler("book")]
 async fn send_book_handler<T, P, Fut>(
     message: Message,
     bot: CacheMe<Throttle<Bot>>,
-    command: BookCommand,
-    books_getter: fn(id: u32, page: u32, allowed_langs: SmallVec<[SmartString; 3]>) -> Fut,
+    callback_data: BookCallbackData,
+    filter: BookListFilter,
+    books_getter: BooksGetter<Fut>,
 ) -> crate::bots::BotHandlerInternal
 where
     T: Format + Clone + Debug,
     P: FormatTitle + Clone + Debug,
     Fut: std::future::Future<Output = anyhow::Result<Option<Page<T, P>>>>,
 {
-    let id = match command {
-        BookCommand::Author { id } => id,
-        BookCommand::Translator { id } => id,
-        BookCommand::Sequence { id } => id,
-    };
+    let id = callback_data.id();
 
     let chat_id = message.chat.id;
     let user_id = match message.from.map(|from| from.id) {
@@ -72,8 +211,15 @@ where
     };
 
     let allowed_langs = get_user_or_default_lang_codes(user_id).await;
+    let page_size = get_user_page_size(user_id).await;
+    let header = get_filter_header(filter.title.as_deref());
+    let books_not_found = if filter.title.is_some() {
+        FILTERED_BOOKS_NOT_FOUND
+    } else {
+        BOOKS_NOT_FOUND
+    };
 
-    let items_page = match books_getter(id, 1, allowed_langs).await {
+    let items_page = match books_getter(id, 1, page_size, allowed_langs, filter).await {
         Ok(Some(v)) => v,
         Ok(None) => {
             safe_send_message_with_reply(
@@ -103,7 +249,7 @@ where
         safe_send_message_with_reply(
             &bot,
             chat_id,
-            BOOKS_NOT_FOUND,
+            books_not_found,
             ReplyParameters::new(message.id),
             None,
         )
@@ -111,20 +257,18 @@ where
         return Ok(());
     };
 
-    let formatted_page = items_page.format(1, TELEGRAM_MESSAGE_MAX_LENGTH);
-
-    let callback_data = match command {
-        BookCommand::Author { id } => BookCallbackData::Author { id, page: 1 },
-        BookCommand::Translator { id } => BookCallbackData::Translator { id, page: 1 },
-        BookCommand::Sequence { id } => BookCallbackData::Sequence { id, page: 1 },
-    };
+    let formatted_page =
+        items_page.format(1, TELEGRAM_MESSAGE_MAX_LENGTH.saturating_sub(header.len()));
 
-    let keyboard = generic_get_pagination_keyboard(1, items_page.pages, callback_data, true);
+    let mut keyboard = generic_get_pagination_keyboard(1, items_page.pages, callback_data.clone());
+    keyboard
+        .inline_keyboard
+        .extend(get_extra_rows(&bot, user_id, &callback_data).await);
 
     safe_send_message_with_reply(
         &bot,
         chat_id,
-        formatted_page,
+        format!("{header}{formatted_page}"),
         ReplyParameters::new(message.id),
         Some(keyboard),
     )
@@ -138,18 +282,14 @@ async fn send_pagination_book_handler<T, P, Fut>(
     cq: CallbackQuery,
     bot: CacheMe<Throttle<Bot>>,
     callback_data: BookCallbackData,
-    books_getter: fn(id: u32, page: u32, allowed_langs: SmallVec<[SmartString; 3]>) -> Fut,
+    books_getter: BooksGetter<Fut>,
 ) -> crate::bots::BotHandlerInternal
 where
     T: Format + Clone + Debug,
     P: FormatTitle + Clone + Debug,
     Fut: std::future::Future<Output = anyhow::Result<Option<Page<T, P>>>>,
 {
-    let (id, page) = match callback_data {
-        BookCallbackData::Author { id, page } => (id, page),
-        BookCallbackData::Translator { id, page } => (id, page),
-        BookCallbackData::Sequence { id, page } => (id, page),
-    };
+    let (id, page) = (callback_data.id(), callback_data.page());
 
     let chat_id = cq.message.as_ref().map(|message| message.chat().id);
     let user_id = cq.from.id;
@@ -166,7 +306,64 @@ where
         }
     };
 
+    let title = match callback_data.query_id() {
+        Some(qid) => match get_search_query(qid, user_id.0).await {
+            Some(title) => Some(title),
+            None => {
+                return safe_answer_callback_query_with_text(&bot, cq.id, FILTER_EXPIRED, true)
+                    .await;
+            }
+        },
+        None => None,
+    };
+
+    let header = get_filter_header(title.as_deref());
+    let books_not_found = if title.is_some() {
+        FILTERED_BOOKS_NOT_FOUND
+    } else {
+        BOOKS_NOT_FOUND
+    };
+    let filter = BookListFilter {
+        title,
+        sort: callback_data.sort(),
+    };
+
     let allowed_langs = get_user_or_default_lang_codes(user_id).await;
+    let page_size = get_user_page_size(user_id).await;
+    let extra_rows = get_extra_rows(&bot, user_id, &callback_data).await;
+
+    paginate(
+        &bot,
+        chat_id,
+        message_id,
+        cq.message,
+        page,
+        &header,
+        |p| books_getter(id, p, page_size, allowed_langs.clone(), filter.clone()),
+        callback_data,
+        move |_| extra_rows,
+        PaginationTexts {
+            not_found: NOT_FOUND,
+            no_items: books_not_found,
+            error_try_later: Some(ERROR_TRY_LATER),
+        },
+    )
+    .await
+}
+
+#[log_handler("book")]
+async fn overview_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    callback_data: BookCallbackData,
+) -> crate::bots::BotHandlerInternal {
+    let Some(message) = cq.message.as_ref() else {
+        return Ok(());
+    };
+    let (chat_id, message_id) = (message.chat().id, message.id());
+    let (id, page) = (callback_data.id(), callback_data.page());
+
+    let allowed_langs = get_user_or_default_lang_codes(cq.from.id).await;
 
     paginate(
         &bot,
@@ -175,8 +372,14 @@ where
         cq.message,
         page,
         "",
-        |p| books_getter(id, p, allowed_langs.clone()),
+        |p| get_author_overview_page(id, p, allowed_langs.clone()),
         callback_data,
+        |_| {
+            vec![vec![callback_button(
+                "📃 Все книги",
+                BookCallbackData::Author { id, page: 1 },
+            )]]
+        },
         PaginationTexts {
             not_found: NOT_FOUND,
             no_items: BOOKS_NOT_FOUND,
@@ -186,37 +389,167 @@ where
     .await
 }
 
+async fn filter_prompt_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    kind: BookListKind,
+    id: u32,
+) -> crate::bots::BotHandlerInternal {
+    let Some(message) = cq.message.as_ref() else {
+        return Ok(());
+    };
+    let chat_id = message.chat().id;
+
+    safe_answer_callback_query(&bot, cq.id.clone()).await?;
+
+    let command = match kind {
+        BookListKind::Author => "a",
+        BookListKind::Translator => "t",
+        BookListKind::Sequence => "s",
+    };
+
+    safe_send_force_reply(
+        &bot,
+        chat_id,
+        format!("{FILTER_PROMPT_PREFIX}/{command}_{id}\n\n{FILTER_PROMPT}"),
+    )
+    .await
+}
+
+/// Text of a reply to the bot's own filter prompt.
+fn parse_filter_reply(message: &Message, me: &Me) -> Option<FilterReply> {
+    let prompt = message.reply_to_message()?;
+
+    if prompt.from.as_ref().map(|user| user.id) != Some(me.user.id) {
+        return None;
+    }
+
+    let command = prompt
+        .text()?
+        .lines()
+        .next()?
+        .strip_prefix(FILTER_PROMPT_PREFIX)?;
+    let title = message.text()?.trim();
+
+    if title.is_empty() {
+        return None;
+    }
+
+    let list = BookCommand::parse(command).ok()?.callback_data();
+
+    Some(FilterReply {
+        kind: list.kind(),
+        id: list.id(),
+        title: title.to_string(),
+    })
+}
+
+async fn filter_reply_handler(
+    message: Message,
+    bot: CacheMe<Throttle<Bot>>,
+    reply: FilterReply,
+) -> crate::bots::BotHandlerInternal {
+    let Some(user) = message.from.as_ref() else {
+        return Ok(());
+    };
+
+    let qid = save_search_query(user.id.0, &reply.title).await;
+    let callback_data =
+        BookCallbackData::filtered(reply.kind, reply.id, BookSort::default(), Some(qid), 1);
+    let filter = BookListFilter {
+        title: Some(reply.title),
+        sort: BookSort::default(),
+    };
+
+    match reply.kind {
+        BookListKind::Author => {
+            send_book_handler(message, bot, callback_data, filter, get_author_books).await
+        }
+        BookListKind::Translator => {
+            send_book_handler(message, bot, callback_data, filter, get_translator_books).await
+        }
+        BookListKind::Sequence => {
+            send_book_handler(message, bot, callback_data, filter, get_sequence_books).await
+        }
+    }
+}
+
+pub async fn book_command_handler(
+    message: Message,
+    bot: CacheMe<Throttle<Bot>>,
+    command: BookCommand,
+) -> crate::bots::BotHandlerInternal {
+    let callback_data = command.callback_data();
+    let filter = BookListFilter::default();
+
+    match command {
+        BookCommand::Author { .. } => {
+            send_book_handler(message, bot, callback_data, filter, get_author_books).await
+        }
+        BookCommand::Translator { .. } => {
+            send_book_handler(message, bot, callback_data, filter, get_translator_books).await
+        }
+        BookCommand::Sequence { .. } => {
+            send_book_handler(message, bot, callback_data, filter, get_sequence_books).await
+        }
+    }
+}
+
 pub fn get_book_handler() -> crate::bots::BotHandler {
     dptree::entry()
         .branch(
             Update::filter_message()
                 .chain(filter_command::<BookCommand>())
+                .endpoint(book_command_handler),
+        )
+        .branch(
+            Update::filter_message()
+                .chain(dptree::filter_map(|message: Message, me: Me| {
+                    parse_filter_reply(&message, &me)
+                }))
+                .endpoint(filter_reply_handler),
+        )
+        .branch(
+            Update::filter_callback_query()
+                .chain(filter_callback_query::<BookCallbackData>())
                 .endpoint(
-                    |message: Message, bot: CacheMe<Throttle<Bot>>, command: BookCommand| async move {
-                        match command {
-                            BookCommand::Author { .. } => {
-                                send_book_handler(
-                                    message,
+                    |cq: CallbackQuery,
+                     bot: CacheMe<Throttle<Bot>>,
+                     callback_data: BookCallbackData| async move {
+                        match callback_data {
+                            BookCallbackData::FilterPrompt { kind, id } => {
+                                return filter_prompt_handler(cq, bot, kind, id).await;
+                            }
+                            BookCallbackData::Overview { .. } => {
+                                return overview_handler(cq, bot, callback_data).await;
+                            }
+                            _ => {}
+                        }
+
+                        match callback_data.kind() {
+                            BookListKind::Author => {
+                                send_pagination_book_handler(
+                                    cq,
                                     bot,
-                                    command,
+                                    callback_data,
                                     get_author_books,
                                 )
                                 .await
                             }
-                            BookCommand::Translator { .. } => {
-                                send_book_handler(
-                                    message,
+                            BookListKind::Translator => {
+                                send_pagination_book_handler(
+                                    cq,
                                     bot,
-                                    command,
+                                    callback_data,
                                     get_translator_books,
                                 )
                                 .await
                             }
-                            BookCommand::Sequence { .. } => {
-                                send_book_handler(
-                                    message,
+                            BookListKind::Sequence => {
+                                send_pagination_book_handler(
+                                    cq,
                                     bot,
-                                    command,
+                                    callback_data,
                                     get_sequence_books,
                                 )
                                 .await
@@ -225,15 +558,55 @@ pub fn get_book_handler() -> crate::bots::BotHandler {
                     },
                 ),
         )
-        .branch(
-            Update::filter_callback_query()
-                .chain(filter_callback_query::<BookCallbackData>())
-                .endpoint(|cq: CallbackQuery, bot: CacheMe<Throttle<Bot>>, callback_data: BookCallbackData| async move {
-                    match callback_data {
-                        BookCallbackData::Author { .. } => send_pagination_book_handler(cq, bot, callback_data, get_author_books).await,
-                        BookCallbackData::Translator { .. } => send_pagination_book_handler(cq, bot, callback_data,  get_translator_books).await,
-                        BookCallbackData::Sequence { .. } => send_pagination_book_handler(cq, bot, callback_data,  get_sequence_books).await,
-                    }
-                }),
-        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(rows: &[Vec<InlineKeyboardButton>]) -> Vec<Vec<&str>> {
+        rows.iter()
+            .map(|row| row.iter().map(|button| button.text.as_str()).collect())
+            .collect()
+    }
+
+    fn data(button: &InlineKeyboardButton) -> &str {
+        match &button.kind {
+            InlineKeyboardButtonKind::CallbackData(data) => data,
+            _ => panic!("not a callback button"),
+        }
+    }
+
+    #[test]
+    fn plain_list_filter_rows() {
+        let rows = get_filter_rows(&BookCallbackData::Author { id: 5, page: 3 });
+
+        assert_eq!(
+            texts(&rows),
+            [
+                vec!["🔎 Фильтр", "🗂 По сериям"],
+                vec!["📅 По году", "🔤 По названию", "🔢 По серии"]
+            ]
+        );
+        assert_eq!(data(&rows[0][0]), "bf_a_5");
+        assert_eq!(data(&rows[0][1]), "bo_5_1");
+        assert_eq!(data(&rows[1][0]), "bl_a_5_y_1");
+    }
+
+    #[test]
+    fn filtered_list_can_be_reset_and_sort_toggled_off() {
+        let rows = get_filter_rows(&BookCallbackData::Filtered {
+            kind: BookListKind::Sequence,
+            id: 7,
+            sort: BookSort::Title,
+            qid: Some("k2x9a1".to_string()),
+            page: 2,
+        });
+
+        assert_eq!(texts(&rows)[0], ["🔎 Фильтр", "❌ Сбросить фильтр"]);
+        assert_eq!(data(&rows[0][1]), "bl_s_7_t_1");
+        assert_eq!(rows[1][1].text, "🔤 По названию ✓");
+        assert_eq!(data(&rows[1][1]), "bl_s_7_n_k2x9a1_1");
+        assert_eq!(data(&rows[1][2]), "bl_s_7_p_k2x9a1_1");
+    }
 }
@@ -0,0 +1,340 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use moka::future::Cache;
+use smallvec::SmallVec;
+use smartstring::alias::String as SmartString;
+
+use crate::bots::approved_bot::{
+    modules::{
+        book_card::commands::BookCardCommand, download::commands::DownloadArchiveCommand,
+        utils::constants::TELEGRAM_MESSAGE_MAX_LENGTH,
+    },
+    services::book_library::{
+        formatters::{Format, FormatResult, FormatTitle},
+        get_all_author_books,
+        types::{AuthorBook, Page, Person},
+    },
+};
+
+const SECTIONS_SEPARATOR: &str = "\n\n";
+/// Room left on every page for the parent title and the page footer.
+const RESERVED_SIZE: usize = 64;
+
+/// An author's books of one series, in series order.
+#[derive(Debug)]
+struct SeriesGroup {
+    id: u32,
+    name: String,
+    books: Vec<(Option<i32>, AuthorBook)>,
+}
+
+/// Series first, by name, then the books outside of any series. A book of
+/// several series is listed in each of them.
+fn group_by_series(books: Vec<AuthorBook>) -> (Vec<SeriesGroup>, Vec<AuthorBook>) {
+    let mut series: HashMap<u32, SeriesGroup> = HashMap::new();
+    let mut standalone = vec![];
+
+    for book in books {
+        if book.sequences.is_empty() {
+            standalone.push(book);
+            continue;
+        }
+
+        for sequence in &book.sequences {
+            series
+                .entry(sequence.id)
+                .or_insert_with(|| SeriesGroup {
+                    id: sequence.id,
+                    name: sequence.name.clone(),
+                    books: vec![],
+                })
+                .books
+                .push((sequence.position, book.clone()));
+        }
+    }
+
+    let mut series: Vec<SeriesGroup> = series.into_values().collect();
+
+    series.sort_by_key(|group| group.name.to_lowercase());
+    for group in series.iter_mut() {
+        // Books without a known position go last, oldest first.
+        group.books.sort_by_key(|(position, book)| {
+            (position.is_none(), position.unwrap_or_default(), book.year)
+        });
+    }
+
+    standalone.sort_by_key(|book| book.year);
+
+    (series, standalone)
+}
+
+fn format_book_line(prefix: &str, book: &AuthorBook) -> String {
+    let year = match book.year {
+        0 => String::new(),
+        year => format!(" ({year})"),
+    };
+
+    format!(
+        "{prefix} {}{year} {}",
+        book.title,
+        BookCardCommand { id: book.id }
+    )
+}
+
+fn format_series(group: &SeriesGroup) -> String {
+    let header = format!(
+        "📚 {} ({})\nКниги: /s_{} · Архив: {}",
+        group.name,
+        group.books.len(),
+        group.id,
+        DownloadArchiveCommand::Sequence { id: group.id }
+    );
+
+    let books = group.books.iter().map(|(position, book)| {
+        let prefix = match position {
+            Some(position) => format!("{position}."),
+            None => "•".to_string(),
+        };
+
+        format_book_line(&prefix, book)
+    });
+
+    std::iter::once(header)
+        .chain(books)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn format_standalone(books: &[AuthorBook]) -> String {
+    std::iter::once(format!("📕 Вне серий ({})", books.len()))
+        .chain(books.iter().map(|book| format_book_line("•", book)))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Packs the sections into pages of at most `max_size` bytes. A section
+/// that does not fit on a page of its own is split between lines.
+fn pack_sections(sections: &[String], max_size: usize) -> Vec<String> {
+    let mut pages: Vec<String> = vec![];
+    let mut current = String::new();
+
+    let mut push = |current: &mut String, part: &str, separator: &str| {
+        if !current.is_empty() && current.len() + separator.len() + part.len() > max_size {
+            pages.push(std::mem::take(current));
+        }
+
+        if !current.is_empty() {
+            current.push_str(separator);
+        }
+
+        current.push_str(part);
+    };
+
+    for section in sections {
+        if section.len() + SECTIONS_SEPARATOR.len() <= max_size {
+            push(&mut current, section, SECTIONS_SEPARATOR);
+            continue;
+        }
+
+        for (index, line) in section.lines().enumerate() {
+            let separator = if index == 0 { SECTIONS_SEPARATOR } else { "\n" };
+            push(&mut current, line, separator);
+        }
+    }
+
+    if !current.is_empty() {
+        pages.push(current);
+    }
+
+    pages
+}
+
+/// One page of the overview, packed to fit a message.
+#[derive(Clone, Debug)]
+pub struct OverviewPage(String);
+
+impl Format for OverviewPage {
+    fn format(&self, _max_size: usize) -> FormatResult {
+        FormatResult {
+            result: self.0.clone(),
+            current_size: self.0.len(),
+            max_size: self.0.len(),
+        }
+    }
+}
+
+/// An author's overview packed into pages, shared by all of its pages.
+struct AuthorOverview {
+    pages: Vec<String>,
+    total: u32,
+    parent_item: Option<Person>,
+}
+
+/// An author id and the languages the overview was built for.
+type AuthorOverviewKey = (u32, SmallVec<[SmartString; 3]>);
+
+/// Paging through an overview would otherwise fetch and group all of the
+/// author's books again for every page.
+static AUTHOR_OVERVIEWS: LazyLock<Cache<AuthorOverviewKey, Option<Arc<AuthorOverview>>>> =
+    LazyLock::new(|| {
+        Cache::builder()
+            .time_to_live(Duration::from_secs(5 * 60))
+            .max_capacity(1024)
+            .build()
+    });
+
+async fn build_author_overview(
+    id: u32,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+) -> anyhow::Result<Option<Arc<AuthorOverview>>> {
+    let Some(books) = get_all_author_books(id, allowed_langs).await? else {
+        return Ok(None);
+    };
+
+    let total = books.items.len() as u32;
+    let title_size = books
+        .parent_item
+        .as_ref()
+        .map(|author| author.format_title().len())
+        .unwrap_or_default();
+
+    let (series, standalone) = group_by_series(books.items);
+
+    let mut sections: Vec<String> = series.iter().map(format_series).collect();
+    if !standalone.is_empty() {
+        sections.push(format_standalone(&standalone));
+    }
+
+    let pages = pack_sections(
+        &sections,
+        TELEGRAM_MESSAGE_MAX_LENGTH.saturating_sub(title_size + RESERVED_SIZE),
+    );
+
+    Ok(Some(Arc::new(AuthorOverview {
+        pages,
+        total,
+        parent_item: books.parent_item,
+    })))
+}
+
+/// The author's books grouped by series, paged by message size rather than
+/// by book count.
+pub async fn get_author_overview_page(
+    id: u32,
+    page: u32,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+) -> anyhow::Result<Option<Page<OverviewPage, Person>>> {
+    let overview = AUTHOR_OVERVIEWS
+        .try_get_with(
+            (id, allowed_langs.clone()),
+            build_author_overview(id, allowed_langs),
+        )
+        .await
+        .map_err(|err| anyhow::anyhow!("{err:?}"))?;
+
+    let Some(overview) = overview else {
+        return Ok(None);
+    };
+
+    Ok(Some(Page {
+        items: overview
+            .pages
+            .get(page.saturating_sub(1) as usize)
+            .cloned()
+            .map(OverviewPage)
+            .into_iter()
+            .collect(),
+        total: overview.total,
+        pages: overview.pages.len() as u32,
+        parent_item: overview.parent_item.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::approved_bot::services::book_library::types::Sequence;
+
+    fn book(
+        id: u32,
+        title: &str,
+        year: i32,
+        sequences: Vec<(u32, &str, Option<i32>)>,
+    ) -> AuthorBook {
+        AuthorBook {
+            id,
+            title: title.to_string(),
+            lang: "ru".to_string(),
+            annotation_exists: false,
+            translators: vec![],
+            sequences: sequences
+                .into_iter()
+                .map(|(id, name, position)| Sequence {
+                    id,
+                    name: name.to_string(),
+                    position,
+                    ..Default::default()
+                })
+                .collect(),
+            year,
+        }
+    }
+
+    #[test]
+    fn groups_books_by_series_in_order() {
+        let (series, standalone) = group_by_series(vec![
+            book(1, "Рассказ", 1990, vec![]),
+            book(2, "Вторая", 2001, vec![(10, "Цикл", Some(2))]),
+            book(
+                3,
+                "Первая",
+                2000,
+                vec![(10, "Цикл", Some(1)), (20, "Антология", None)],
+            ),
+            book(4, "Ранний рассказ", 1980, vec![]),
+        ]);
+
+        let names: Vec<&str> = series.iter().map(|group| group.name.as_str()).collect();
+        assert_eq!(names, ["Антология", "Цикл"]);
+
+        let cycle: Vec<u32> = series[1].books.iter().map(|(_, book)| book.id).collect();
+        assert_eq!(cycle, [3, 2]);
+
+        let standalone: Vec<u32> = standalone.iter().map(|book| book.id).collect();
+        assert_eq!(standalone, [4, 1]);
+    }
+
+    #[test]
+    fn series_section_links_listing_and_archive() {
+        let (series, _) =
+            group_by_series(vec![book(3, "Первая", 2000, vec![(10, "Цикл", Some(1))])]);
+
+        assert_eq!(
+            format_series(&series[0]),
+            "📚 Цикл (1)\nКниги: /s_10 · Архив: /da_s_10\n1. Первая (2000) /b_3"
+        );
+    }
+
+    #[test]
+    fn sections_are_packed_into_pages() {
+        let sections = vec!["a".repeat(10), "b".repeat(10), "c".repeat(10)];
+
+        assert_eq!(
+            pack_sections(&sections, 22),
+            [
+                format!("{}\n\n{}", "a".repeat(10), "b".repeat(10)),
+                "c".repeat(10)
+            ]
+        );
+
+        let long_section = vec![format!("{}\n{}", "a".repeat(10), "b".repeat(10))];
+        assert_eq!(
+            pack_sections(&long_section, 12),
+            ["a".repeat(10), "b".repeat(10)]
+        );
+    }
+}
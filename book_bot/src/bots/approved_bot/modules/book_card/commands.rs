@@ -0,0 +1,56 @@
+use std::fmt::Display;
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::bots::approved_bot::modules::utils::{
+    errors::CommandParseError, filter_command::CommandParse,
+};
+
+static RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^/b_(?P<book_id>\d+)$").unwrap());
+
+#[derive(Clone)]
+pub struct BookCardCommand {
+    pub id: u32,
+}
+
+impl Display for BookCardCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "/b_{}", self.id)
+    }
+}
+
+impl CommandParse<Self> for BookCardCommand {
+    fn parse(s: &str) -> Result<Self, CommandParseError> {
+        let caps = RE.captures(s).ok_or(CommandParseError)?;
+
+        let id: u32 = caps["book_id"].parse().map_err(|_| CommandParseError)?;
+
+        Ok(BookCardCommand { id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BookCardCommand;
+    use crate::bots::approved_bot::modules::utils::filter_command::CommandParse;
+
+    #[test]
+    fn round_trip() {
+        let command = BookCardCommand { id: 123 };
+        assert_eq!(
+            BookCardCommand::parse(&command.to_string()).unwrap().id,
+            123
+        );
+    }
+
+    #[test]
+    fn does_not_match_annotation_command() {
+        assert!(BookCardCommand::parse("/b_an_123").is_err());
+    }
+
+    #[test]
+    fn rejects_non_numeric_id() {
+        assert!(BookCardCommand::parse("/b_abc").is_err());
+    }
+}
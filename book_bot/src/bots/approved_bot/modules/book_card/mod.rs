@@ -0,0 +1,171 @@
+pub mod commands;
+
+use book_bot_macros::log_handler;
+
+use teloxide::{
+    adaptors::{CacheMe, Throttle},
+    dispatching::UpdateFilterExt,
+    dptree,
+    prelude::*,
+    types::ReplyParameters,
+};
+
+use crate::bots::{
+    approved_bot::services::{
+        book_library::{get_book, get_book_annotation},
+        images::covers::{book_cover_key, BOOK_COVERS},
+    },
+    BotHandlerInternal,
+};
+
+use self::commands::BookCardCommand;
+
+use super::{
+    annotations::{
+        formatter::AnnotationFormat, html::annotation_plain_text, send_annotation_photo,
+        send_stored_photo,
+    },
+    download::keyboards::get_download_format_keyboard,
+    shelf::get_user_shelf_toggle_button,
+    utils::{
+        constants::{ERROR_TRY_LATER, NOT_FOUND},
+        filter_command::filter_command,
+        telegram_utils::safe_send_message_with_reply,
+    },
+};
+
+const ANNOTATION_EXCERPT_LENGTH: usize = 400;
+
+/// First `max_chars` characters of the annotation, cut on a word boundary.
+fn annotation_excerpt(text: &str, max_chars: usize) -> Option<String> {
+    let text = text.trim();
+
+    if text.is_empty() {
+        return None;
+    }
+
+    if text.chars().count() <= max_chars {
+        return Some(text.to_string());
+    }
+
+    let cut: String = text.chars().take(max_chars).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(pos) => &cut[..pos],
+        None => cut.as_str(),
+    };
+
+    Some(format!("{}…", cut.trim_end()))
+}
+
+#[log_handler("book_card")]
+pub async fn book_card_handler(
+    message: Message,
+    bot: CacheMe<Throttle<Bot>>,
+    command: BookCardCommand,
+) -> BotHandlerInternal {
+    let book = match get_book(command.id).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            return safe_send_message_with_reply(
+                &bot,
+                message.chat.id,
+                NOT_FOUND,
+                ReplyParameters::new(message.id),
+                None,
+            )
+            .await;
+        }
+        Err(err) => {
+            safe_send_message_with_reply(
+                &bot,
+                message.chat.id,
+                ERROR_TRY_LATER,
+                ReplyParameters::new(message.id),
+                None,
+            )
+            .await?;
+            return Err(err);
+        }
+    };
+
+    // The annotation is optional decoration for the card, so lookup
+    // failures only cost us the excerpt and the cover.
+    let annotation = match book.annotation_exists {
+        true => get_book_annotation(book.id).await.ok().flatten(),
+        false => None,
+    };
+
+    match annotation.as_ref().and_then(|a| a.get_file()) {
+        Some(file) => send_annotation_photo(&bot, message.chat.id, file).await?,
+        // Without a library cover, fall back to one taken from the book
+        // when it was downloaded.
+        None => {
+            send_stored_photo(&bot, message.chat.id, &book_cover_key(book.id), || {
+                BOOK_COVERS.get(&book.id)
+            })
+            .await?
+        }
+    }
+
+    let excerpt = annotation
+        .as_ref()
+        .filter(|a| a.is_normal_text())
+        .and_then(|a| {
+            annotation_excerpt(
+                &annotation_plain_text(a.get_text()),
+                ANNOTATION_EXCERPT_LENGTH,
+            )
+        });
+
+    let me = bot.get_me().await.ok();
+    let mut keyboard = get_download_format_keyboard(&book, me.as_ref().map(|me| me.username()));
+
+    if let Some(user) = message.from.as_ref() {
+        keyboard
+            .inline_keyboard
+            .push(vec![get_user_shelf_toggle_button(user.id, book.id).await]);
+    }
+
+    safe_send_message_with_reply(
+        &bot,
+        message.chat.id,
+        book.format_card(excerpt.as_deref()),
+        ReplyParameters::new(message.id),
+        Some(keyboard),
+    )
+    .await
+}
+
+pub fn get_book_card_handler() -> crate::bots::BotHandler {
+    dptree::entry().branch(
+        Update::filter_message()
+            .chain(filter_command::<BookCardCommand>())
+            .endpoint(book_card_handler),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::annotation_excerpt;
+
+    #[test]
+    fn short_text_is_kept() {
+        assert_eq!(
+            annotation_excerpt("  Коротко. ", 10).as_deref(),
+            Some("Коротко.")
+        );
+    }
+
+    #[test]
+    fn long_text_is_cut_on_word_boundary() {
+        assert_eq!(
+            annotation_excerpt("Война и мир", 8).as_deref(),
+            Some("Война и…")
+        );
+    }
+
+    #[test]
+    fn empty_text_is_none() {
+        assert_eq!(annotation_excerpt(" \n ", 10), None);
+    }
+}
@@ -2,6 +2,8 @@ use std::time::Duration;
 
 use book_bot_macros::log_handler;
 use chrono::Utc;
+use smallvec::SmallVec;
+use smartstring::alias::String as SmartString;
 use teloxide::{
     adaptors::{CacheMe, Throttle},
     prelude::*,
@@ -24,6 +26,9 @@ use crate::{
                     create_task, get_task, CreateTaskData, Task, TaskObjectType, TaskStatus,
                 },
                 book_cache::download_file_by_link,
+                book_library::{
+                    get_all_author_books, get_all_sequence_books, get_all_translator_books,
+                },
                 build_url,
                 user_settings::{
                     get_user_file_name_lang_for, get_user_or_default_lang_codes, FileNameLang,
@@ -36,7 +41,8 @@ use crate::{
 };
 
 use super::{
-    callback_data::DownloadArchiveQueryData, file_send::_send_downloaded_file,
+    callback_data::DownloadArchiveQueryData,
+    file_send::{_send_downloaded_file, record_downloads},
     keyboards::get_check_keyboard,
 };
 
@@ -87,13 +93,25 @@ pub async fn wait_archive(
     task_id: String,
     input_message: MaybeInaccessibleMessage,
 ) -> BotHandlerInternal {
+    deliver_archive(bot, task_id, input_message).await?;
+
+    Ok(())
+}
+
+/// Polls the task until it finishes and sends the archive or a link to it.
+/// `true` once the user has got the archive either way.
+async fn deliver_archive(
+    bot: CacheMe<Throttle<Bot>>,
+    task_id: String,
+    input_message: MaybeInaccessibleMessage,
+) -> anyhow::Result<bool> {
     let mut interval = time::interval(Duration::from_secs(15));
 
     let message = match input_message {
         MaybeInaccessibleMessage::Regular(message) => message,
         _ => {
             send_error_message(&bot, input_message.chat().id, input_message.id()).await;
-            return Ok(());
+            return Ok(false);
         }
     };
 
@@ -155,22 +173,22 @@ pub async fn wait_archive(
             log::error!("Task {} failed: {:?}", task.id, task.error_message);
             send_error_message(&bot, message.chat.id, message.id).await;
         }
-        return Ok(());
+        return Ok(false);
     }
 
     if task.status != TaskStatus::Complete {
         send_error_message(&bot, message.chat.id, message.id).await;
-        return Ok(());
+        return Ok(false);
     }
 
     let Some(content_size) = task.content_size else {
         send_archive_link(&bot, message.chat.id, message.id, &task).await?;
-        return Ok(());
+        return Ok(true);
     };
 
     if content_size > 1024 * 1024 * 1024 {
         send_archive_link(&bot, message.chat.id, message.id, &task).await?;
-        return Ok(());
+        return Ok(true);
     }
 
     let link = build_url(
@@ -189,7 +207,7 @@ pub async fn wait_archive(
             Some(v) => v,
             None => {
                 send_error_message(&bot, message.chat.id, message.id).await;
-                return Ok(());
+                return Ok(false);
             }
         },
         Err(err) => {
@@ -203,6 +221,7 @@ pub async fn wait_archive(
         &MaybeInaccessibleMessage::Regular(message.clone()),
         &bot,
         downloaded_data,
+        None,
     )
     .await
     {
@@ -215,7 +234,7 @@ pub async fn wait_archive(
 
     let _ = safe_delete_message(&bot, message.chat.id, message.id).await;
 
-    Ok(())
+    Ok(true)
 }
 
 #[log_handler("download")]
@@ -224,17 +243,15 @@ pub async fn download_archive(
     download_archive_query_data: DownloadArchiveQueryData,
     bot: CacheMe<Throttle<Bot>>,
 ) -> BotHandlerInternal {
-    let allowed_langs = get_user_or_default_lang_codes(cq.from.id).await;
-
-    let (id, file_type, task_type) = match download_archive_query_data {
+    let (object, file_type) = match download_archive_query_data {
         DownloadArchiveQueryData::Sequence { id, file_type } => {
-            (id, file_type, TaskObjectType::Sequence)
+            (TaskObjectType::Sequence { id }, file_type)
         }
         DownloadArchiveQueryData::Author { id, file_type } => {
-            (id, file_type, TaskObjectType::Author)
+            (TaskObjectType::Author { id }, file_type)
         }
         DownloadArchiveQueryData::Translator { id, file_type } => {
-            (id, file_type, TaskObjectType::Translator)
+            (TaskObjectType::Translator { id }, file_type)
         }
     };
 
@@ -242,7 +259,20 @@ pub async fn download_archive(
         return Ok(());
     };
 
-    let user_id = cq.from.id.0;
+    start_archive_task(bot, message, cq.from.id, object, file_type).await
+}
+
+/// Creates a batch-downloader task for `object` and tracks it in
+/// `message` until the archive is sent.
+pub async fn start_archive_task(
+    bot: CacheMe<Throttle<Bot>>,
+    message: MaybeInaccessibleMessage,
+    user_id: UserId,
+    object: TaskObjectType,
+    file_type: String,
+) -> BotHandlerInternal {
+    let allowed_langs = get_user_or_default_lang_codes(user_id).await;
+    let user_id = user_id.0;
 
     // `normalized` mirrors the cache server's `?normalized=` parameter.
     // Default for the server is `true` (transliterated names); we send
@@ -254,10 +284,9 @@ pub async fn download_archive(
 
     let task = create_task(
         CreateTaskData {
-            object_id: id,
-            object_type: task_type,
-            file_format: file_type,
-            allowed_langs,
+            object: object.clone(),
+            file_format: file_type.clone(),
+            allowed_langs: allowed_langs.clone(),
             normalized,
         },
         Some(user_id),
@@ -282,9 +311,35 @@ pub async fn download_archive(
     )
     .await?;
 
-    if let Err(err) = wait_archive(bot, task.id, message).await {
-        log::error!("{err:?}");
+    match deliver_archive(bot, task.id, message).await {
+        Ok(true) => match archive_book_ids(object, allowed_langs).await {
+            Ok(book_ids) => record_downloads(user_id, &book_ids, &file_type).await,
+            Err(err) => log::warn!("Failed to record download: {err:?}"),
+        },
+        Ok(false) => (),
+        Err(err) => log::error!("{err:?}"),
     }
 
     Ok(())
 }
+
+/// Books that went into the archive of `object`, for `/history`.
+async fn archive_book_ids(
+    object: TaskObjectType,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+) -> anyhow::Result<Vec<u32>> {
+    let book_ids = match object {
+        TaskObjectType::Sequence { id } => get_all_sequence_books(id, allowed_langs)
+            .await?
+            .map(|page| page.items.iter().map(|book| book.id).collect()),
+        TaskObjectType::Author { id } => get_all_author_books(id, allowed_langs)
+            .await?
+            .map(|page| page.items.iter().map(|book| book.id).collect()),
+        TaskObjectType::Translator { id } => get_all_translator_books(id, allowed_langs)
+            .await?
+            .map(|page| page.items.iter().map(|book| book.id).collect()),
+        TaskObjectType::Books { ids } => Some(ids),
+    };
+
+    Ok(book_ids.unwrap_or_default())
+}
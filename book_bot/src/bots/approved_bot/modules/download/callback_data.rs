@@ -14,6 +14,9 @@ static RE_ARCHIVE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^da_(?P<obj_type>[sat])_(?P<id>\d+)_(?P<file_type>\w+)$").unwrap()
 });
 
+static RE_FORMATS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^d_all_(?P<book_id>\d+)$").unwrap());
+
 static RE_CHECK: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^check_da_(?P<task_id>\w+)$").unwrap());
 
@@ -87,6 +90,30 @@ impl FromStr for DownloadArchiveQueryData {
     }
 }
 
+/// "Other formats" under a one-tap download: shows the full format keyboard.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DownloadFormatsQueryData {
+    pub book_id: u32,
+}
+
+impl Display for DownloadFormatsQueryData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "d_all_{}", self.book_id)
+    }
+}
+
+impl FromStr for DownloadFormatsQueryData {
+    type Err = CallbackQueryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let caps = RE_FORMATS.captures(s).ok_or(CallbackQueryParseError)?;
+        let book_id: u32 = caps["book_id"]
+            .parse()
+            .map_err(|_| CallbackQueryParseError)?;
+        Ok(DownloadFormatsQueryData { book_id })
+    }
+}
+
 #[derive(Clone)]
 pub struct CheckArchiveStatus {
     pub task_id: String,
@@ -110,7 +137,9 @@ impl FromStr for CheckArchiveStatus {
 
 #[cfg(test)]
 mod tests {
-    use super::{CheckArchiveStatus, DownloadArchiveQueryData, DownloadQueryData};
+    use super::{
+        CheckArchiveStatus, DownloadArchiveQueryData, DownloadFormatsQueryData, DownloadQueryData,
+    };
     use std::str::FromStr;
 
     #[test]
@@ -132,6 +161,16 @@ mod tests {
         assert!(DownloadQueryData::from_str("d_x_fb2").is_err());
     }
 
+    #[test]
+    fn round_trip_download_formats() {
+        let cd = DownloadFormatsQueryData { book_id: 5 };
+        assert_eq!(
+            DownloadFormatsQueryData::from_str(&cd.to_string()).unwrap(),
+            cd
+        );
+        assert!(DownloadQueryData::from_str(&cd.to_string()).is_err());
+    }
+
     #[test]
     fn round_trip_archive_sequence() {
         let cd = DownloadArchiveQueryData::Sequence {
@@ -4,6 +4,8 @@ use teloxide::{
     prelude::*,
     types::{InputFile, MaybeInaccessibleMessage, MessageId},
 };
+use tempfile::NamedTempFile;
+use tokio::io::AsyncWriteExt;
 use tracing::log;
 
 use crate::{
@@ -18,6 +20,8 @@ use crate::{
                     types::{CachedMessage, DownloadFile},
                 },
                 donation_notifications::send_donation_notification,
+                download_history::DOWNLOAD_HISTORY,
+                images::covers::{extract_cover, may_have_cover, BOOK_COVERS},
             },
         },
         BotHandlerInternal,
@@ -25,7 +29,7 @@ use crate::{
     bots_manager::BotCache,
 };
 
-use super::callback_data::DownloadQueryData;
+use super::{callback_data::DownloadQueryData, series::suggest_next_in_series};
 
 async fn _send_cached(
     message: &MaybeInaccessibleMessage,
@@ -41,6 +45,31 @@ async fn _send_cached(
     .await
 }
 
+/// Adds a sent book to the user's `/history`. History is a convenience, so
+/// failures are only logged.
+async fn record_download(user_id: Option<u64>, download_data: &DownloadQueryData) {
+    let Some(user_id) = user_id else {
+        return;
+    };
+
+    let DownloadQueryData::DownloadData { book_id, file_type } = download_data;
+
+    record_downloads(user_id, &[*book_id], file_type).await;
+}
+
+/// Adds books sent together, e.g. in an archive, to the user's `/history`.
+pub(super) async fn record_downloads(user_id: u64, book_ids: &[u32], file_type: &str) {
+    for book_id in book_ids {
+        if let Err(err) = DOWNLOAD_HISTORY
+            .add(user_id, *book_id, file_type.to_string())
+            .await
+        {
+            log::warn!("Failed to record download: {err:?}");
+            return;
+        }
+    }
+}
+
 pub async fn send_cached_message(
     message: MaybeInaccessibleMessage,
     bot: CacheMe<Throttle<Bot>>,
@@ -57,6 +86,8 @@ pub async fn send_cached_message(
             };
 
             if _send_cached(&message, &bot, cached).await.is_ok() {
+                record_download(user_id, &download_data).await;
+
                 if need_delete_message {
                     if let MaybeInaccessibleMessage::Regular(message) = &message {
                         let _ = safe_delete_message(&bot, message.chat.id, message.id).await;
@@ -68,6 +99,8 @@ pub async fn send_cached_message(
                     Err(err) => log::error!("{err:?}"),
                 }
 
+                suggest_next_in_series(&bot, message.chat().id, user_id, &download_data);
+
                 return Ok(());
             }
         };
@@ -79,10 +112,28 @@ pub async fn send_cached_message(
     Ok(())
 }
 
+/// Saves the response to a temporary file, which is removed once dropped.
+async fn download_to_temp_file(mut response: reqwest::Response) -> anyhow::Result<NamedTempFile> {
+    let file = NamedTempFile::new()?;
+    let mut writer = tokio::fs::File::from_std(file.reopen()?);
+
+    while let Some(chunk) = response.chunk().await? {
+        writer.write_all(&chunk).await?;
+    }
+    writer.flush().await?;
+
+    Ok(file)
+}
+
+/// Sends a book, with its cover as the thumbnail when the format carries
+/// one. `book_id` is set for single books, whose cover is then kept for
+/// the book card. Books resent from the channel cache are copied without
+/// being read, so they get no cover here.
 pub async fn _send_downloaded_file(
     message: &MaybeInaccessibleMessage,
     bot: &CacheMe<Throttle<Bot>>,
     downloaded_data: DownloadFile,
+    book_id: Option<u32>,
 ) -> BotHandlerInternal {
     let DownloadFile {
         response,
@@ -90,12 +141,34 @@ pub async fn _send_downloaded_file(
         caption,
     } = downloaded_data;
 
-    let stream = response.bytes_stream().map_err(std::io::Error::other);
-    let data = tokio_util::io::StreamReader::new(stream);
+    if book_id.is_some() && may_have_cover(&filename) {
+        let file = download_to_temp_file(response).await?;
+
+        let path = file.path().to_path_buf();
+        let name = filename.clone();
+        let cover = tokio::task::spawn_blocking(move || extract_cover(&path, &name))
+            .await
+            .ok()
+            .flatten();
+
+        let document = InputFile::file(file.path()).file_name(filename);
+        let thumbnail = cover
+            .as_ref()
+            .map(|cover| InputFile::memory(cover.thumbnail.clone()).file_name("cover.jpg"));
+
+        safe_send_document(bot, message.chat().id, document, caption, thumbnail).await?;
 
-    let document = InputFile::read(data).file_name(filename);
+        if let (Some(book_id), Some(cover)) = (book_id, cover) {
+            BOOK_COVERS.insert(book_id, cover.photo).await;
+        }
+    } else {
+        let stream = response.bytes_stream().map_err(std::io::Error::other);
+        let data = tokio_util::io::StreamReader::new(stream);
+
+        let document = InputFile::read(data).file_name(filename);
 
-    safe_send_document(bot, message.chat().id, document, caption).await?;
+        safe_send_document(bot, message.chat().id, document, caption, None).await?;
+    }
 
     send_donation_notification(bot, message).await?;
 
@@ -116,7 +189,13 @@ pub async fn send_with_download_from_channel(
         }
     };
 
-    _send_downloaded_file(&message, &bot, downloaded_file).await?;
+    let DownloadQueryData::DownloadData { book_id, .. } = &download_data;
+
+    _send_downloaded_file(&message, &bot, downloaded_file, Some(*book_id)).await?;
+
+    record_download(user_id, &download_data).await;
+
+    let chat_id = message.chat().id;
 
     if need_delete_message {
         if let MaybeInaccessibleMessage::Regular(message) = message {
@@ -124,6 +203,8 @@ pub async fn send_with_download_from_channel(
         };
     }
 
+    suggest_next_in_series(&bot, chat_id, user_id, &download_data);
+
     Ok(())
 }
 
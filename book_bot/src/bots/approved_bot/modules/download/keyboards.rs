@@ -1,10 +1,13 @@
 use teloxide::types::{InlineKeyboardButton, InlineKeyboardButtonKind, InlineKeyboardMarkup};
 
-use crate::bots::approved_bot::services::book_library::types::Book;
+use crate::bots::approved_bot::{
+    modules::utils::deep_link::{get_share_button, DeepLink},
+    services::book_library::types::Book,
+};
 
 use super::{
-    callback_data::{CheckArchiveStatus, DownloadQueryData},
-    commands::DownloadArchiveCommand,
+    callback_data::{CheckArchiveStatus, DownloadFormatsQueryData, DownloadQueryData},
+    commands::{DownloadArchiveCommand, StartDownloadCommand},
 };
 
 pub fn get_check_keyboard(task_id: String) -> InlineKeyboardMarkup {
@@ -18,43 +21,156 @@ pub fn get_check_keyboard(task_id: String) -> InlineKeyboardMarkup {
     }
 }
 
-pub fn get_download_format_keyboard(book: &Book) -> InlineKeyboardMarkup {
+pub fn get_download_format_keyboard(
+    book: &Book,
+    bot_username: Option<&str>,
+) -> InlineKeyboardMarkup {
+    let mut inline_keyboard: Vec<Vec<InlineKeyboardButton>> = book
+        .available_types
+        .iter()
+        .map(|item| -> Vec<InlineKeyboardButton> {
+            vec![InlineKeyboardButton {
+                text: format!("📥 {item}"),
+                kind: InlineKeyboardButtonKind::CallbackData(
+                    (DownloadQueryData::DownloadData {
+                        book_id: book.id,
+                        file_type: item.clone(),
+                    })
+                    .to_string(),
+                ),
+            }]
+        })
+        .collect();
+
+    let mut share_row = vec![InlineKeyboardButton {
+        text: String::from("📤 Отправить в чат"),
+        kind: InlineKeyboardButtonKind::SwitchInlineQuery(
+            (StartDownloadCommand { id: book.id }).to_string(),
+        ),
+    }];
+    share_row.extend(
+        bot_username
+            .and_then(|username| get_share_button(&DeepLink::Book { id: book.id }, username)),
+    );
+    inline_keyboard.push(share_row);
+
+    InlineKeyboardMarkup { inline_keyboard }
+}
+
+/// Sits under a preferred-format download in place of the format keyboard.
+pub fn get_other_formats_keyboard(book_id: u32) -> InlineKeyboardMarkup {
     InlineKeyboardMarkup {
-        inline_keyboard: book
-            .available_types
-            .iter()
-            .map(|item| -> Vec<InlineKeyboardButton> {
+        inline_keyboard: vec![vec![InlineKeyboardButton {
+            text: String::from("📚 Другие форматы"),
+            kind: InlineKeyboardButtonKind::CallbackData(
+                DownloadFormatsQueryData { book_id }.to_string(),
+            ),
+        }]],
+    }
+}
+
+/// Formats offered for archives of hand-picked books; the batch downloader
+/// skips books that lack the chosen one.
+pub const BOOKS_ARCHIVE_FORMATS: [&str; 3] = ["fb2", "epub", "mobi"];
+
+/// `BOOKS_ARCHIVE_FORMATS` with the user's `preferred` one first and checked.
+/// `callback_data` builds the data of each format's button.
+pub fn get_books_archive_format_keyboard(
+    preferred: Option<&str>,
+    callback_data: impl Fn(&str) -> String,
+) -> InlineKeyboardMarkup {
+    let file_types = preferred.into_iter().chain(
+        BOOKS_ARCHIVE_FORMATS
+            .into_iter()
+            .filter(|file_type| Some(*file_type) != preferred),
+    );
+
+    InlineKeyboardMarkup {
+        inline_keyboard: file_types
+            .map(|file_type| {
+                let check = if Some(file_type) == preferred {
+                    " ✓"
+                } else {
+                    ""
+                };
+
                 vec![InlineKeyboardButton {
-                    text: format!("📥 {item}"),
-                    kind: InlineKeyboardButtonKind::CallbackData(
-                        (DownloadQueryData::DownloadData {
-                            book_id: book.id,
-                            file_type: item.clone(),
-                        })
-                        .to_string(),
-                    ),
+                    text: format!("{file_type}{check}"),
+                    kind: InlineKeyboardButtonKind::CallbackData(callback_data(file_type)),
                 }]
             })
             .collect(),
     }
 }
 
+/// The user's `preferred` format, if any, goes first and is checked.
 pub fn get_download_archive_format_keyboard(
     command: DownloadArchiveCommand,
     available_types: &[String],
+    preferred: Option<&String>,
 ) -> InlineKeyboardMarkup {
-    InlineKeyboardMarkup {
-        inline_keyboard: available_types
+    let file_types = preferred.into_iter().chain(
+        available_types
             .iter()
+            .filter(|file_type| Some(*file_type) != preferred),
+    );
+
+    InlineKeyboardMarkup {
+        inline_keyboard: file_types
             .filter(|file_type| !file_type.contains("zip"))
             .map(|file_type| {
                 let callback_data = command.to_query_data(file_type.to_string()).to_string();
+                let check = if Some(file_type) == preferred {
+                    " ✓"
+                } else {
+                    ""
+                };
 
                 vec![InlineKeyboardButton {
-                    text: file_type.to_string(),
+                    text: format!("{file_type}{check}"),
                     kind: InlineKeyboardButtonKind::CallbackData(callback_data),
                 }]
             })
             .collect(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_keyboard_puts_preferred_format_first() {
+        let available = vec!["epub".to_string(), "fb2".to_string(), "fb2zip".to_string()];
+        let preferred = "fb2".to_string();
+
+        let keyboard = get_download_archive_format_keyboard(
+            DownloadArchiveCommand::Author { id: 1 },
+            &available,
+            Some(&preferred),
+        );
+        let texts: Vec<&str> = keyboard
+            .inline_keyboard
+            .iter()
+            .map(|row| row[0].text.as_str())
+            .collect();
+
+        assert_eq!(texts, vec!["fb2 ✓", "epub"]);
+    }
+
+    #[test]
+    fn books_archive_keyboard_puts_preferred_format_first() {
+        let keyboard = get_books_archive_format_keyboard(Some("epub"), |v| format!("x_{v}"));
+        let texts: Vec<&str> = keyboard
+            .inline_keyboard
+            .iter()
+            .map(|row| row[0].text.as_str())
+            .collect();
+
+        assert_eq!(texts, vec!["epub ✓", "fb2", "mobi"]);
+        assert!(matches!(
+            &keyboard.inline_keyboard[0][0].kind,
+            InlineKeyboardButtonKind::CallbackData(data) if data == "x_epub"
+        ));
+    }
+}
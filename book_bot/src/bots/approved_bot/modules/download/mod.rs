@@ -3,9 +3,12 @@ pub mod callback_data;
 pub mod commands;
 pub mod file_send;
 pub mod keyboards;
+pub mod series;
 
 use super::utils::constants::*;
-use super::utils::telegram_utils::safe_send_message_with_reply;
+use super::utils::telegram_utils::{
+    safe_edit_message_text, safe_send_message, safe_send_message_with_reply,
+};
 
 use book_bot_macros::log_handler;
 
@@ -24,7 +27,9 @@ use crate::bots::{
                 get_author_books_available_types, get_book, get_sequence_books_available_types,
                 get_translator_books_available_types,
             },
-            user_settings::get_user_or_default_lang_codes,
+            user_settings::{
+                get_user_or_default_lang_codes, get_user_preferred_formats, pick_preferred_format,
+            },
         },
         tools::filter_callback_query,
     },
@@ -34,13 +39,18 @@ use crate::bots_manager::BotCache;
 
 use self::{
     archive::download_archive,
-    callback_data::{CheckArchiveStatus, DownloadArchiveQueryData, DownloadQueryData},
+    callback_data::{
+        CheckArchiveStatus, DownloadArchiveQueryData, DownloadFormatsQueryData, DownloadQueryData,
+    },
     commands::{DownloadArchiveCommand, StartDownloadCommand},
     file_send::download_handler,
-    keyboards::{get_download_archive_format_keyboard, get_download_format_keyboard},
+    keyboards::{
+        get_download_archive_format_keyboard, get_download_format_keyboard,
+        get_other_formats_keyboard,
+    },
 };
 
-use super::utils::filter_command::filter_command;
+use super::utils::{filter_command::filter_command, message_text::is_message_text_equals};
 
 use archive::wait_archive;
 
@@ -48,6 +58,7 @@ use archive::wait_archive;
 async fn get_download_keyboard_handler(
     message: Message,
     bot: CacheMe<Throttle<Bot>>,
+    cache: BotCache,
     download_data: StartDownloadCommand,
 ) -> BotHandlerInternal {
     let book = match get_book(download_data.id).await {
@@ -77,12 +88,44 @@ async fn get_download_keyboard_handler(
         }
     };
 
-    let keyboard = get_download_format_keyboard(&book);
+    let preferred_formats = match message.from.as_ref() {
+        Some(user) => get_user_preferred_formats(user.id).await,
+        None => Default::default(),
+    };
+
+    if let Some(file_type) = pick_preferred_format(&preferred_formats, &book.available_types) {
+        safe_send_message_with_reply(
+            &bot,
+            message.chat.id,
+            format!("📥 {file_type}"),
+            ReplyParameters::new(message.id),
+            Some(get_other_formats_keyboard(book.id)),
+        )
+        .await?;
+
+        let user_id = message.from.as_ref().map(|user| user.id.0);
+
+        return download_handler(
+            MaybeInaccessibleMessage::Regular(Box::new(message)),
+            bot,
+            cache,
+            DownloadQueryData::DownloadData {
+                book_id: book.id,
+                file_type: file_type.clone(),
+            },
+            false,
+            user_id,
+        )
+        .await;
+    }
+
+    let me = bot.get_me().await.ok();
+    let keyboard = get_download_format_keyboard(&book, me.as_ref().map(|me| me.username()));
 
     safe_send_message_with_reply(
         &bot,
         message.chat.id,
-        "Выбери формат:",
+        CHOOSE_FORMAT,
         ReplyParameters::new(message.id),
         Some(keyboard),
     )
@@ -130,12 +173,17 @@ async fn get_download_archive_keyboard_handler(
         Err(err) => return Err(err),
     };
 
-    let keyboard = get_download_archive_format_keyboard(command, &available_types);
+    let preferred_formats = get_user_preferred_formats(from.id).await;
+    let keyboard = get_download_archive_format_keyboard(
+        command,
+        &available_types,
+        pick_preferred_format(&preferred_formats, &available_types),
+    );
 
     safe_send_message_with_reply(
         &bot,
         message.chat.id,
-        "Выбери формат:",
+        CHOOSE_FORMAT,
         ReplyParameters::new(message.id),
         Some(keyboard),
     )
@@ -144,6 +192,41 @@ async fn get_download_archive_keyboard_handler(
     Ok(())
 }
 
+#[log_handler("download")]
+async fn download_formats_handler(
+    cq: CallbackQuery,
+    query_data: DownloadFormatsQueryData,
+    bot: CacheMe<Throttle<Bot>>,
+) -> BotHandlerInternal {
+    let Some(message) = cq.message else {
+        return Ok(());
+    };
+
+    let book = match get_book(query_data.book_id).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            safe_send_message(&bot, message.chat().id, NOT_FOUND, None).await?;
+            return Ok(());
+        }
+        Err(err) => {
+            safe_send_message(&bot, message.chat().id, ERROR_TRY_LATER, None).await?;
+            return Err(err);
+        }
+    };
+
+    let me = bot.get_me().await.ok();
+    let keyboard = get_download_format_keyboard(&book, me.as_ref().map(|me| me.username()));
+
+    safe_edit_message_text(
+        &bot,
+        message.chat().id,
+        message.id(),
+        CHOOSE_FORMAT,
+        Some(keyboard),
+    )
+    .await
+}
+
 #[log_handler("download")]
 async fn download_query_handler(
     cq: CallbackQuery,
@@ -155,7 +238,20 @@ async fn download_query_handler(
         return Ok(());
     };
     let user_id = Some(cq.from.id.0);
-    download_handler(message, bot, cache, download_query_data, true, user_id).await
+
+    // Cards and listings with download buttons stay; only the bare format
+    // prompt is cleaned up.
+    let need_delete_message = is_message_text_equals(Some(message.clone()), CHOOSE_FORMAT);
+
+    download_handler(
+        message,
+        bot,
+        cache,
+        download_query_data,
+        need_delete_message,
+        user_id,
+    )
+    .await
 }
 
 pub fn get_download_handler() -> crate::bots::BotHandler {
@@ -170,6 +266,11 @@ pub fn get_download_handler() -> crate::bots::BotHandler {
                 .chain(filter_callback_query::<DownloadQueryData>())
                 .endpoint(download_query_handler),
         )
+        .branch(
+            Update::filter_callback_query()
+                .chain(filter_callback_query::<DownloadFormatsQueryData>())
+                .endpoint(download_formats_handler),
+        )
         .branch(
             Update::filter_message()
                 .chain(filter_command::<DownloadArchiveCommand>())
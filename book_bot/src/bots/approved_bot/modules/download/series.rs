@@ -0,0 +1,238 @@
+use teloxide::{
+    adaptors::{CacheMe, Throttle},
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardButtonKind, InlineKeyboardMarkup},
+};
+use tracing::log;
+
+use crate::bots::approved_bot::{
+    modules::{book_card::commands::BookCardCommand, utils::telegram_utils::safe_send_message},
+    services::{
+        book_library::{
+            get_book, get_sequence_books,
+            types::{Sequence, SequenceBook},
+            BookListFilter, BookSort,
+        },
+        user_settings::{get_user_or_default_lang_codes, get_user_series_suggestions},
+    },
+};
+
+use super::callback_data::{DownloadFormatsQueryData, DownloadQueryData};
+
+/// Books fetched around the downloaded one to find its neighbours.
+const SUGGESTION_PAGE_SIZE: u32 = 20;
+
+/// The page of the series, sorted by position, that most likely holds the
+/// book. Books in other languages are filtered out by the server, so a
+/// book near a page edge may miss a neighbour; no suggestion is sent then.
+fn get_suggestion_page(position: Option<i32>) -> u32 {
+    let index = position.unwrap_or(1).max(1) as u32 - 1;
+
+    index / SUGGESTION_PAGE_SIZE + 1
+}
+
+/// The books before and after `book_id` in series order. `None` when the
+/// book is not in the list or is the last one.
+fn find_neighbours(
+    books: &mut [SequenceBook],
+    book_id: u32,
+) -> Option<(Option<&SequenceBook>, &SequenceBook)> {
+    books.sort_by_key(|book| book.position);
+
+    let index = books.iter().position(|book| book.id == book_id)?;
+    let next = books.get(index + 1)?;
+    let previous = index.checked_sub(1).map(|index| &books[index]);
+
+    Some((previous, next))
+}
+
+fn format_book_line(prefix: &str, book: &SequenceBook) -> String {
+    format!(
+        "{prefix} {}. {} {}",
+        book.position,
+        book.title,
+        BookCardCommand { id: book.id }
+    )
+}
+
+fn format_suggestion(
+    sequence: &Sequence,
+    previous: Option<&SequenceBook>,
+    next: &SequenceBook,
+) -> String {
+    let mut lines = vec![format!("📚 Серия «{}»", sequence.name)];
+
+    if let Some(previous) = previous {
+        lines.push(format_book_line("⬅️", previous));
+    }
+    lines.push(format_book_line("➡️", next));
+
+    lines.join("\n")
+}
+
+/// Buttons open the format choice: the neighbours may lack the format
+/// that was just downloaded.
+fn get_suggestion_keyboard(
+    previous: Option<&SequenceBook>,
+    next: &SequenceBook,
+) -> InlineKeyboardMarkup {
+    let button = |text: &str, book: &SequenceBook| InlineKeyboardButton {
+        text: text.to_string(),
+        kind: InlineKeyboardButtonKind::CallbackData(
+            DownloadFormatsQueryData { book_id: book.id }.to_string(),
+        ),
+    };
+
+    let mut row = vec![];
+    if let Some(previous) = previous {
+        row.push(button("⬅️ Предыдущая", previous));
+    }
+    row.push(button("Следующая ➡️", next));
+
+    InlineKeyboardMarkup {
+        inline_keyboard: vec![row],
+    }
+}
+
+async fn send_series_suggestion(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    user_id: UserId,
+    book_id: u32,
+) -> anyhow::Result<()> {
+    let Some(book) = get_book(book_id).await? else {
+        return Ok(());
+    };
+    let Some(sequence) = book.sequences.first() else {
+        return Ok(());
+    };
+
+    let allowed_langs = get_user_or_default_lang_codes(user_id).await;
+    let filter = BookListFilter {
+        sort: BookSort::Position,
+        ..Default::default()
+    };
+    let Some(mut books) = get_sequence_books(
+        sequence.id,
+        get_suggestion_page(sequence.position),
+        SUGGESTION_PAGE_SIZE,
+        allowed_langs,
+        filter,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+
+    let Some((previous, next)) = find_neighbours(&mut books.items, book_id) else {
+        return Ok(());
+    };
+
+    safe_send_message(
+        bot,
+        chat_id,
+        format_suggestion(sequence, previous, next),
+        Some(get_suggestion_keyboard(previous, next)),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Offers the neighbours of a just delivered book in its series, unless the
+/// user turned it off in `/settings`. Runs in the background so the
+/// download is finished first; the book is already sent, so failures are
+/// only logged.
+pub fn suggest_next_in_series(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    user_id: Option<u64>,
+    download_data: &DownloadQueryData,
+) {
+    let Some(user_id) = user_id.map(UserId) else {
+        return;
+    };
+
+    let bot = bot.clone();
+    let DownloadQueryData::DownloadData { book_id, .. } = *download_data;
+
+    tokio::spawn(async move {
+        if !get_user_series_suggestions(user_id).await {
+            return;
+        }
+
+        if let Err(err) = send_series_suggestion(&bot, chat_id, user_id, book_id).await {
+            log::warn!("Failed to suggest next book of the series: {err:?}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(id: u32, position: i32) -> SequenceBook {
+        SequenceBook {
+            id,
+            title: format!("Книга {id}"),
+            lang: "ru".to_string(),
+            authors: vec![],
+            translators: vec![],
+            annotation_exists: false,
+            year: 2000,
+            position,
+        }
+    }
+
+    fn ids(
+        neighbours: Option<(Option<&SequenceBook>, &SequenceBook)>,
+    ) -> Option<(Option<u32>, u32)> {
+        neighbours.map(|(previous, next)| (previous.map(|book| book.id), next.id))
+    }
+
+    #[test]
+    fn finds_neighbours_in_series_order() {
+        let mut books = vec![book(30, 3), book(10, 1), book(20, 2)];
+
+        assert_eq!(ids(find_neighbours(&mut books, 10)), Some((None, 20)));
+        assert_eq!(ids(find_neighbours(&mut books, 20)), Some((Some(10), 30)));
+    }
+
+    #[test]
+    fn last_or_unknown_book_has_no_suggestion() {
+        let mut books = vec![book(10, 1), book(20, 2)];
+
+        assert!(find_neighbours(&mut books, 20).is_none());
+        assert!(find_neighbours(&mut books, 99).is_none());
+    }
+
+    #[test]
+    fn suggestion_page_holds_position() {
+        assert_eq!(get_suggestion_page(None), 1);
+        assert_eq!(get_suggestion_page(Some(1)), 1);
+        assert_eq!(get_suggestion_page(Some(20)), 1);
+        assert_eq!(get_suggestion_page(Some(21)), 2);
+    }
+
+    #[test]
+    fn suggestion_lists_neighbours() {
+        let sequence = Sequence {
+            id: 5,
+            name: "Цикл".to_string(),
+            position: Some(2),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            format_suggestion(&sequence, Some(&book(10, 1)), &book(30, 3)),
+            "📚 Серия «Цикл»\n⬅️ 1. Книга 10 /b_10\n➡️ 3. Книга 30 /b_30"
+        );
+
+        let keyboard = get_suggestion_keyboard(None, &book(30, 3));
+        assert_eq!(keyboard.inline_keyboard[0].len(), 1);
+        assert_eq!(
+            keyboard.inline_keyboard[0][0].kind,
+            InlineKeyboardButtonKind::CallbackData("d_all_30".to_string())
+        );
+    }
+}
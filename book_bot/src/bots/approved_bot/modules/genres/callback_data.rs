@@ -0,0 +1,138 @@
+use std::{fmt::Display, str::FromStr};
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::bots::approved_bot::{
+    modules::utils::{errors::CallbackQueryParseError, pagination::GetPaginationCallbackData},
+    services::book_library::BookSort,
+};
+
+static RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^gc_(?:m|m_(?P<index>\d+)|b_(?P<meta>\d+)_(?P<genre_id>\d+)_(?P<sort>[ntyp])_(?P<page>\d+))$",
+    )
+    .unwrap()
+});
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GenresCallbackData {
+    /// List of genre metas.
+    Metas,
+    /// Genres of the meta at `index` in `get_genre_metas`.
+    Meta { index: u32 },
+    /// A page of the genre's books. `meta` is kept for the back button.
+    Books {
+        meta: u32,
+        genre_id: u32,
+        sort: BookSort,
+        page: u32,
+    },
+}
+
+impl FromStr for GenresCallbackData {
+    type Err = CallbackQueryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let caps = RE.captures(s).ok_or(CallbackQueryParseError)?;
+
+        let number = |name: &str| -> Result<u32, CallbackQueryParseError> {
+            caps[name].parse().map_err(|_| CallbackQueryParseError)
+        };
+
+        if caps.name("index").is_some() {
+            return Ok(GenresCallbackData::Meta {
+                index: number("index")?,
+            });
+        }
+
+        if caps.name("genre_id").is_some() {
+            return Ok(GenresCallbackData::Books {
+                meta: number("meta")?,
+                genre_id: number("genre_id")?,
+                sort: BookSort::from_code(&caps["sort"]).ok_or(CallbackQueryParseError)?,
+                page: std::cmp::max(1, number("page")?),
+            });
+        }
+
+        Ok(GenresCallbackData::Metas)
+    }
+}
+
+impl Display for GenresCallbackData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenresCallbackData::Metas => write!(f, "gc_m"),
+            GenresCallbackData::Meta { index } => write!(f, "gc_m_{index}"),
+            GenresCallbackData::Books {
+                meta,
+                genre_id,
+                sort,
+                page,
+            } => write!(f, "gc_b_{meta}_{genre_id}_{}_{page}", sort.code()),
+        }
+    }
+}
+
+impl GetPaginationCallbackData for GenresCallbackData {
+    fn get_pagination_callback_data(&self, target_page: u32) -> String {
+        match self {
+            GenresCallbackData::Books {
+                meta,
+                genre_id,
+                sort,
+                ..
+            } => GenresCallbackData::Books {
+                meta: *meta,
+                genre_id: *genre_id,
+                sort: *sort,
+                page: target_page,
+            }
+            .to_string(),
+            _ => self.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for data in [
+            GenresCallbackData::Metas,
+            GenresCallbackData::Meta { index: 4 },
+            GenresCallbackData::Books {
+                meta: 2,
+                genre_id: 117,
+                sort: BookSort::Year,
+                page: 3,
+            },
+        ] {
+            assert_eq!(
+                GenresCallbackData::from_str(&data.to_string()).unwrap(),
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn pagination_keeps_genre_and_sort() {
+        let data = GenresCallbackData::Books {
+            meta: 0,
+            genre_id: 5,
+            sort: BookSort::Title,
+            page: 1,
+        };
+
+        assert_eq!(data.get_pagination_callback_data(6), "gc_b_0_5_t_6");
+    }
+
+    #[test]
+    fn rejects_foreign_data() {
+        assert!(GenresCallbackData::from_str("genres_1").is_err());
+        assert!(GenresCallbackData::from_str("gc_b_0_5_x_1").is_err());
+        assert!(GenresCallbackData::from_str("gc_m_").is_err());
+    }
+}
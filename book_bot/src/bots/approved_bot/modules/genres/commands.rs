@@ -0,0 +1,7 @@
+use teloxide::macros::BotCommands;
+
+#[derive(BotCommands, Clone)]
+#[command(rename_rule = "lowercase")]
+pub enum GenresCommand {
+    Genres,
+}
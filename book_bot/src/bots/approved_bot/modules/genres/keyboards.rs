@@ -0,0 +1,103 @@
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardButtonKind, InlineKeyboardMarkup};
+
+use crate::bots::approved_bot::services::book_library::{types::Genre, BookSort};
+
+pub const GENRE_BOOKS_SORTS: [(BookSort, &str); 3] = [
+    (BookSort::Newest, "🆕 Новые"),
+    (BookSort::Title, "🔤 По названию"),
+    (BookSort::Year, "📅 По году"),
+];
+
+fn callback_button(text: String, data: String) -> InlineKeyboardButton {
+    InlineKeyboardButton {
+        text,
+        kind: InlineKeyboardButtonKind::CallbackData(data),
+    }
+}
+
+/// One button per genre meta; `meta_data` builds the callback from the
+/// meta's index in `get_genre_metas`.
+pub fn get_genre_metas_keyboard(
+    metas: Vec<String>,
+    meta_data: impl Fn(u32) -> String,
+) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup {
+        inline_keyboard: metas
+            .into_iter()
+            .enumerate()
+            .map(|(index, meta)| vec![callback_button(meta, meta_data(index as u32))])
+            .collect(),
+    }
+}
+
+/// One button per genre plus "< Назад >" leading to `back_data`.
+pub fn get_genres_keyboard(
+    genres: Vec<Genre>,
+    genre_data: impl Fn(u32) -> String,
+    back_data: String,
+) -> InlineKeyboardMarkup {
+    let mut buttons: Vec<Vec<InlineKeyboardButton>> = genres
+        .into_iter()
+        .map(|genre| vec![callback_button(genre.description, genre_data(genre.id))])
+        .collect();
+
+    buttons.push(vec![callback_button("< Назад >".to_string(), back_data)]);
+
+    InlineKeyboardMarkup {
+        inline_keyboard: buttons,
+    }
+}
+
+/// Sort switch for a book listing; the current order is marked with "✓".
+pub fn get_sort_row(
+    sorts: &[(BookSort, &str)],
+    current: BookSort,
+    sort_data: impl Fn(BookSort) -> String,
+) -> Vec<InlineKeyboardButton> {
+    sorts
+        .iter()
+        .map(|(sort, text)| {
+            let text = if *sort == current {
+                format!("{text} ✓")
+            } else {
+                text.to_string()
+            };
+
+            callback_button(text, sort_data(*sort))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(row: &[InlineKeyboardButton]) -> Vec<&str> {
+        row.iter().map(|button| button.text.as_str()).collect()
+    }
+
+    #[test]
+    fn sort_row_marks_current_order() {
+        let row = get_sort_row(&GENRE_BOOKS_SORTS, BookSort::Title, |sort| {
+            format!("{sort:?}")
+        });
+
+        assert_eq!(texts(&row), ["🆕 Новые", "🔤 По названию ✓", "📅 По году"]);
+    }
+
+    #[test]
+    fn genres_keyboard_ends_with_back_button() {
+        let genres = vec![Genre {
+            id: 7,
+            description: "Фантастика".to_string(),
+        }];
+
+        let keyboard = get_genres_keyboard(genres, |id| format!("g_{id}"), "back".to_string());
+
+        assert_eq!(keyboard.inline_keyboard.len(), 2);
+        assert_eq!(
+            keyboard.inline_keyboard[1][0].kind,
+            InlineKeyboardButtonKind::CallbackData("back".to_string())
+        );
+    }
+}
@@ -0,0 +1,260 @@
+pub mod callback_data;
+pub mod commands;
+pub mod keyboards;
+
+use book_bot_macros::log_handler;
+
+use teloxide::{
+    adaptors::{CacheMe, Throttle},
+    dispatching::UpdateFilterExt,
+    dptree,
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardButtonKind, ReplyParameters},
+};
+
+use crate::bots::{
+    approved_bot::{
+        services::{
+            book_library::{self, types::SearchBook, BookSort},
+            user_settings::{get_user_or_default_lang_codes, get_user_page_size},
+        },
+        tools::filter_callback_query,
+    },
+    BotHandlerInternal,
+};
+
+use self::{
+    callback_data::GenresCallbackData,
+    commands::GenresCommand,
+    keyboards::{get_genre_metas_keyboard, get_genres_keyboard, get_sort_row, GENRE_BOOKS_SORTS},
+};
+
+use super::{
+    shelf::get_add_to_shelf_rows,
+    utils::{
+        constants::{ERROR_TRY_LATER, NOT_FOUND},
+        pagination::{paginate, PaginationTexts},
+        telegram_utils::{safe_edit_message_text, safe_send_message, safe_send_message_with_reply},
+    },
+};
+
+const CHOOSE_META: &str = "📚 Каталог по жанрам. Выбери раздел:";
+const GENRE_BOOKS_HEADER: &str = "📚 Книги жанра:\n\n";
+const GENRE_IS_EMPTY: &str = "В этом жанре нет книг на выбранных языках";
+
+fn get_metas_keyboard(metas: Vec<String>) -> teloxide::types::InlineKeyboardMarkup {
+    get_genre_metas_keyboard(metas, |index| {
+        GenresCallbackData::Meta { index }.to_string()
+    })
+}
+
+/// "⭐" per book, the sort switch and a way back to the meta's genres.
+fn get_genre_books_rows(
+    books: &[SearchBook],
+    meta: u32,
+    genre_id: u32,
+    sort: BookSort,
+) -> Vec<Vec<InlineKeyboardButton>> {
+    let mut rows = get_add_to_shelf_rows(books);
+
+    rows.push(get_sort_row(&GENRE_BOOKS_SORTS, sort, |sort| {
+        GenresCallbackData::Books {
+            meta,
+            genre_id,
+            sort,
+            page: 1,
+        }
+        .to_string()
+    }));
+    rows.push(vec![InlineKeyboardButton {
+        text: "< К жанрам >".to_string(),
+        kind: InlineKeyboardButtonKind::CallbackData(
+            GenresCallbackData::Meta { index: meta }.to_string(),
+        ),
+    }]);
+
+    rows
+}
+
+#[log_handler("genres")]
+async fn genres_command_handler(
+    message: Message,
+    bot: CacheMe<Throttle<Bot>>,
+) -> BotHandlerInternal {
+    let metas = match book_library::get_genre_metas().await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            return safe_send_message_with_reply(
+                &bot,
+                message.chat.id,
+                NOT_FOUND,
+                ReplyParameters::new(message.id),
+                None,
+            )
+            .await;
+        }
+        Err(err) => {
+            safe_send_message(&bot, message.chat.id, ERROR_TRY_LATER, None).await?;
+            return Err(err);
+        }
+    };
+
+    safe_send_message_with_reply(
+        &bot,
+        message.chat.id,
+        CHOOSE_META,
+        ReplyParameters::new(message.id),
+        Some(get_metas_keyboard(metas)),
+    )
+    .await
+}
+
+async fn metas_handler(cq: CallbackQuery, bot: CacheMe<Throttle<Bot>>) -> BotHandlerInternal {
+    let Some(message) = cq.message else {
+        return Ok(());
+    };
+
+    let metas = match book_library::get_genre_metas().await {
+        Ok(Some(v)) => v,
+        Ok(None) => return safe_send_message(&bot, message.chat().id, NOT_FOUND, None).await,
+        Err(err) => {
+            safe_send_message(&bot, message.chat().id, ERROR_TRY_LATER, None).await?;
+            return Err(err);
+        }
+    };
+
+    safe_edit_message_text(
+        &bot,
+        message.chat().id,
+        message.id(),
+        CHOOSE_META,
+        Some(get_metas_keyboard(metas)),
+    )
+    .await
+}
+
+async fn meta_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    index: u32,
+) -> BotHandlerInternal {
+    let Some(message) = cq.message else {
+        return Ok(());
+    };
+    let chat_id = message.chat().id;
+
+    let metas = match book_library::get_genre_metas().await {
+        Ok(v) => v.unwrap_or_default(),
+        Err(err) => {
+            safe_send_message(&bot, chat_id, ERROR_TRY_LATER, None).await?;
+            return Err(err);
+        }
+    };
+
+    let Some(meta) = metas.get(index as usize) else {
+        return safe_send_message(&bot, chat_id, NOT_FOUND, None).await;
+    };
+
+    let genres = match book_library::get_genres(meta.into()).await {
+        Ok(Some(v)) => v.items,
+        Ok(None) => return safe_send_message(&bot, chat_id, NOT_FOUND, None).await,
+        Err(err) => {
+            safe_send_message(&bot, chat_id, ERROR_TRY_LATER, None).await?;
+            return Err(err);
+        }
+    };
+
+    let keyboard = get_genres_keyboard(
+        genres,
+        |genre_id| {
+            GenresCallbackData::Books {
+                meta: index,
+                genre_id,
+                sort: BookSort::default(),
+                page: 1,
+            }
+            .to_string()
+        },
+        GenresCallbackData::Metas.to_string(),
+    );
+
+    safe_edit_message_text(
+        &bot,
+        chat_id,
+        message.id(),
+        format!("🗂 {meta}\n\nВыбери жанр:"),
+        Some(keyboard),
+    )
+    .await
+}
+
+async fn genre_books_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    callback_data: GenresCallbackData,
+) -> BotHandlerInternal {
+    let GenresCallbackData::Books {
+        meta,
+        genre_id,
+        sort,
+        page,
+    } = callback_data
+    else {
+        return Ok(());
+    };
+
+    let Some(message) = cq.message.as_ref() else {
+        return Ok(());
+    };
+    let (chat_id, message_id) = (message.chat().id, message.id());
+
+    let allowed_langs = get_user_or_default_lang_codes(cq.from.id).await;
+    let page_size = get_user_page_size(cq.from.id).await;
+
+    paginate(
+        &bot,
+        chat_id,
+        message_id,
+        cq.message,
+        page,
+        GENRE_BOOKS_HEADER,
+        |p| book_library::get_genre_books(genre_id, p, page_size, allowed_langs.clone(), sort),
+        callback_data,
+        |books| get_genre_books_rows(books, meta, genre_id, sort),
+        PaginationTexts {
+            not_found: NOT_FOUND,
+            no_items: GENRE_IS_EMPTY,
+            error_try_later: Some(ERROR_TRY_LATER),
+        },
+    )
+    .await
+}
+
+#[log_handler("genres")]
+async fn genres_callback_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    callback_data: GenresCallbackData,
+) -> BotHandlerInternal {
+    match callback_data {
+        GenresCallbackData::Metas => metas_handler(cq, bot).await,
+        GenresCallbackData::Meta { index } => meta_handler(cq, bot, index).await,
+        GenresCallbackData::Books { .. } => genre_books_handler(cq, bot, callback_data).await,
+    }
+}
+
+pub fn get_genres_handler() -> crate::bots::BotHandler {
+    dptree::entry()
+        .branch(
+            Update::filter_message().branch(
+                dptree::entry()
+                    .filter_command::<GenresCommand>()
+                    .endpoint(genres_command_handler),
+            ),
+        )
+        .branch(
+            Update::filter_callback_query()
+                .chain(filter_callback_query::<GenresCallbackData>())
+                .endpoint(genres_callback_handler),
+        )
+}
@@ -3,6 +3,29 @@ use teloxide::utils::command::BotCommands;
 #[derive(BotCommands, Clone)]
 #[command(rename_rule = "lowercase")]
 pub enum HelpCommand {
-    Start,
+    /// `/start` with an optional deep-link payload, see `DeepLink`.
+    Start(String),
     Help,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::HelpCommand;
+    use teloxide::utils::command::BotCommands;
+
+    #[test]
+    fn parses_start_without_payload() {
+        match HelpCommand::parse("/start", "bot").unwrap() {
+            HelpCommand::Start(payload) => assert_eq!(payload, ""),
+            _ => panic!("wrong variant"),
+        }
+    }
+
+    #[test]
+    fn parses_start_payload() {
+        match HelpCommand::parse("/start a_45", "bot").unwrap() {
+            HelpCommand::Start(payload) => assert_eq!(payload, "a_45"),
+            _ => panic!("wrong variant"),
+        }
+    }
+}
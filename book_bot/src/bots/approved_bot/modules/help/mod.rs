@@ -1,5 +1,7 @@
 pub mod commands;
 
+use std::str::FromStr;
+
 use crate::bots::BotHandlerInternal;
 use book_bot_macros::log_handler;
 
@@ -9,7 +11,41 @@ use teloxide::{
 };
 
 use self::commands::HelpCommand;
-use super::utils::telegram_utils::safe_send_message_html;
+use super::{
+    book::{book_command_handler, commands::BookCommand},
+    book_card::{book_card_handler, commands::BookCardCommand},
+    search::search_handler,
+    utils::{deep_link::DeepLink, telegram_utils::safe_send_message_html},
+};
+
+#[log_handler("help")]
+async fn start_handler(
+    message: Message,
+    bot: CacheMe<Throttle<Bot>>,
+    command: HelpCommand,
+) -> BotHandlerInternal {
+    let link = match command {
+        HelpCommand::Start(payload) => DeepLink::from_str(&payload).ok(),
+        HelpCommand::Help => None,
+    };
+
+    match link {
+        Some(DeepLink::Book { id }) => {
+            book_card_handler(message, bot, BookCardCommand { id }).await
+        }
+        Some(DeepLink::Author { id }) => {
+            book_command_handler(message, bot, BookCommand::Author { id }).await
+        }
+        Some(DeepLink::Translator { id }) => {
+            book_command_handler(message, bot, BookCommand::Translator { id }).await
+        }
+        Some(DeepLink::Sequence { id }) => {
+            book_command_handler(message, bot, BookCommand::Sequence { id }).await
+        }
+        Some(DeepLink::Search { query }) => search_handler(message, bot, Some(query)).await,
+        None => help_handler(message, bot).await,
+    }
+}
 
 #[log_handler("help")]
 pub async fn help_handler(message: Message, bot: CacheMe<Throttle<Bot>>) -> BotHandlerInternal {
@@ -19,6 +55,14 @@ pub async fn help_handler(message: Message, bot: CacheMe<Throttle<Bot>>) -> BotH
         .map(|user| teloxide::utils::html::escape(&user.first_name))
         .unwrap_or_else(|| "пользователь".to_string());
 
+    let inline_hint = match bot.get_me().await {
+        Ok(me) => format!(
+            "\nПоиск из любого чата: @{} запрос (a: — авторы, s: — серии).\n",
+            me.username()
+        ),
+        Err(_) => "".to_string(),
+    };
+
     safe_send_message_html(
         &bot,
         message.chat.id,
@@ -29,7 +73,10 @@ pub async fn help_handler(message: Message, bot: CacheMe<Throttle<Bot>>) -> BotH
 Этот бот поможет тебе загружать книги.
 
 Настройки языков для поиска /settings.
-
+Каталог книг по жанрам — /genres.
+Поиск по автору, названию, языку и году — /searchhelp.
+Избранные книги — на полке /shelf, скачанные — в /history.
+{inline_hint}
 Регистрация своего бота:
 1. <a href=\"https://telegra.ph/Registraciya-svoego-bota-01-24\">Зарегистрируй бота</a> в @BotFather.
 2. И перешли сюда сообщение об успешной регистрации.
@@ -46,7 +93,7 @@ pub fn get_help_handler() -> crate::bots::BotHandler {
         Update::filter_message().branch(
             dptree::entry()
                 .filter_command::<HelpCommand>()
-                .endpoint(help_handler),
+                .endpoint(start_handler),
         ),
     )
 }
@@ -0,0 +1,61 @@
+use std::{fmt::Display, str::FromStr};
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::bots::approved_bot::modules::utils::{
+    errors::CallbackQueryParseError, pagination::GetPaginationCallbackData,
+};
+
+static RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^history_(?P<page>\d+)$").unwrap());
+
+/// A page of the `/history` listing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryCallbackData {
+    pub page: u32,
+}
+
+impl FromStr for HistoryCallbackData {
+    type Err = CallbackQueryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let caps = RE.captures(s).ok_or(CallbackQueryParseError)?;
+        let page: u32 = caps["page"].parse().map_err(|_| CallbackQueryParseError)?;
+
+        Ok(HistoryCallbackData {
+            page: std::cmp::max(1, page),
+        })
+    }
+}
+
+impl Display for HistoryCallbackData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "history_{}", self.page)
+    }
+}
+
+impl GetPaginationCallbackData for HistoryCallbackData {
+    fn get_pagination_callback_data(&self, target_page: u32) -> String {
+        HistoryCallbackData { page: target_page }.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let data = HistoryCallbackData { page: 3 };
+        assert_eq!(
+            HistoryCallbackData::from_str(&data.to_string()).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn rejects_foreign_data() {
+        assert!(HistoryCallbackData::from_str("history_x").is_err());
+        assert!(HistoryCallbackData::from_str("shelf_1").is_err());
+    }
+}
@@ -0,0 +1,279 @@
+pub mod callback_data;
+pub mod commands;
+
+use book_bot_macros::log_handler;
+
+use teloxide::{
+    adaptors::{CacheMe, Throttle},
+    dispatching::UpdateFilterExt,
+    dptree,
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardButtonKind, ReplyParameters},
+};
+
+use crate::bots::{
+    approved_bot::{
+        services::{
+            book_library::{
+                formatters::{Format, FormatResult},
+                get_book,
+                types::{Book, Empty, Page},
+            },
+            download_history::{DownloadRecord, DOWNLOAD_HISTORY},
+            user_settings::get_user_page_size,
+        },
+        tools::filter_callback_query,
+    },
+    BotHandlerInternal,
+};
+
+use self::{callback_data::HistoryCallbackData, commands::HistoryCommand};
+
+use super::{
+    download::callback_data::DownloadQueryData,
+    utils::{
+        constants::{ERROR_TRY_LATER, REPEAT_REQUEST, TELEGRAM_MESSAGE_MAX_LENGTH},
+        keyboard::short_title,
+        pagination::{generic_get_pagination_keyboard, paginate, PaginationTexts},
+        telegram_utils::{safe_send_message, safe_send_message_with_reply},
+    },
+};
+
+const HISTORY_HEADER: &str = "🕓 История загрузок:\n\n";
+const HISTORY_IS_EMPTY: &str = "Вы ещё ничего не скачивали.";
+
+/// A download together with the book it refers to.
+#[derive(Clone, Debug)]
+struct HistoryItem {
+    record: DownloadRecord,
+    book: Book,
+}
+
+impl Format for HistoryItem {
+    fn format(&self, max_size: usize) -> FormatResult {
+        let prefix = format!(
+            "🕓 {} · {}\n",
+            self.record.downloaded_at.format("%d.%m.%Y %H:%M UTC"),
+            self.record.file_type
+        );
+
+        let FormatResult {
+            result,
+            current_size,
+            max_size,
+        } = self.book.format(max_size.saturating_sub(prefix.len()));
+
+        FormatResult {
+            result: format!("{prefix}{result}"),
+            current_size: current_size + prefix.len(),
+            max_size: max_size + prefix.len(),
+        }
+    }
+}
+
+/// "📥 <title> (<format>)" row for every item, sending the same file again.
+fn get_download_again_rows(items: &[HistoryItem]) -> Vec<Vec<InlineKeyboardButton>> {
+    items
+        .iter()
+        .map(|item| {
+            vec![InlineKeyboardButton {
+                text: format!(
+                    "📥 {} ({})",
+                    short_title(&item.book.title),
+                    item.record.file_type
+                ),
+                kind: InlineKeyboardButtonKind::CallbackData(
+                    DownloadQueryData::DownloadData {
+                        book_id: item.record.book_id,
+                        file_type: item.record.file_type.clone(),
+                    }
+                    .to_string(),
+                ),
+            }]
+        })
+        .collect()
+}
+
+async fn get_history_page(
+    user_id: UserId,
+    page: u32,
+) -> anyhow::Result<Option<Page<HistoryItem, Empty>>> {
+    let page_size = get_user_page_size(user_id).await;
+    let count = DOWNLOAD_HISTORY.count(user_id.0).await?;
+    let records = DOWNLOAD_HISTORY
+        .list(user_id.0, page.saturating_sub(1) * page_size, page_size)
+        .await?;
+
+    let books =
+        futures::future::try_join_all(records.iter().map(|record| get_book(record.book_id)))
+            .await?;
+
+    // Books removed from the library since are skipped.
+    let items = records
+        .into_iter()
+        .zip(books)
+        .filter_map(|(record, book)| book.map(|book| HistoryItem { record, book }))
+        .collect();
+
+    Ok(Some(Page {
+        items,
+        total: count,
+        pages: count.div_ceil(page_size),
+        parent_item: None,
+    }))
+}
+
+#[log_handler("history")]
+async fn history_command_handler(
+    message: Message,
+    bot: CacheMe<Throttle<Bot>>,
+) -> BotHandlerInternal {
+    let Some(user_id) = message.from.as_ref().map(|user| user.id) else {
+        return safe_send_message_with_reply(
+            &bot,
+            message.chat.id,
+            REPEAT_REQUEST,
+            ReplyParameters::new(message.id),
+            None,
+        )
+        .await;
+    };
+
+    let items_page = match get_history_page(user_id, 1).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return Ok(()),
+        Err(err) => {
+            safe_send_message(&bot, message.chat.id, ERROR_TRY_LATER, None).await?;
+            return Err(err);
+        }
+    };
+
+    if items_page.pages == 0 {
+        return safe_send_message_with_reply(
+            &bot,
+            message.chat.id,
+            HISTORY_IS_EMPTY,
+            ReplyParameters::new(message.id),
+            None,
+        )
+        .await;
+    }
+
+    let formatted_page = items_page.format(
+        1,
+        TELEGRAM_MESSAGE_MAX_LENGTH.saturating_sub(HISTORY_HEADER.len()),
+    );
+
+    let mut keyboard =
+        generic_get_pagination_keyboard(1, items_page.pages, HistoryCallbackData { page: 1 });
+    keyboard
+        .inline_keyboard
+        .extend(get_download_again_rows(&items_page.items));
+
+    safe_send_message_with_reply(
+        &bot,
+        message.chat.id,
+        format!("{HISTORY_HEADER}{formatted_page}"),
+        ReplyParameters::new(message.id),
+        Some(keyboard),
+    )
+    .await
+}
+
+#[log_handler("history")]
+async fn history_pagination_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    callback_data: HistoryCallbackData,
+) -> BotHandlerInternal {
+    let Some(message) = cq.message.as_ref() else {
+        return Ok(());
+    };
+    let (chat_id, message_id) = (message.chat().id, message.id());
+    let user_id = cq.from.id;
+
+    paginate(
+        &bot,
+        chat_id,
+        message_id,
+        cq.message,
+        callback_data.page,
+        HISTORY_HEADER,
+        |p| get_history_page(user_id, p),
+        callback_data,
+        get_download_again_rows,
+        PaginationTexts {
+            not_found: HISTORY_IS_EMPTY,
+            no_items: HISTORY_IS_EMPTY,
+            error_try_later: Some(ERROR_TRY_LATER),
+        },
+    )
+    .await
+}
+
+pub fn get_history_handler() -> crate::bots::BotHandler {
+    dptree::entry()
+        .branch(
+            Update::filter_message().branch(
+                dptree::entry()
+                    .filter_command::<HistoryCommand>()
+                    .endpoint(history_command_handler),
+            ),
+        )
+        .branch(
+            Update::filter_callback_query()
+                .chain(filter_callback_query::<HistoryCallbackData>())
+                .endpoint(history_pagination_handler),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn make_item() -> HistoryItem {
+        HistoryItem {
+            record: DownloadRecord {
+                book_id: 1,
+                file_type: "epub".to_string(),
+                downloaded_at: Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap(),
+            },
+            book: serde_json::from_value(serde_json::json!({
+                "id": 1,
+                "title": "Книга",
+                "lang": "ru",
+                "available_types": ["fb2", "epub"],
+                "annotation_exists": false,
+                "authors": [],
+                "translators": [],
+                "sequences": [],
+                "genres": [],
+                "year": 2024,
+                "pages": null,
+                "position": null
+            }))
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn item_starts_with_download_time_and_format() {
+        let FormatResult { result, .. } = make_item().format(1000);
+
+        assert!(result.starts_with("🕓 01.05.2024 12:30 UTC · epub\n"));
+        assert!(result.contains("Книга"));
+    }
+
+    #[test]
+    fn download_again_button_reuses_download_data() {
+        let rows = get_download_again_rows(&[make_item()]);
+
+        assert_eq!(rows[0][0].text, "📥 Книга (epub)");
+        assert!(matches!(
+            &rows[0][0].kind,
+            InlineKeyboardButtonKind::CallbackData(data) if data == "d_1_epub"
+        ));
+    }
+}
@@ -0,0 +1,304 @@
+pub mod query;
+
+use book_bot_macros::log_handler;
+
+use teloxide::{
+    adaptors::{CacheMe, Throttle},
+    prelude::*,
+    types::{
+        FileId, InlineQueryResult, InlineQueryResultArticle, InlineQueryResultCachedDocument,
+        InputMessageContent, InputMessageContentText,
+    },
+};
+
+use crate::bots::{
+    approved_bot::{
+        modules::{
+            download::{callback_data::DownloadQueryData, commands::StartDownloadCommand},
+            utils::{
+                constants::TELEGRAM_MESSAGE_MAX_LENGTH, filter_command::CommandParse,
+                telegram_utils::safe_answer_inline_query,
+            },
+        },
+        services::{
+            book_cache::get_cached_file_id,
+            book_library::{
+                formatters::{Format, FormatTitle},
+                get_book, search_author, search_book, search_sequence,
+                types::{Author, Book, Page, Person, SearchBook, Sequence},
+                DEFAULT_PAGE_SIZE,
+            },
+            user_settings::get_user_or_default_lang_codes,
+        },
+    },
+    BotHandlerInternal,
+};
+use crate::bots_manager::BotCache;
+
+use self::query::{next_offset, offset_to_page, InlineSearchKind, InlineSearchQuery};
+
+trait InlineArticle {
+    fn inline_article(&self) -> InlineQueryResultArticle;
+}
+
+fn article(id: String, title: String, item: &impl Format) -> InlineQueryResultArticle {
+    let text = item.format(TELEGRAM_MESSAGE_MAX_LENGTH).result;
+
+    InlineQueryResultArticle::new(
+        id,
+        title,
+        InputMessageContent::Text(InputMessageContentText::new(text)),
+    )
+}
+
+fn full_name(last_name: &str, first_name: &str, middle_name: &str) -> String {
+    [last_name, first_name, middle_name]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn people_names(people: &[Person]) -> String {
+    people
+        .iter()
+        .map(|p| full_name(&p.last_name, &p.first_name, &p.middle_name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl InlineArticle for SearchBook {
+    fn inline_article(&self) -> InlineQueryResultArticle {
+        let description = format!("{} | {}", people_names(&self.authors), self.lang);
+
+        article(format!("b_{}", self.id), self.title.clone(), self).description(description)
+    }
+}
+
+impl InlineArticle for Author {
+    fn inline_article(&self) -> InlineQueryResultArticle {
+        let title = full_name(&self.last_name, &self.first_name, &self.middle_name);
+
+        article(format!("a_{}", self.id), title, self)
+    }
+}
+
+impl InlineArticle for Sequence {
+    fn inline_article(&self) -> InlineQueryResultArticle {
+        article(format!("s_{}", self.id), self.name.clone(), self)
+    }
+}
+
+fn page_to_results<T, P>(page: Page<T, P>) -> (Vec<InlineQueryResult>, u32)
+where
+    T: InlineArticle,
+    P: FormatTitle,
+{
+    let results = page
+        .items
+        .iter()
+        .map(|item| InlineQueryResult::Article(item.inline_article()))
+        .collect();
+
+    (results, page.pages)
+}
+
+/// Cached documents for every format that already has a file_id for this
+/// bot, or a single article with the `/d_` command when none does.
+fn download_results(
+    book: &Book,
+    file_ids: Vec<(String, Option<String>)>,
+) -> Vec<InlineQueryResult> {
+    let documents: Vec<InlineQueryResult> = file_ids
+        .into_iter()
+        .filter_map(|(file_type, file_id)| {
+            let file_id = file_id?;
+
+            Some(InlineQueryResult::CachedDocument(
+                InlineQueryResultCachedDocument::new(
+                    format!("d_{}_{file_type}", book.id),
+                    format!("{} ({file_type})", book.title),
+                    FileId(file_id),
+                )
+                .description(people_names(&book.authors)),
+            ))
+        })
+        .collect();
+
+    if !documents.is_empty() {
+        return documents;
+    }
+
+    let command = StartDownloadCommand { id: book.id };
+
+    vec![InlineQueryResult::Article(
+        InlineQueryResultArticle::new(
+            format!("d_{}", book.id),
+            book.title.clone(),
+            InputMessageContent::Text(InputMessageContentText::new(format!(
+                "📖 {}\nСкачать: {command}",
+                book.title
+            ))),
+        )
+        .description(command.to_string()),
+    )]
+}
+
+async fn inline_download_handler(
+    inline_query: InlineQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    cache: BotCache,
+    command: StartDownloadCommand,
+) -> BotHandlerInternal {
+    let book = match get_book(command.id).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            return safe_answer_inline_query(&bot, inline_query.id, vec![], String::new()).await
+        }
+        Err(err) => {
+            safe_answer_inline_query(&bot, inline_query.id, vec![], String::new()).await?;
+            return Err(err);
+        }
+    };
+
+    // Without the shared cache there is nothing the bot could reuse.
+    let bot_id = match (cache, bot.get_me().await) {
+        (BotCache::NoCache, _) | (_, Err(_)) => None,
+        (_, Ok(me)) => Some(me.id.0),
+    };
+    let user_id = Some(inline_query.from.id.0);
+
+    let file_ids = futures::future::join_all(book.available_types.iter().map(|file_type| {
+        let download_data = DownloadQueryData::DownloadData {
+            book_id: book.id,
+            file_type: file_type.clone(),
+        };
+
+        async move {
+            let file_id = match bot_id {
+                Some(bot_id) => get_cached_file_id(&download_data, bot_id, user_id)
+                    .await
+                    .unwrap_or_else(|err| {
+                        tracing::warn!("Failed to get cached file_id: {err:?}");
+                        None
+                    })
+                    .map(|cached| cached.file_id),
+                None => None,
+            };
+
+            (file_type.clone(), file_id)
+        }
+    }))
+    .await;
+
+    let results = download_results(&book, file_ids);
+
+    safe_answer_inline_query(&bot, inline_query.id, results, String::new()).await
+}
+
+#[log_handler("inline")]
+async fn inline_query_handler(
+    inline_query: InlineQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    cache: BotCache,
+) -> BotHandlerInternal {
+    if let Ok(command) = StartDownloadCommand::parse(inline_query.query.trim()) {
+        return inline_download_handler(inline_query, bot, cache, command).await;
+    }
+
+    let Some(query) = InlineSearchQuery::parse(&inline_query.query) else {
+        return safe_answer_inline_query(&bot, inline_query.id, vec![], String::new()).await;
+    };
+
+    let page = offset_to_page(&inline_query.offset);
+    let allowed_langs = get_user_or_default_lang_codes(inline_query.from.id).await;
+
+    let result = match query.kind {
+        InlineSearchKind::Books => search_book(query.text, page, DEFAULT_PAGE_SIZE, allowed_langs)
+            .await
+            .map(|p| p.map(page_to_results)),
+        InlineSearchKind::Authors => {
+            search_author(query.text, page, DEFAULT_PAGE_SIZE, allowed_langs)
+                .await
+                .map(|p| p.map(page_to_results))
+        }
+        InlineSearchKind::Sequences => {
+            search_sequence(query.text, page, DEFAULT_PAGE_SIZE, allowed_langs)
+                .await
+                .map(|p| p.map(page_to_results))
+        }
+    };
+
+    let (results, pages) = match result {
+        Ok(Some(v)) => v,
+        Ok(None) => (vec![], 0),
+        Err(err) => {
+            safe_answer_inline_query(&bot, inline_query.id, vec![], String::new()).await?;
+            return Err(err);
+        }
+    };
+
+    safe_answer_inline_query(&bot, inline_query.id, results, next_offset(page, pages)).await
+}
+
+pub fn get_inline_handler() -> crate::bots::BotHandler {
+    dptree::entry().branch(Update::filter_inline_query().endpoint(inline_query_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::download_results;
+    use crate::bots::approved_bot::services::book_library::types::Book;
+    use teloxide::types::InlineQueryResult;
+
+    fn make_book() -> Book {
+        serde_json::from_value(serde_json::json!({
+            "id": 42,
+            "title": "Война и мир",
+            "lang": "ru",
+            "available_types": ["fb2", "epub"],
+            "annotation_exists": false,
+            "authors": [],
+            "translators": [],
+            "sequences": [],
+            "genres": [],
+            "year": 1869,
+            "pages": null,
+            "position": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn returns_cached_documents_for_known_file_ids() {
+        let results = download_results(
+            &make_book(),
+            vec![
+                ("fb2".to_string(), Some("file-fb2".to_string())),
+                ("epub".to_string(), None),
+            ],
+        );
+
+        assert_eq!(results.len(), 1);
+        match &results[0] {
+            InlineQueryResult::CachedDocument(doc) => {
+                assert_eq!(doc.id, "d_42_fb2");
+                assert_eq!(doc.document_file_id.0, "file-fb2");
+            }
+            _ => panic!("expected a cached document"),
+        }
+    }
+
+    #[test]
+    fn falls_back_to_download_command_article() {
+        let results = download_results(&make_book(), vec![("fb2".to_string(), None)]);
+
+        assert_eq!(results.len(), 1);
+        match &results[0] {
+            InlineQueryResult::Article(article) => {
+                assert_eq!(article.description.as_deref(), Some("/d_42"))
+            }
+            _ => panic!("expected an article"),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+/// What an inline query searches for, selected by an optional prefix
+/// (`a:` for authors, `s:` for sequences, books otherwise).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InlineSearchKind {
+    Books,
+    Authors,
+    Sequences,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InlineSearchQuery {
+    pub kind: InlineSearchKind,
+    pub text: String,
+}
+
+// Cyrillic look-alikes are accepted so the prefix works without
+// switching the keyboard layout.
+const AUTHOR_PREFIXES: [&str; 2] = ["a:", "а:"];
+const SEQUENCE_PREFIXES: [&str; 2] = ["s:", "с:"];
+
+impl InlineSearchQuery {
+    pub fn parse(query: &str) -> Option<Self> {
+        let query = query.trim();
+
+        let strip = |prefixes: &[&str]| {
+            prefixes.iter().find_map(|prefix| {
+                query
+                    .get(..prefix.len())
+                    .filter(|head| head.eq_ignore_ascii_case(prefix))
+                    .map(|_| &query[prefix.len()..])
+            })
+        };
+
+        let (kind, text) = if let Some(rest) = strip(&AUTHOR_PREFIXES) {
+            (InlineSearchKind::Authors, rest)
+        } else if let Some(rest) = strip(&SEQUENCE_PREFIXES) {
+            (InlineSearchKind::Sequences, rest)
+        } else {
+            (InlineSearchKind::Books, query)
+        };
+
+        let text = text.trim().replace(['/', '&', '?'], "");
+
+        if text.is_empty() {
+            return None;
+        }
+
+        Some(Self { kind, text })
+    }
+}
+
+/// Maps the inline `offset` onto a library page. Telegram sends an empty
+/// offset for the first request.
+pub fn offset_to_page(offset: &str) -> u32 {
+    offset.parse::<u32>().unwrap_or(1).max(1)
+}
+
+/// Offset for the next portion of results, empty when there is none.
+pub fn next_offset(page: u32, total_pages: u32) -> String {
+    if page < total_pages {
+        (page + 1).to_string()
+    } else {
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_query_searches_books() {
+        let q = InlineSearchQuery::parse("  война и мир ").unwrap();
+        assert_eq!(q.kind, InlineSearchKind::Books);
+        assert_eq!(q.text, "война и мир");
+    }
+
+    #[test]
+    fn author_prefix_latin_and_cyrillic() {
+        for raw in ["a: Толстой", "A:Толстой", "а: Толстой"] {
+            let q = InlineSearchQuery::parse(raw).unwrap();
+            assert_eq!(q.kind, InlineSearchKind::Authors);
+            assert_eq!(q.text, "Толстой");
+        }
+    }
+
+    #[test]
+    fn sequence_prefix() {
+        let q = InlineSearchQuery::parse("s: Дюна").unwrap();
+        assert_eq!(q.kind, InlineSearchKind::Sequences);
+        assert_eq!(q.text, "Дюна");
+    }
+
+    #[test]
+    fn empty_query_is_none() {
+        assert!(InlineSearchQuery::parse("").is_none());
+        assert!(InlineSearchQuery::parse("a:  ").is_none());
+    }
+
+    #[test]
+    fn strips_url_breaking_characters() {
+        let q = InlineSearchQuery::parse("what?/&").unwrap();
+        assert_eq!(q.text, "what");
+    }
+
+    #[test]
+    fn offset_parsing() {
+        assert_eq!(offset_to_page(""), 1);
+        assert_eq!(offset_to_page("0"), 1);
+        assert_eq!(offset_to_page("3"), 3);
+        assert_eq!(offset_to_page("junk"), 1);
+    }
+
+    #[test]
+    fn next_offset_stops_on_last_page() {
+        assert_eq!(next_offset(1, 3), "2");
+        assert_eq!(next_offset(3, 3), "");
+        assert_eq!(next_offset(1, 0), "");
+    }
+}
@@ -1,10 +1,18 @@
 pub mod annotations;
 pub mod book;
+pub mod book_card;
 pub mod download;
+pub mod genres;
 pub mod help;
+pub mod history;
+pub mod inline;
+pub mod page_input;
 pub mod random;
 pub mod search;
+pub mod selection;
 pub mod settings;
+pub mod shelf;
+pub mod subscriptions;
 pub mod support;
 pub mod update_history;
 pub mod utils;
@@ -0,0 +1,132 @@
+use std::{fmt::Display, str::FromStr};
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::bots::approved_bot::modules::utils::errors::CallbackQueryParseError;
+
+static RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^pg_(?P<page>\d+)_(?P<pages>\d+)_(?P<input>\d*)_(?P<template>[a-z].*)$").unwrap()
+});
+
+/// State of the page number keypad under a listing. `template` is the
+/// listing's pagination callback data without the page number, see
+/// `pagination::get_page_template`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PageInputCallbackData {
+    /// The page shown when the keypad was opened.
+    pub page: u32,
+    pub pages: u32,
+    /// Digits typed so far.
+    pub input: String,
+    pub template: String,
+}
+
+impl PageInputCallbackData {
+    /// The listing's callback data for `page`.
+    pub fn page_callback_data(&self, page: u32) -> String {
+        format!("{}{page}", self.template)
+    }
+
+    /// The keypad with `digit` typed. Input longer than the number of pages
+    /// or starting with zero is not accepted.
+    pub fn with_digit(&self, digit: u32) -> Self {
+        let mut input = self.input.clone();
+
+        if !(input.is_empty() && digit == 0) && input.len() < self.pages.to_string().len() {
+            input.push_str(&digit.to_string());
+        }
+
+        PageInputCallbackData {
+            input,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_last_digit_removed(&self) -> Self {
+        let mut input = self.input.clone();
+        input.pop();
+
+        PageInputCallbackData {
+            input,
+            ..self.clone()
+        }
+    }
+
+    /// The typed page, clamped to the listing.
+    pub fn target_page(&self) -> Option<u32> {
+        let page: u32 = self.input.parse().ok()?;
+
+        Some(page.clamp(1, self.pages))
+    }
+}
+
+impl FromStr for PageInputCallbackData {
+    type Err = CallbackQueryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let caps = RE.captures(s).ok_or(CallbackQueryParseError)?;
+
+        let page: u32 = caps["page"].parse().map_err(|_| CallbackQueryParseError)?;
+        let pages: u32 = caps["pages"].parse().map_err(|_| CallbackQueryParseError)?;
+
+        Ok(PageInputCallbackData {
+            page,
+            pages,
+            input: caps["input"].to_string(),
+            template: caps["template"].to_string(),
+        })
+    }
+}
+
+impl Display for PageInputCallbackData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "pg_{}_{}_{}_{}",
+            self.page, self.pages, self.input, self.template
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypad(input: &str) -> PageInputCallbackData {
+        PageInputCallbackData {
+            page: 3,
+            pages: 120,
+            input: input.to_string(),
+            template: "sb_k2x9a1_".to_string(),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for data in [keypad(""), keypad("42")] {
+            assert_eq!(
+                PageInputCallbackData::from_str(&data.to_string()).unwrap(),
+                data
+            );
+        }
+
+        assert_eq!(keypad("").to_string(), "pg_3_120__sb_k2x9a1_");
+    }
+
+    #[test]
+    fn typing_is_limited_to_page_count_digits() {
+        assert_eq!(keypad("").with_digit(0).input, "");
+        assert_eq!(keypad("1").with_digit(0).input, "10");
+        assert_eq!(keypad("100").with_digit(5).input, "100");
+        assert_eq!(keypad("42").with_last_digit_removed().input, "4");
+    }
+
+    #[test]
+    fn target_page_is_clamped() {
+        assert_eq!(keypad("").target_page(), None);
+        assert_eq!(keypad("42").target_page(), Some(42));
+        assert_eq!(keypad("999").target_page(), Some(120));
+        assert_eq!(keypad("42").page_callback_data(42), "sb_k2x9a1_42");
+    }
+}
@@ -0,0 +1,73 @@
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardButtonKind, InlineKeyboardMarkup};
+
+use super::callback_data::PageInputCallbackData;
+
+fn button(text: impl Into<String>, callback_data: String) -> InlineKeyboardButton {
+    InlineKeyboardButton {
+        text: text.into(),
+        kind: InlineKeyboardButtonKind::CallbackData(callback_data),
+    }
+}
+
+/// Digits, erase and "go" buttons. Go and cancel carry the listing's own
+/// callback data, so its handler shows the page and restores the page row.
+pub fn get_page_input_keyboard(data: &PageInputCallbackData) -> InlineKeyboardMarkup {
+    let display = match data.input.as_str() {
+        "" => "…",
+        input => input,
+    };
+
+    let mut inline_keyboard = vec![vec![button(
+        format!("Страница {display} из {}", data.pages),
+        data.to_string(),
+    )]];
+
+    inline_keyboard.extend([[1, 2, 3], [4, 5, 6], [7, 8, 9]].map(|row| {
+        row.map(|digit| button(digit.to_string(), data.with_digit(digit).to_string()))
+            .to_vec()
+    }));
+
+    let mut last_row = vec![
+        button("⌫", data.with_last_digit_removed().to_string()),
+        button("0", data.with_digit(0).to_string()),
+    ];
+    if let Some(target_page) = data.target_page() {
+        last_row.push(button("➡️", data.page_callback_data(target_page)));
+    }
+    inline_keyboard.push(last_row);
+
+    inline_keyboard.push(vec![button("← Отмена", data.page_callback_data(data.page))]);
+
+    InlineKeyboardMarkup { inline_keyboard }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn callback_data(button: &InlineKeyboardButton) -> &str {
+        match &button.kind {
+            InlineKeyboardButtonKind::CallbackData(data) => data,
+            _ => panic!("not a callback button"),
+        }
+    }
+
+    #[test]
+    fn go_button_appears_once_a_page_is_typed() {
+        let mut data = PageInputCallbackData {
+            page: 2,
+            pages: 30,
+            input: String::new(),
+            template: "history_".to_string(),
+        };
+
+        let keyboard = get_page_input_keyboard(&data);
+        assert_eq!(keyboard.inline_keyboard[4].len(), 2);
+        assert_eq!(callback_data(&keyboard.inline_keyboard[5][0]), "history_2");
+
+        data.input = "17".to_string();
+        let keyboard = get_page_input_keyboard(&data);
+        assert_eq!(keyboard.inline_keyboard[0][0].text, "Страница 17 из 30");
+        assert_eq!(callback_data(&keyboard.inline_keyboard[4][2]), "history_17");
+    }
+}
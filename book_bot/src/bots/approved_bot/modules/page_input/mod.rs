@@ -0,0 +1,51 @@
+pub mod callback_data;
+pub mod keyboards;
+
+use book_bot_macros::log_handler;
+use teloxide::{
+    adaptors::{CacheMe, Throttle},
+    prelude::*,
+};
+
+use crate::bots::{
+    approved_bot::{
+        modules::utils::telegram_utils::{
+            safe_answer_callback_query, safe_edit_message_reply_markup,
+        },
+        tools::filter_callback_query,
+    },
+    BotHandlerInternal,
+};
+
+use self::{callback_data::PageInputCallbackData, keyboards::get_page_input_keyboard};
+
+/// Redraws the page number keypad of any listing; the listing's own
+/// handler takes over once a page is chosen.
+#[log_handler("page_input")]
+async fn page_input_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    callback_data: PageInputCallbackData,
+) -> BotHandlerInternal {
+    let Some(message) = cq.message.as_ref() else {
+        return Ok(());
+    };
+
+    safe_edit_message_reply_markup(
+        &bot,
+        message.chat().id,
+        message.id(),
+        get_page_input_keyboard(&callback_data),
+    )
+    .await?;
+
+    safe_answer_callback_query(&bot, cq.id).await
+}
+
+pub fn get_page_input_handler() -> crate::bots::BotHandler {
+    dptree::entry().branch(
+        Update::filter_callback_query()
+            .chain(filter_callback_query::<PageInputCallbackData>())
+            .endpoint(page_input_handler),
+    )
+}
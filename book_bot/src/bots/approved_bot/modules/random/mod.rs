@@ -13,6 +13,7 @@ use teloxide::{
 
 use crate::bots::{
     approved_bot::{
+        modules::genres::keyboards::{get_genre_metas_keyboard, get_genres_keyboard},
         modules::random::callback_data::RandomCallbackData,
         modules::utils::telegram_utils::{
             safe_edit_message_reply_markup, safe_send_message, safe_send_message_with_reply,
@@ -169,23 +170,9 @@ async fn get_genre_metas_handler(
         }
     };
 
-    let keyboard = InlineKeyboardMarkup {
-        inline_keyboard: genre_metas
-            .into_iter()
-            .enumerate()
-            .map(|(index, genre_meta)| {
-                vec![InlineKeyboardButton {
-                    kind: teloxide::types::InlineKeyboardButtonKind::CallbackData(
-                        RandomCallbackData::Genres {
-                            index: index as u32,
-                        }
-                        .to_string(),
-                    ),
-                    text: genre_meta,
-                }]
-            })
-            .collect(),
-    };
+    let keyboard = get_genre_metas_keyboard(genre_metas, |index| {
+        RandomCallbackData::Genres { index }.to_string()
+    });
 
     safe_edit_message_reply_markup(&bot, message.chat().id, message.id(), keyboard).await?;
 
@@ -225,29 +212,11 @@ async fn get_genres_by_meta_handler(
         Err(err) => return Err(err),
     };
 
-    let mut buttons: Vec<Vec<InlineKeyboardButton>> = genres_page
-        .items
-        .into_iter()
-        .map(|genre| {
-            vec![InlineKeyboardButton {
-                kind: teloxide::types::InlineKeyboardButtonKind::CallbackData(
-                    RandomCallbackData::RandomBookByGenre { id: genre.id }.to_string(),
-                ),
-                text: genre.description,
-            }]
-        })
-        .collect();
-
-    buttons.push(vec![InlineKeyboardButton {
-        kind: teloxide::types::InlineKeyboardButtonKind::CallbackData(
-            RandomCallbackData::RandomBookByGenreRequest.to_string(),
-        ),
-        text: "< Назад >".to_string(),
-    }]);
-
-    let keyboard = InlineKeyboardMarkup {
-        inline_keyboard: buttons,
-    };
+    let keyboard = get_genres_keyboard(
+        genres_page.items,
+        |id| RandomCallbackData::RandomBookByGenre { id }.to_string(),
+        RandomCallbackData::RandomBookByGenreRequest.to_string(),
+    );
 
     let message = match cq.message {
         Some(message) => message,
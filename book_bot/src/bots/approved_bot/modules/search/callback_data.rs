@@ -8,24 +8,118 @@ use crate::bots::approved_bot::{
     services::user_settings::DefaultSearchType,
 };
 
-static RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^(?P<search_type>s[abst])_(?P<page>\d+)$").unwrap());
+static RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:(?P<search_type>s[abst])_(?:(?P<qid>[0-9a-z]+)_)?(?P<page>\d+)|s_(?P<view>all|orig)(?:_(?P<view_qid>[0-9a-z]+))?)$",
+    )
+    .unwrap()
+});
 
-#[derive(Clone)]
+/// `qid` is the id of the query in `services::search_queries`. Buttons
+/// sent before queries were stored have none; their query is recovered
+/// from the replied-to message, see `utils::get_query`.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SearchCallbackData {
-    Book { page: u32 },
-    Authors { page: u32 },
-    Sequences { page: u32 },
-    Translators { page: u32 },
+    Book {
+        qid: Option<String>,
+        page: u32,
+    },
+    Authors {
+        qid: Option<String>,
+        page: u32,
+    },
+    Sequences {
+        qid: Option<String>,
+        page: u32,
+    },
+    Translators {
+        qid: Option<String>,
+        page: u32,
+    },
+    /// Top hits of every search type at once.
+    All {
+        qid: Option<String>,
+    },
+    /// Search type choice for the typed query after results were shown
+    /// for a normalized variant of it.
+    Original {
+        qid: Option<String>,
+    },
+}
+
+impl SearchCallbackData {
+    pub fn page(&self) -> u32 {
+        match self {
+            SearchCallbackData::Book { page, .. }
+            | SearchCallbackData::Authors { page, .. }
+            | SearchCallbackData::Sequences { page, .. }
+            | SearchCallbackData::Translators { page, .. } => *page,
+            SearchCallbackData::All { .. } | SearchCallbackData::Original { .. } => 1,
+        }
+    }
+
+    pub fn query_id(&self) -> Option<&str> {
+        match self {
+            SearchCallbackData::Book { qid, .. }
+            | SearchCallbackData::Authors { qid, .. }
+            | SearchCallbackData::Sequences { qid, .. }
+            | SearchCallbackData::Translators { qid, .. }
+            | SearchCallbackData::All { qid }
+            | SearchCallbackData::Original { qid } => qid.as_deref(),
+        }
+    }
+
+    /// The same search type and page for another stored query.
+    pub fn with_query_id(&self, query_id: &str) -> Self {
+        let mut result = self.clone();
+
+        match &mut result {
+            SearchCallbackData::Book { qid, .. }
+            | SearchCallbackData::Authors { qid, .. }
+            | SearchCallbackData::Sequences { qid, .. }
+            | SearchCallbackData::Translators { qid, .. }
+            | SearchCallbackData::All { qid }
+            | SearchCallbackData::Original { qid } => *qid = Some(query_id.to_string()),
+        }
+
+        result
+    }
+
+    fn with_page(&self, target_page: u32) -> Self {
+        let mut result = self.clone();
+
+        match &mut result {
+            SearchCallbackData::Book { page, .. }
+            | SearchCallbackData::Authors { page, .. }
+            | SearchCallbackData::Sequences { page, .. }
+            | SearchCallbackData::Translators { page, .. } => *page = target_page,
+            SearchCallbackData::All { .. } | SearchCallbackData::Original { .. } => {}
+        }
+
+        result
+    }
 }
 
 impl Display for SearchCallbackData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prefix = match self {
+            SearchCallbackData::Book { .. } => "sb",
+            SearchCallbackData::Authors { .. } => "sa",
+            SearchCallbackData::Sequences { .. } => "ss",
+            SearchCallbackData::Translators { .. } => "st",
+            SearchCallbackData::All { .. } => "s_all",
+            SearchCallbackData::Original { .. } => "s_orig",
+        };
+
+        write!(f, "{prefix}")?;
+
+        if let Some(qid) = self.query_id() {
+            write!(f, "_{qid}")?;
+        }
+
         match self {
-            SearchCallbackData::Book { page } => write!(f, "sb_{page}"),
-            SearchCallbackData::Authors { page } => write!(f, "sa_{page}"),
-            SearchCallbackData::Sequences { page } => write!(f, "ss_{page}"),
-            SearchCallbackData::Translators { page } => write!(f, "st_{page}"),
+            SearchCallbackData::All { .. } | SearchCallbackData::Original { .. } => Ok(()),
+            _ => write!(f, "_{}", self.page()),
         }
     }
 }
@@ -36,7 +130,17 @@ impl FromStr for SearchCallbackData {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let caps = RE.captures(s).ok_or(strum::ParseError::VariantNotFound)?;
 
+        if let Some(view) = caps.name("view") {
+            let qid = caps.name("view_qid").map(|qid| qid.as_str().to_string());
+
+            return match view.as_str() {
+                "all" => Ok(SearchCallbackData::All { qid }),
+                _ => Ok(SearchCallbackData::Original { qid }),
+            };
+        }
+
         let search_type = &caps["search_type"];
+        let qid = caps.name("qid").map(|qid| qid.as_str().to_string());
         let page: u32 = caps["page"]
             .parse()
             .map_err(|_| strum::ParseError::VariantNotFound)?;
@@ -45,86 +149,123 @@ impl FromStr for SearchCallbackData {
         let page: u32 = std::cmp::max(1, page);
 
         match search_type {
-            "sb" => Ok(SearchCallbackData::Book { page }),
-            "sa" => Ok(SearchCallbackData::Authors { page }),
-            "ss" => Ok(SearchCallbackData::Sequences { page }),
-            "st" => Ok(SearchCallbackData::Translators { page }),
+            "sb" => Ok(SearchCallbackData::Book { qid, page }),
+            "sa" => Ok(SearchCallbackData::Authors { qid, page }),
+            "ss" => Ok(SearchCallbackData::Sequences { qid, page }),
+            "st" => Ok(SearchCallbackData::Translators { qid, page }),
             _ => Err(strum::ParseError::VariantNotFound),
         }
     }
 }
 
 /// Converts default search type to SearchCallbackData with page 1.
-pub fn default_search_to_callback_data(t: DefaultSearchType) -> SearchCallbackData {
+pub fn default_search_to_callback_data(t: DefaultSearchType, qid: &str) -> SearchCallbackData {
+    let qid = Some(qid.to_string());
+
     match t {
-        DefaultSearchType::Book => SearchCallbackData::Book { page: 1 },
-        DefaultSearchType::Author => SearchCallbackData::Authors { page: 1 },
-        DefaultSearchType::Series => SearchCallbackData::Sequences { page: 1 },
-        DefaultSearchType::Translator => SearchCallbackData::Translators { page: 1 },
+        DefaultSearchType::Book => SearchCallbackData::Book { qid, page: 1 },
+        DefaultSearchType::Author => SearchCallbackData::Authors { qid, page: 1 },
+        DefaultSearchType::Series => SearchCallbackData::Sequences { qid, page: 1 },
+        DefaultSearchType::Translator => SearchCallbackData::Translators { qid, page: 1 },
+        DefaultSearchType::All => SearchCallbackData::All { qid },
     }
 }
 
 impl GetPaginationCallbackData for SearchCallbackData {
     fn get_pagination_callback_data(&self, target_page: u32) -> String {
-        match self {
-            SearchCallbackData::Book { .. } => SearchCallbackData::Book { page: target_page },
-            SearchCallbackData::Authors { .. } => SearchCallbackData::Authors { page: target_page },
-            SearchCallbackData::Sequences { .. } => {
-                SearchCallbackData::Sequences { page: target_page }
-            }
-            SearchCallbackData::Translators { .. } => {
-                SearchCallbackData::Translators { page: target_page }
-            }
-        }
-        .to_string()
+        self.with_page(target_page).to_string()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::SearchCallbackData;
+    use crate::bots::approved_bot::modules::utils::pagination::GetPaginationCallbackData;
     use std::str::FromStr;
 
     #[test]
     fn round_trip_book() {
-        let cd = SearchCallbackData::Book { page: 3 };
+        let cd = SearchCallbackData::Book { qid: None, page: 3 };
         match SearchCallbackData::from_str(&cd.to_string()).unwrap() {
-            SearchCallbackData::Book { page } => assert_eq!(page, 3),
+            SearchCallbackData::Book { page, .. } => assert_eq!(page, 3),
             _ => panic!("wrong variant"),
         }
     }
 
     #[test]
     fn round_trip_authors() {
-        let cd = SearchCallbackData::Authors { page: 4 };
+        let cd = SearchCallbackData::Authors { qid: None, page: 4 };
         match SearchCallbackData::from_str(&cd.to_string()).unwrap() {
-            SearchCallbackData::Authors { page } => assert_eq!(page, 4),
+            SearchCallbackData::Authors { page, .. } => assert_eq!(page, 4),
             _ => panic!("wrong variant"),
         }
     }
 
     #[test]
     fn round_trip_sequences() {
-        let cd = SearchCallbackData::Sequences { page: 5 };
+        let cd = SearchCallbackData::Sequences { qid: None, page: 5 };
         match SearchCallbackData::from_str(&cd.to_string()).unwrap() {
-            SearchCallbackData::Sequences { page } => assert_eq!(page, 5),
+            SearchCallbackData::Sequences { page, .. } => assert_eq!(page, 5),
             _ => panic!("wrong variant"),
         }
     }
 
     #[test]
     fn round_trip_translators() {
-        let cd = SearchCallbackData::Translators { page: 6 };
+        let cd = SearchCallbackData::Translators { qid: None, page: 6 };
         match SearchCallbackData::from_str(&cd.to_string()).unwrap() {
-            SearchCallbackData::Translators { page } => assert_eq!(page, 6),
+            SearchCallbackData::Translators { page, .. } => assert_eq!(page, 6),
             _ => panic!("wrong variant"),
         }
     }
 
+    #[test]
+    fn round_trip_all() {
+        for cd in [
+            SearchCallbackData::All { qid: None },
+            SearchCallbackData::All {
+                qid: Some("k2x9a1".to_string()),
+            },
+            SearchCallbackData::Original { qid: None },
+            SearchCallbackData::Original {
+                qid: Some("k2x9a1".to_string()),
+            },
+        ] {
+            assert_eq!(SearchCallbackData::from_str(&cd.to_string()).unwrap(), cd);
+        }
+    }
+
+    #[test]
+    fn round_trip_with_query_id() {
+        let cd = SearchCallbackData::Book {
+            qid: Some("k2x9a1".to_string()),
+            page: 7,
+        };
+
+        assert_eq!(cd.to_string(), "sb_k2x9a1_7");
+        assert_eq!(SearchCallbackData::from_str("sb_k2x9a1_7").unwrap(), cd);
+        assert_eq!(cd.get_pagination_callback_data(8), "sb_k2x9a1_8");
+    }
+
+    #[test]
+    fn numeric_query_id_is_not_taken_for_page() {
+        assert_eq!(
+            SearchCallbackData::from_str("sa_123_2").unwrap(),
+            SearchCallbackData::Authors {
+                qid: Some("123".to_string()),
+                page: 2
+            }
+        );
+        assert_eq!(
+            SearchCallbackData::from_str("sa_2").unwrap(),
+            SearchCallbackData::Authors { qid: None, page: 2 }
+        );
+    }
+
     #[test]
     fn page_zero_normalized_to_one() {
         match SearchCallbackData::from_str("sb_0").unwrap() {
-            SearchCallbackData::Book { page } => assert_eq!(page, 1),
+            SearchCallbackData::Book { page, .. } => assert_eq!(page, 1),
             _ => panic!("wrong variant"),
         }
     }
@@ -0,0 +1,217 @@
+use smallvec::SmallVec;
+use smartstring::alias::String as SmartString;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardButtonKind, InlineKeyboardMarkup};
+
+use crate::bots::approved_bot::{
+    modules::utils::constants::TELEGRAM_MESSAGE_MAX_LENGTH,
+    services::book_library::{
+        formatters::Format,
+        search_author, search_book, search_sequence, search_translator,
+        types::{Empty, Page},
+        DEFAULT_PAGE_SIZE,
+    },
+};
+
+use super::callback_data::SearchCallbackData;
+
+/// Results shown per search type in the combined view.
+const TOP_HITS: usize = 3;
+const SECTIONS_COUNT: usize = 4;
+const SECTIONS_SEPARATOR: &str = "\n\n\n";
+
+/// One search type of the combined view: its top hits and a button
+/// leading to the full listing.
+struct Section {
+    text: String,
+    more_button: InlineKeyboardButton,
+}
+
+fn get_section<T>(
+    title: &str,
+    more_text: &str,
+    page: Option<Page<T, Empty>>,
+    more: SearchCallbackData,
+    max_size: usize,
+) -> Option<Section>
+where
+    T: Format,
+{
+    let page = page.filter(|page| page.pages > 0 && !page.items.is_empty())?;
+
+    // The book server reports `total` for every page; fall back to the
+    // number of items shown should it ever be missing.
+    let total = std::cmp::max(page.total as usize, page.items.len());
+    let items = &page.items[..std::cmp::min(page.items.len(), TOP_HITS)];
+
+    let header = format!("{title} ({total}):\n\n");
+    let item_size = max_size.saturating_sub(header.len()) / items.len();
+
+    let formatted_items = items
+        .iter()
+        .map(|item| item.format(item_size).result)
+        .collect::<Vec<String>>()
+        .join("\n\n");
+
+    Some(Section {
+        text: format!("{header}{formatted_items}"),
+        more_button: InlineKeyboardButton {
+            text: format!("{more_text} ({total}) →"),
+            kind: InlineKeyboardButtonKind::CallbackData(more.to_string()),
+        },
+    })
+}
+
+/// Searches books, authors, series and translators concurrently. Returns
+/// the message text with every non-empty type's top hits and "more"
+/// buttons dropping into the per-type pagination, or `None` if nothing
+/// was found at all.
+pub async fn search_all(
+    query: String,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+    qid: Option<&str>,
+) -> anyhow::Result<Option<(String, InlineKeyboardMarkup)>> {
+    let qid = qid.map(str::to_string);
+
+    let (books, authors, sequences, translators) = tokio::try_join!(
+        search_book(query.clone(), 1, DEFAULT_PAGE_SIZE, allowed_langs.clone()),
+        search_author(query.clone(), 1, DEFAULT_PAGE_SIZE, allowed_langs.clone()),
+        search_sequence(query.clone(), 1, DEFAULT_PAGE_SIZE, allowed_langs.clone()),
+        search_translator(query, 1, DEFAULT_PAGE_SIZE, allowed_langs),
+    )?;
+
+    let section_size = TELEGRAM_MESSAGE_MAX_LENGTH
+        .saturating_sub(SECTIONS_SEPARATOR.len() * (SECTIONS_COUNT - 1))
+        / SECTIONS_COUNT;
+
+    let sections: Vec<Section> = [
+        get_section(
+            "📚 Книги",
+            "Все книги",
+            books,
+            SearchCallbackData::Book {
+                qid: qid.clone(),
+                page: 1,
+            },
+            section_size,
+        ),
+        get_section(
+            "👤 Авторы",
+            "Все авторы",
+            authors,
+            SearchCallbackData::Authors {
+                qid: qid.clone(),
+                page: 1,
+            },
+            section_size,
+        ),
+        get_section(
+            "📖 Серии",
+            "Все серии",
+            sequences,
+            SearchCallbackData::Sequences {
+                qid: qid.clone(),
+                page: 1,
+            },
+            section_size,
+        ),
+        get_section(
+            "🌐 Переводчики",
+            "Все переводчики",
+            translators,
+            SearchCallbackData::Translators { qid, page: 1 },
+            section_size,
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    if sections.is_empty() {
+        return Ok(None);
+    }
+
+    let text = sections
+        .iter()
+        .map(|section| section.text.as_str())
+        .collect::<Vec<&str>>()
+        .join(SECTIONS_SEPARATOR);
+
+    let keyboard = InlineKeyboardMarkup {
+        inline_keyboard: sections
+            .into_iter()
+            .map(|section| vec![section.more_button])
+            .collect(),
+    };
+
+    Ok(Some((text, keyboard)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::approved_bot::services::book_library::formatters::FormatResult;
+
+    #[derive(Clone, Debug)]
+    struct FakeItem(&'static str);
+
+    impl Format for FakeItem {
+        fn format(&self, _max_size: usize) -> FormatResult {
+            FormatResult {
+                result: self.0.to_string(),
+                current_size: self.0.len(),
+                max_size: self.0.len(),
+            }
+        }
+    }
+
+    fn make_page(items: Vec<FakeItem>, total: u32) -> Page<FakeItem, Empty> {
+        Page {
+            pages: total.div_ceil(5),
+            items,
+            total,
+            parent_item: None,
+        }
+    }
+
+    #[test]
+    fn section_shows_top_hits_and_total() {
+        let page = make_page(
+            vec![FakeItem("a"), FakeItem("b"), FakeItem("c"), FakeItem("d")],
+            12,
+        );
+
+        let section = get_section(
+            "📚 Книги",
+            "Все книги",
+            Some(page),
+            SearchCallbackData::Book {
+                qid: Some("k2x9a1".to_string()),
+                page: 1,
+            },
+            1000,
+        )
+        .unwrap();
+
+        assert_eq!(section.text, "📚 Книги (12):\n\na\n\nb\n\nc");
+        assert_eq!(section.more_button.text, "Все книги (12) →");
+        assert_eq!(
+            section.more_button.kind,
+            InlineKeyboardButtonKind::CallbackData("sb_k2x9a1_1".to_string())
+        );
+    }
+
+    #[test]
+    fn empty_results_have_no_section() {
+        let more = || SearchCallbackData::Authors { qid: None, page: 1 };
+
+        assert!(get_section::<FakeItem>("👤 Авторы", "Все авторы", None, more(), 1000).is_none());
+        assert!(get_section(
+            "👤 Авторы",
+            "Все авторы",
+            Some(make_page(vec![], 0)),
+            more(),
+            1000
+        )
+        .is_none());
+    }
+}
@@ -1,4 +1,7 @@
 pub mod callback_data;
+pub mod combined;
+pub mod commands;
+pub mod query;
 pub mod utils;
 
 use book_bot_macros::log_handler;
@@ -18,14 +21,20 @@ use teloxide::{
 
 use crate::bots::{
     approved_bot::{
-        modules::utils::telegram_utils::{safe_send_message, safe_send_message_with_reply},
+        modules::utils::telegram_utils::{
+            safe_edit_message_text, safe_send_message, safe_send_message_with_reply,
+        },
         services::{
             book_library::{
                 formatters::{Format, FormatTitle},
-                search_author, search_book, search_sequence, search_translator,
-                types::{Empty, Page},
+                search_author, search_sequence, search_translator,
+                types::{Empty, Page, SearchBook},
+            },
+            search_queries::save_search_query,
+            user_settings::{
+                get_user_default_search, get_user_or_default_lang_codes, get_user_page_size,
+                DefaultSearchType,
             },
-            user_settings::{get_user_default_search, get_user_or_default_lang_codes},
         },
         tools::filter_callback_query,
     },
@@ -34,9 +43,15 @@ use crate::bots::{
 
 use self::{
     callback_data::{default_search_to_callback_data, SearchCallbackData},
-    utils::get_query,
+    combined::search_all,
+    commands::SearchCommand,
+    query::{parse_search_query, search_book_by_query, SEARCH_HELP},
+    utils::{corrected_query_header, get_corrected_query, query_variants, resolve_query},
 };
 
+use super::selection::{callback_data::SelectionList, get_select_button};
+use super::shelf::get_add_to_shelf_rows;
+use super::utils::keyboard::short_title;
 use super::utils::pagination::{generic_get_pagination_keyboard, paginate, PaginationTexts};
 
 #[log_handler("search")]
@@ -44,7 +59,13 @@ async fn generic_search_pagination_handler<T, P, Fut>(
     cq: CallbackQuery,
     bot: CacheMe<Throttle<Bot>>,
     search_data: SearchCallbackData,
-    items_getter: fn(query: String, page: u32, allowed_langs: SmallVec<[SmartString; 3]>) -> Fut,
+    items_getter: fn(
+        query: String,
+        page: u32,
+        page_size: u32,
+        allowed_langs: SmallVec<[SmartString; 3]>,
+    ) -> Fut,
+    item_rows: ItemRows<T>,
 ) -> BotHandlerInternal
 where
     T: Format + Clone + Debug,
@@ -54,7 +75,7 @@ where
     let chat_id = cq.chat_id();
     let user_id = cq.from.id;
     let message_id = cq.message.as_ref().map(|message| message.id());
-    let query = get_query(&cq);
+    let query = resolve_query(&cq, search_data.query_id()).await;
 
     let (chat_id, query, message_id) = match (chat_id, query, message_id) {
         (Some(chat_id), Some(query), Some(message_id)) => (chat_id, query, message_id),
@@ -67,31 +88,41 @@ where
         }
     };
 
+    // Buttons from before queries were stored get the recovered query
+    // stored, so the select button below has an id to refer to.
+    let search_data = match search_data.query_id() {
+        Some(_) => search_data,
+        None => search_data.with_query_id(&save_search_query(user_id.0, &query).await),
+    };
+
     let allowed_langs = get_user_or_default_lang_codes(user_id).await;
+    let page_size = get_user_page_size(user_id).await;
 
-    let page = match search_data {
-        SearchCallbackData::Book { page } => page,
-        SearchCallbackData::Authors { page } => page,
-        SearchCallbackData::Sequences { page } => page,
-        SearchCallbackData::Translators { page } => page,
-    };
+    let page = search_data.page();
 
     let not_found_text = match search_data {
         SearchCallbackData::Book { .. } => BOOKS_NOT_FOUND,
         SearchCallbackData::Authors { .. } => AUTHORS_NOT_FOUND,
         SearchCallbackData::Sequences { .. } => SEQUENCES_NOT_FOUND,
         SearchCallbackData::Translators { .. } => TRANSLATORS_NOT_FOUND,
+        SearchCallbackData::All { .. } | SearchCallbackData::Original { .. } => NOTHING_FOUND,
     };
 
+    let header = get_corrected_query(&cq)
+        .map(|query| corrected_query_header(&query))
+        .unwrap_or_default();
+    let rows_data = search_data.clone();
+
     paginate(
         &bot,
         chat_id,
         message_id,
         cq.message,
         page,
-        "",
-        |p| items_getter(query.clone(), p, allowed_langs.clone()),
+        &header,
+        |p| items_getter(query.clone(), p, page_size, allowed_langs.clone()),
         search_data,
+        |items| item_rows(items, &rows_data),
         PaginationTexts {
             not_found: not_found_text,
             no_items: not_found_text,
@@ -101,33 +132,298 @@ where
     .await
 }
 
+#[log_handler("search")]
+async fn search_all_callback_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    search_data: SearchCallbackData,
+) -> BotHandlerInternal {
+    let Some(message) = cq.message.as_ref() else {
+        return Ok(());
+    };
+    let (chat_id, message_id) = (message.chat().id, message.id());
+
+    let Some(query) = resolve_query(&cq, search_data.query_id()).await else {
+        return safe_send_message(&bot, chat_id, REPEAT_SEARCH, None).await;
+    };
+
+    let qid = match search_data.query_id() {
+        Some(qid) => qid.to_string(),
+        None => save_search_query(cq.from.id.0, &query).await,
+    };
+
+    let allowed_langs = get_user_or_default_lang_codes(cq.from.id).await;
+
+    match search_all(query, allowed_langs, Some(&qid)).await {
+        Ok(Some((text, keyboard))) => {
+            safe_edit_message_text(&bot, chat_id, message_id, text, Some(keyboard)).await
+        }
+        Ok(None) => safe_edit_message_text(&bot, chat_id, message_id, NOTHING_FOUND, None).await,
+        Err(err) => {
+            safe_send_message(&bot, chat_id, ERROR_TRY_LATER, None).await?;
+            Err(err)
+        }
+    }
+}
+
+/// Back from the results of a normalized variant to the search type
+/// choice for the typed query.
+#[log_handler("search")]
+async fn search_original_callback_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    qid: Option<String>,
+) -> BotHandlerInternal {
+    let Some(message) = cq.message else {
+        return Ok(());
+    };
+
+    safe_edit_message_text(
+        &bot,
+        message.chat().id,
+        message.id(),
+        "Что ищем?",
+        Some(get_search_type_keyboard(qid.as_deref())),
+    )
+    .await
+}
+
+/// Builds keyboard rows for the items of the results page `search_data`
+/// points at.
+type ItemRows<T> = fn(&[T], &SearchCallbackData) -> Vec<Vec<InlineKeyboardButton>>;
+
+fn no_item_rows<T>(
+    _items: &[T],
+    _search_data: &SearchCallbackData,
+) -> Vec<Vec<InlineKeyboardButton>> {
+    vec![]
+}
+
+/// "⭐" per book plus "Select" for the page of the stored query.
+fn get_book_rows(
+    books: &[SearchBook],
+    search_data: &SearchCallbackData,
+) -> Vec<Vec<InlineKeyboardButton>> {
+    let mut rows = get_add_to_shelf_rows(books);
+
+    if let Some(qid) = search_data.query_id() {
+        let list = SelectionList::Search {
+            qid: qid.to_string(),
+        };
+        rows.push(vec![get_select_button(list, search_data.page())]);
+    }
+
+    rows
+}
+
+type FirstPage = (String, u32, Vec<Vec<InlineKeyboardButton>>);
+
 async fn search_first_page<T, Fut>(
+    search_data: &SearchCallbackData,
     query: String,
+    page_size: u32,
     allowed_langs: SmallVec<[SmartString; 3]>,
-    search_fn: fn(String, u32, SmallVec<[SmartString; 3]>) -> Fut,
-) -> anyhow::Result<Option<(String, u32)>>
+    search_fn: fn(String, u32, u32, SmallVec<[SmartString; 3]>) -> Fut,
+    item_rows: ItemRows<T>,
+) -> anyhow::Result<Option<FirstPage>>
 where
     T: Format + Clone + Debug,
     Fut: std::future::Future<Output = anyhow::Result<Option<Page<T, Empty>>>>,
 {
-    match search_fn(query, 1, allowed_langs).await {
+    match search_fn(query, 1, page_size, allowed_langs).await {
         Ok(None) => Ok(None),
         Ok(Some(p)) if p.pages == 0 => Ok(None),
-        Ok(Some(p)) => Ok(Some((p.format(1, TELEGRAM_MESSAGE_MAX_LENGTH), p.pages))),
+        Ok(Some(p)) => Ok(Some((
+            p.format(1, TELEGRAM_MESSAGE_MAX_LENGTH),
+            p.pages,
+            item_rows(&p.items, search_data),
+        ))),
         Err(err) => Err(err),
     }
 }
 
+/// First page of `search_data`'s search type. The combined view has no
+/// pagination of its own, so it is reported as a single page.
+async fn search_type_first_page(
+    search_data: &SearchCallbackData,
+    query: String,
+    page_size: u32,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+) -> anyhow::Result<Option<FirstPage>> {
+    match search_data {
+        SearchCallbackData::Book { .. } => {
+            search_first_page(
+                search_data,
+                query,
+                page_size,
+                allowed_langs,
+                search_book_by_query,
+                get_book_rows,
+            )
+            .await
+        }
+        SearchCallbackData::Authors { .. } => {
+            search_first_page(
+                search_data,
+                query,
+                page_size,
+                allowed_langs,
+                search_author,
+                no_item_rows,
+            )
+            .await
+        }
+        SearchCallbackData::Sequences { .. } => {
+            search_first_page(
+                search_data,
+                query,
+                page_size,
+                allowed_langs,
+                search_sequence,
+                no_item_rows,
+            )
+            .await
+        }
+        SearchCallbackData::Translators { .. } => {
+            search_first_page(
+                search_data,
+                query,
+                page_size,
+                allowed_langs,
+                search_translator,
+                no_item_rows,
+            )
+            .await
+        }
+        SearchCallbackData::All { .. } | SearchCallbackData::Original { .. } => {
+            Ok(search_all(query, allowed_langs, search_data.query_id())
+                .await?
+                .map(|(text, keyboard)| (text, 1, keyboard.inline_keyboard)))
+        }
+    }
+}
+
+/// Runs `search` for `query` and, if it finds nothing, for the normalized
+/// variants from `utils::query_variants` in turn. Returns the variant the
+/// results were found by, if not the query itself. Queries with fields
+/// are not rewritten.
+async fn search_with_fallback<T, Fut>(
+    query: &str,
+    search: impl Fn(String) -> Fut,
+) -> anyhow::Result<Option<(Option<String>, T)>>
+where
+    Fut: std::future::Future<Output = anyhow::Result<Option<T>>>,
+{
+    if let Some(found) = search(query.to_string()).await? {
+        return Ok(Some((None, found)));
+    }
+
+    if parse_search_query(query).is_ok_and(|parsed| parsed.is_structured()) {
+        return Ok(None);
+    }
+
+    for variant in query_variants(query) {
+        if let Some(found) = search(variant.clone()).await? {
+            return Ok(Some((Some(variant), found)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// "Что ищем?" choice of the search type.
+fn get_search_type_keyboard(qid: Option<&str>) -> InlineKeyboardMarkup {
+    let qid = qid.map(str::to_string);
+    let button = |text: &str, data: SearchCallbackData| {
+        vec![InlineKeyboardButton {
+            text: text.to_string(),
+            kind: teloxide::types::InlineKeyboardButtonKind::CallbackData(data.to_string()),
+        }]
+    };
+
+    InlineKeyboardMarkup {
+        inline_keyboard: vec![
+            button(
+                "Книгу",
+                SearchCallbackData::Book {
+                    qid: qid.clone(),
+                    page: 1,
+                },
+            ),
+            button(
+                "Автора",
+                SearchCallbackData::Authors {
+                    qid: qid.clone(),
+                    page: 1,
+                },
+            ),
+            button(
+                "Серию",
+                SearchCallbackData::Sequences {
+                    qid: qid.clone(),
+                    page: 1,
+                },
+            ),
+            button(
+                "Переводчика",
+                SearchCallbackData::Translators {
+                    qid: qid.clone(),
+                    page: 1,
+                },
+            ),
+            button("Всё сразу", SearchCallbackData::All { qid }),
+        ],
+    }
+}
+
 #[log_handler("search")]
 pub async fn message_handler(message: Message, bot: CacheMe<Throttle<Bot>>) -> BotHandlerInternal {
-    let query = message.text().map(|t| t.trim()).filter(|t| !t.is_empty());
+    let query = message.text().map(|t| t.trim().to_string());
+
+    search_handler(message, bot, query).await
+}
+
+/// Runs a search for `query` as if it was typed as `message`. The query is
+/// stored in `services::search_queries` for the pagination buttons.
+pub async fn search_handler(
+    message: Message,
+    bot: CacheMe<Throttle<Bot>>,
+    query: Option<String>,
+) -> BotHandlerInternal {
+    let query = query.as_deref().map(str::trim).filter(|t| !t.is_empty());
     let user_id = message.from.as_ref().map(|u| u.id);
 
-    if let (Some(user_id), Some(query)) = (user_id, query) {
-        if let Some(default_type) = get_user_default_search(user_id).await {
-            let search_data = default_search_to_callback_data(default_type);
+    let structured = match query.map(parse_search_query).transpose() {
+        Ok(parsed) => parsed.is_some_and(|parsed| parsed.is_structured()),
+        Err(err) => {
+            return safe_send_message_with_reply(
+                &bot,
+                message.chat.id,
+                format!("{err}\n\nПодробнее: /searchhelp"),
+                ReplyParameters::new(message.id),
+                None,
+            )
+            .await;
+        }
+    };
+
+    let qid = match (user_id, query) {
+        (Some(user_id), Some(query)) => Some(save_search_query(user_id.0, query).await),
+        _ => None,
+    };
+
+    if let (Some(user_id), Some(query), Some(qid)) = (user_id, query, qid.as_deref()) {
+        // Queries with fields always search books.
+        let default_type = if structured {
+            Some(DefaultSearchType::Book)
+        } else {
+            get_user_default_search(user_id).await
+        };
+
+        if let Some(default_type) = default_type {
+            let search_data = default_search_to_callback_data(default_type, qid);
             let allowed_langs = get_user_or_default_lang_codes(user_id).await;
-            let query_owned = query.to_string();
+            let page_size = get_user_page_size(user_id).await;
             let chat_id = message.chat.id;
 
             let not_found_text = match &search_data {
@@ -135,24 +431,34 @@ pub async fn message_handler(message: Message, bot: CacheMe<Throttle<Bot>>) -> B
                 SearchCallbackData::Authors { .. } => AUTHORS_NOT_FOUND,
                 SearchCallbackData::Sequences { .. } => SEQUENCES_NOT_FOUND,
                 SearchCallbackData::Translators { .. } => TRANSLATORS_NOT_FOUND,
+                SearchCallbackData::All { .. } | SearchCallbackData::Original { .. } => {
+                    NOTHING_FOUND
+                }
             };
 
-            let result = match &search_data {
-                SearchCallbackData::Book { .. } => {
-                    search_first_page(query_owned, allowed_langs, search_book).await
-                }
-                SearchCallbackData::Authors { .. } => {
-                    search_first_page(query_owned, allowed_langs, search_author).await
-                }
-                SearchCallbackData::Sequences { .. } => {
-                    search_first_page(query_owned, allowed_langs, search_sequence).await
-                }
-                SearchCallbackData::Translators { .. } => {
-                    search_first_page(query_owned, allowed_langs, search_translator).await
+            // Every variant tried gets its own stored query, so pages of
+            // the results shown keep searching for it.
+            let result = search_with_fallback(query, |variant| {
+                let search_data = search_data.clone();
+                let allowed_langs = allowed_langs.clone();
+
+                async move {
+                    let search_data = if variant == query {
+                        search_data
+                    } else {
+                        search_data.with_query_id(&save_search_query(user_id.0, &variant).await)
+                    };
+
+                    Ok(
+                        search_type_first_page(&search_data, variant, page_size, allowed_langs)
+                            .await?
+                            .map(|page| (search_data, page)),
+                    )
                 }
-            };
+            })
+            .await;
 
-            let (formatted, pages) = match result {
+            let (corrected_query, (search_data, (formatted, pages, item_rows))) = match result {
                 Ok(Some(v)) => v,
                 Ok(None) => {
                     safe_send_message_with_reply(
@@ -171,11 +477,30 @@ pub async fn message_handler(message: Message, bot: CacheMe<Throttle<Bot>>) -> B
                 }
             };
 
-            let keyboard = generic_get_pagination_keyboard(1, pages, search_data, true);
+            let mut keyboard = generic_get_pagination_keyboard(1, pages, search_data);
+            keyboard.inline_keyboard.extend(item_rows);
+
+            let text = match corrected_query {
+                Some(corrected_query) => {
+                    keyboard.inline_keyboard.push(vec![InlineKeyboardButton {
+                        text: format!("🔎 Искать «{}»", short_title(query)),
+                        kind: teloxide::types::InlineKeyboardButtonKind::CallbackData(
+                            SearchCallbackData::Original {
+                                qid: Some(qid.to_string()),
+                            }
+                            .to_string(),
+                        ),
+                    }]);
+
+                    format!("{}{formatted}", corrected_query_header(&corrected_query))
+                }
+                None => formatted,
+            };
+
             safe_send_message_with_reply(
                 &bot,
                 chat_id,
-                formatted,
+                text,
                 ReplyParameters::new(message.id),
                 Some(keyboard),
             )
@@ -184,50 +509,39 @@ pub async fn message_handler(message: Message, bot: CacheMe<Throttle<Bot>>) -> B
         }
     }
 
-    let message_text = "Что ищем?";
-    let keyboard = InlineKeyboardMarkup {
-        inline_keyboard: vec![
-            vec![InlineKeyboardButton {
-                text: "Книгу".to_string(),
-                kind: teloxide::types::InlineKeyboardButtonKind::CallbackData(
-                    (SearchCallbackData::Book { page: 1 }).to_string(),
-                ),
-            }],
-            vec![InlineKeyboardButton {
-                text: "Автора".to_string(),
-                kind: teloxide::types::InlineKeyboardButtonKind::CallbackData(
-                    (SearchCallbackData::Authors { page: 1 }).to_string(),
-                ),
-            }],
-            vec![InlineKeyboardButton {
-                text: "Серию".to_string(),
-                kind: teloxide::types::InlineKeyboardButtonKind::CallbackData(
-                    (SearchCallbackData::Sequences { page: 1 }).to_string(),
-                ),
-            }],
-            vec![InlineKeyboardButton {
-                text: "Переводчика".to_string(),
-                kind: teloxide::types::InlineKeyboardButtonKind::CallbackData(
-                    (SearchCallbackData::Translators { page: 1 }).to_string(),
-                ),
-            }],
-        ],
-    };
-
     safe_send_message_with_reply(
         &bot,
         message.chat.id,
-        message_text,
+        "Что ищем?",
         ReplyParameters::new(message.id),
-        Some(keyboard),
+        Some(get_search_type_keyboard(qid.as_deref())),
     )
     .await?;
 
     Ok(())
 }
 
+#[log_handler("search")]
+async fn search_help_handler(message: Message, bot: CacheMe<Throttle<Bot>>) -> BotHandlerInternal {
+    safe_send_message_with_reply(
+        &bot,
+        message.chat.id,
+        SEARCH_HELP,
+        ReplyParameters::new(message.id),
+        None,
+    )
+    .await
+}
+
 pub fn get_search_handler() -> crate::bots::BotHandler {
     dptree::entry()
+        .branch(
+            Update::filter_message().branch(
+                dptree::entry()
+                    .filter_command::<SearchCommand>()
+                    .endpoint(search_help_handler),
+            ),
+        )
         .branch(
             Update::filter_message()
                 .endpoint(|message, bot| async move { message_handler(message, bot).await }),
@@ -245,7 +559,8 @@ pub fn get_search_handler() -> crate::bots::BotHandler {
                                     cq,
                                     bot,
                                     callback_data,
-                                    search_book,
+                                    search_book_by_query,
+                                    get_book_rows,
                                 )
                                 .await
                             }
@@ -255,6 +570,7 @@ pub fn get_search_handler() -> crate::bots::BotHandler {
                                     bot,
                                     callback_data,
                                     search_author,
+                                    no_item_rows,
                                 )
                                 .await
                             }
@@ -264,6 +580,7 @@ pub fn get_search_handler() -> crate::bots::BotHandler {
                                     bot,
                                     callback_data,
                                     search_sequence,
+                                    no_item_rows,
                                 )
                                 .await
                             }
@@ -273,9 +590,16 @@ pub fn get_search_handler() -> crate::bots::BotHandler {
                                     bot,
                                     callback_data,
                                     search_translator,
+                                    no_item_rows,
                                 )
                                 .await
                             }
+                            SearchCallbackData::All { .. } => {
+                                search_all_callback_handler(cq, bot, callback_data).await
+                            }
+                            SearchCallbackData::Original { qid } => {
+                                search_original_callback_handler(cq, bot, qid).await
+                            }
                         }
                     },
                 ),
@@ -284,17 +608,22 @@ pub fn get_search_handler() -> crate::bots::BotHandler {
 
 #[cfg(test)]
 mod tests {
-    use super::search_first_page;
-    use crate::bots::approved_bot::services::book_library::types::{Empty, Page, SearchBook};
+    use super::{no_item_rows, search_first_page, search_with_fallback, SearchCallbackData};
+    use crate::bots::approved_bot::services::book_library::{
+        types::{Empty, Page, SearchBook},
+        DEFAULT_PAGE_SIZE,
+    };
     use smallvec::smallvec;
 
     async fn fake_found(
         _query: String,
         _page: u32,
+        _page_size: u32,
         _allowed_langs: smallvec::SmallVec<[smartstring::alias::String; 3]>,
     ) -> anyhow::Result<Option<Page<SearchBook, Empty>>> {
         Ok(Some(Page {
             items: vec![],
+            total: 7,
             pages: 2,
             parent_item: None,
         }))
@@ -303,10 +632,12 @@ mod tests {
     async fn fake_zero_pages(
         _query: String,
         _page: u32,
+        _page_size: u32,
         _allowed_langs: smallvec::SmallVec<[smartstring::alias::String; 3]>,
     ) -> anyhow::Result<Option<Page<SearchBook, Empty>>> {
         Ok(Some(Page {
             items: vec![],
+            total: 0,
             pages: 0,
             parent_item: None,
         }))
@@ -315,6 +646,7 @@ mod tests {
     async fn fake_not_found(
         _query: String,
         _page: u32,
+        _page_size: u32,
         _allowed_langs: smallvec::SmallVec<[smartstring::alias::String; 3]>,
     ) -> anyhow::Result<Option<Page<SearchBook, Empty>>> {
         Ok(None)
@@ -322,26 +654,68 @@ mod tests {
 
     #[tokio::test]
     async fn returns_formatted_page_and_pages_on_success() {
-        let result = search_first_page("q".to_string(), smallvec!["ru".into()], fake_found)
-            .await
-            .unwrap();
-        let (_, pages) = result.expect("expected Some");
+        let result = search_first_page(
+            &SearchCallbackData::Book { qid: None, page: 1 },
+            "q".to_string(),
+            DEFAULT_PAGE_SIZE,
+            smallvec!["ru".into()],
+            fake_found,
+            no_item_rows,
+        )
+        .await
+        .unwrap();
+        let (_, pages, _) = result.expect("expected Some");
         assert_eq!(pages, 2);
     }
 
     #[tokio::test]
     async fn returns_none_on_zero_pages() {
-        let result = search_first_page("q".to_string(), smallvec!["ru".into()], fake_zero_pages)
-            .await
-            .unwrap();
+        let result = search_first_page(
+            &SearchCallbackData::Book { qid: None, page: 1 },
+            "q".to_string(),
+            DEFAULT_PAGE_SIZE,
+            smallvec!["ru".into()],
+            fake_zero_pages,
+            no_item_rows,
+        )
+        .await
+        .unwrap();
         assert!(result.is_none());
     }
 
     #[tokio::test]
     async fn returns_none_when_search_fn_returns_none() {
-        let result = search_first_page("q".to_string(), smallvec!["ru".into()], fake_not_found)
-            .await
-            .unwrap();
+        let result = search_first_page(
+            &SearchCallbackData::Book { qid: None, page: 1 },
+            "q".to_string(),
+            DEFAULT_PAGE_SIZE,
+            smallvec!["ru".into()],
+            fake_not_found,
+            no_item_rows,
+        )
+        .await
+        .unwrap();
         assert!(result.is_none());
     }
+
+    #[tokio::test]
+    async fn falls_back_to_normalized_variant() {
+        let search = |query: String| async move {
+            Ok::<_, anyhow::Error>((query == "война и мир").then_some(query.len()))
+        };
+
+        let found = search_with_fallback("djqyf b vbh", search).await.unwrap();
+        assert_eq!(
+            found,
+            Some((Some("война и мир".to_string()), "война и мир".len()))
+        );
+
+        let found = search_with_fallback("война и мир", search).await.unwrap();
+        assert_eq!(found, Some((None, "война и мир".len())));
+
+        assert_eq!(
+            search_with_fallback("author:djqyf", search).await.unwrap(),
+            None
+        );
+    }
 }
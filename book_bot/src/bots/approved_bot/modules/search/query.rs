@@ -0,0 +1,323 @@
+use smallvec::{smallvec, SmallVec};
+use smartstring::alias::String as SmartString;
+use thiserror::Error;
+
+use crate::bots::approved_bot::services::book_library::{
+    search_book, search_book_by_filter,
+    types::{Empty, Page, SearchBook},
+    BookFilter,
+};
+
+const AUTHOR: &str = "author";
+const TITLE: &str = "title";
+const LANG: &str = "lang";
+const YEAR: &str = "year";
+const GENRE: &str = "genre";
+
+const KEYS: [&str; 5] = [AUTHOR, TITLE, LANG, YEAR, GENRE];
+
+/// Reply to `/searchhelp`.
+pub const SEARCH_HELP: &str = "\
+Кроме обычного текста, в запросе можно указать поля:
+
+author:Толстой — автор
+title:война — название
+lang:en — язык книги (вместо языков из /settings)
+year:1990 или year:1990-2000 — год издания
+genre:sf — жанр
+
+Значения с пробелами берите в кавычки: author:\"Лев Толстой\".
+Поля можно сочетать между собой и с текстом:
+author:Толстой title:война lang:ru year:1860-1870
+
+Запрос с полями всегда ищет книги.";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum QueryParseError {
+    #[error("Не указано значение для «{0}:»")]
+    EmptyValue(&'static str),
+    #[error("«{0}:» указан несколько раз")]
+    DuplicateKey(&'static str),
+    #[error("Не понял год «{0}». Пример: year:1990 или year:1990-2000")]
+    InvalidYear(String),
+    #[error("Не понял язык «{0}». Пример: lang:en")]
+    InvalidLang(String),
+    #[error("Кроме языка, укажите, что искать: текст, author:, title:, year: или genre:")]
+    NothingToSearch,
+}
+
+/// A book search query with optional `key:value` fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub filter: BookFilter,
+    /// Overrides the user's allowed languages.
+    pub lang: Option<SmartString>,
+}
+
+impl SearchQuery {
+    /// Whether any field besides the free text was given.
+    pub fn is_structured(&self) -> bool {
+        let BookFilter {
+            text: _,
+            title,
+            author,
+            genre,
+            year_gte,
+            year_lte,
+        } = &self.filter;
+
+        title.is_some()
+            || author.is_some()
+            || genre.is_some()
+            || year_gte.is_some()
+            || year_lte.is_some()
+            || self.lang.is_some()
+    }
+}
+
+/// Splits on whitespace, keeping double-quoted parts (quotes removed)
+/// together: `author:"Лев Толстой"` is one token. An unclosed quote runs
+/// to the end of the query.
+fn tokenize(s: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in s.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+/// Returns the known key and its (possibly empty) value of a `key:value`
+/// token. Tokens with an unknown key are plain text.
+fn split_key(token: &str) -> Option<(&'static str, &str)> {
+    let (key, value) = token.split_once(':')?;
+
+    KEYS.iter()
+        .find(|known| known.eq_ignore_ascii_case(key))
+        .map(|known| (*known, value))
+}
+
+fn parse_year(value: &str) -> Result<(Option<i32>, Option<i32>), QueryParseError> {
+    let invalid = || QueryParseError::InvalidYear(value.to_string());
+
+    let parse = |part: &str| -> Result<Option<i32>, QueryParseError> {
+        match part.trim() {
+            "" => Ok(None),
+            part => part.parse().map(Some).map_err(|_| invalid()),
+        }
+    };
+
+    let (from, to) = match value.split_once('-') {
+        Some((from, to)) => (parse(from)?, parse(to)?),
+        None => {
+            let year = parse(value)?;
+            (year, year)
+        }
+    };
+
+    match (from, to) {
+        (None, None) => Err(invalid()),
+        (Some(from), Some(to)) if from > to => Err(invalid()),
+        years => Ok(years),
+    }
+}
+
+fn parse_lang(value: &str) -> Result<SmartString, QueryParseError> {
+    let is_valid = (2..=3).contains(&value.len()) && value.chars().all(|c| c.is_ascii_alphabetic());
+
+    if !is_valid {
+        return Err(QueryParseError::InvalidLang(value.to_string()));
+    }
+
+    Ok(value.to_ascii_lowercase().into())
+}
+
+fn set_once<T>(slot: &mut Option<T>, key: &'static str, value: T) -> Result<(), QueryParseError> {
+    if slot.is_some() {
+        return Err(QueryParseError::DuplicateKey(key));
+    }
+
+    *slot = Some(value);
+    Ok(())
+}
+
+/// Parses `author:Толстой title:война lang:en year:1990-2000 genre:sf`
+/// style queries. Everything that is not a known field is free text.
+pub fn parse_search_query(s: &str) -> Result<SearchQuery, QueryParseError> {
+    let mut query = SearchQuery::default();
+    let mut text: Vec<String> = vec![];
+    let mut tokens = tokenize(s).into_iter();
+
+    while let Some(token) = tokens.next() {
+        let Some((key, value)) = split_key(&token) else {
+            text.push(token);
+            continue;
+        };
+
+        // `author: Толстой` — the value is the next token.
+        let value = match value {
+            "" => tokens.next().ok_or(QueryParseError::EmptyValue(key))?,
+            value => value.to_string(),
+        };
+
+        let filter = &mut query.filter;
+
+        match key {
+            AUTHOR => set_once(&mut filter.author, key, value)?,
+            TITLE => set_once(&mut filter.title, key, value)?,
+            GENRE => set_once(&mut filter.genre, key, value)?,
+            LANG => set_once(&mut query.lang, key, parse_lang(&value)?)?,
+            _ => {
+                if filter.year_gte.is_some() || filter.year_lte.is_some() {
+                    return Err(QueryParseError::DuplicateKey(key));
+                }
+
+                (filter.year_gte, filter.year_lte) = parse_year(&value)?;
+            }
+        }
+    }
+
+    if !text.is_empty() {
+        query.filter.text = Some(text.join(" "));
+    }
+
+    if query.lang.is_some() && query.filter == BookFilter::default() {
+        return Err(QueryParseError::NothingToSearch);
+    }
+
+    Ok(query)
+}
+
+/// `search_book` that understands the `key:value` fields of
+/// `parse_search_query`. Plain queries go to the book server unchanged.
+pub async fn search_book_by_query(
+    query: String,
+    page: u32,
+    page_size: u32,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+) -> anyhow::Result<Option<Page<SearchBook, Empty>>> {
+    let parsed = parse_search_query(&query)?;
+
+    if !parsed.is_structured() {
+        return search_book(query, page, page_size, allowed_langs).await;
+    }
+
+    let allowed_langs = match parsed.lang {
+        Some(lang) => smallvec![lang],
+        None => allowed_langs,
+    };
+
+    search_book_by_filter(&parsed.filter, page, page_size, allowed_langs).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text_is_not_structured() {
+        let query = parse_search_query("война и мир").unwrap();
+
+        assert!(!query.is_structured());
+        assert_eq!(query.filter.text.as_deref(), Some("война и мир"));
+    }
+
+    #[test]
+    fn parses_all_fields() {
+        let query =
+            parse_search_query("author:Толстой title:война lang:EN year:1990-2000 genre:sf")
+                .unwrap();
+
+        assert!(query.is_structured());
+        assert_eq!(
+            query.filter,
+            BookFilter {
+                text: None,
+                title: Some("война".to_string()),
+                author: Some("Толстой".to_string()),
+                genre: Some("sf".to_string()),
+                year_gte: Some(1990),
+                year_lte: Some(2000),
+            }
+        );
+        assert_eq!(query.lang.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn quoted_values_and_free_text() {
+        let query = parse_search_query("author:\"Лев Толстой\" мир Title: война").unwrap();
+
+        assert_eq!(query.filter.author.as_deref(), Some("Лев Толстой"));
+        assert_eq!(query.filter.title.as_deref(), Some("война"));
+        assert_eq!(query.filter.text.as_deref(), Some("мир"));
+
+        let query = parse_search_query("author:\"Лев Толстой").unwrap();
+        assert_eq!(query.filter.author.as_deref(), Some("Лев Толстой"));
+    }
+
+    #[test]
+    fn unknown_keys_are_text() {
+        let query = parse_search_query("Глава 1: начало").unwrap();
+
+        assert!(!query.is_structured());
+        assert_eq!(query.filter.text.as_deref(), Some("Глава 1: начало"));
+    }
+
+    #[test]
+    fn open_year_ranges() {
+        let query = parse_search_query("title:мир year:-1900").unwrap();
+        assert_eq!(
+            (query.filter.year_gte, query.filter.year_lte),
+            (None, Some(1900))
+        );
+
+        let query = parse_search_query("title:мир year:1990").unwrap();
+        assert_eq!(
+            (query.filter.year_gte, query.filter.year_lte),
+            (Some(1990), Some(1990))
+        );
+    }
+
+    #[test]
+    fn malformed_queries() {
+        assert_eq!(
+            parse_search_query("title:мир year:abc"),
+            Err(QueryParseError::InvalidYear("abc".to_string()))
+        );
+        assert_eq!(
+            parse_search_query("year:2000-1990"),
+            Err(QueryParseError::InvalidYear("2000-1990".to_string()))
+        );
+        assert_eq!(
+            parse_search_query("мир lang:русский"),
+            Err(QueryParseError::InvalidLang("русский".to_string()))
+        );
+        assert_eq!(
+            parse_search_query("мир author:"),
+            Err(QueryParseError::EmptyValue(AUTHOR))
+        );
+        assert_eq!(
+            parse_search_query("author:a author:b"),
+            Err(QueryParseError::DuplicateKey(AUTHOR))
+        );
+        assert_eq!(
+            parse_search_query("lang:en"),
+            Err(QueryParseError::NothingToSearch)
+        );
+    }
+}
@@ -1,12 +1,71 @@
+use std::str::FromStr;
+
 use teloxide::types::{CallbackQuery, MaybeInaccessibleMessage};
 
+use crate::bots::approved_bot::{
+    modules::utils::deep_link::DeepLink, services::search_queries::get_search_query,
+};
+
+/// Recovers the query of a search started by a `/start <payload>` deep link.
+fn get_deep_link_query(text: &str) -> Option<String> {
+    let payload = text.strip_prefix("/start ")?;
+
+    match DeepLink::from_str(payload).ok()? {
+        DeepLink::Search { query } => Some(query),
+        _ => None,
+    }
+}
+
+/// First line of a results message shown for a normalized variant of the
+/// typed query, followed by that variant.
+const CORRECTED_QUERY_PREFIX: &str = "🔁 Показаны результаты по запросу: ";
+
+/// Header of a results page found by the `query` variant.
+pub fn corrected_query_header(query: &str) -> String {
+    format!("{CORRECTED_QUERY_PREFIX}{query}\n\n")
+}
+
+fn get_corrected_query_from_text(text: &str) -> Option<String> {
+    text.lines()
+        .next()?
+        .strip_prefix(CORRECTED_QUERY_PREFIX)
+        .map(str::to_string)
+}
+
+/// The normalized variant a results message was found by, if any.
+pub fn get_corrected_query(cq: &CallbackQuery) -> Option<String> {
+    match cq.regular_message()?.text() {
+        Some(text) => get_corrected_query_from_text(text),
+        None => None,
+    }
+}
+
+/// The query a search callback is about: the stored one for `qid`, else
+/// recovered from the message, see `get_query`.
+pub async fn resolve_query(cq: &CallbackQuery, qid: Option<&str>) -> Option<String> {
+    if let Some(query) = match qid {
+        Some(qid) => get_search_query(qid, cq.from.id.0).await,
+        None => None,
+    } {
+        return Some(query);
+    }
+
+    get_query(cq)
+}
+
 pub fn get_query(cq: &CallbackQuery) -> Option<String> {
+    if let Some(query) = get_corrected_query(cq) {
+        return Some(query);
+    }
+
     match &cq.message {
         Some(message) => match message {
             MaybeInaccessibleMessage::Regular(message) => match message.reply_to_message() {
-                Some(reply_to_message) => reply_to_message
-                    .text()
-                    .map(|text| text.replace(['/', '&', '?'], "")),
+                Some(reply_to_message) => reply_to_message.text().map(|text| {
+                    get_deep_link_query(text)
+                        .unwrap_or_else(|| text.to_string())
+                        .replace(['/', '&', '?'], "")
+                }),
                 None => None,
             },
             MaybeInaccessibleMessage::Inaccessible(_) => None,
@@ -15,9 +74,197 @@ pub fn get_query(cq: &CallbackQuery) -> Option<String> {
     }
 }
 
+const QWERTY: &str = "qwertyuiop[]asdfghjkl;'zxcvbnm,.`";
+const JCUKEN: &str = "йцукенгшщзхъфывапролджэячсмитьбюё";
+
+fn is_cyrillic(c: char) -> bool {
+    matches!(c, 'а'..='я' | 'ё')
+}
+
+/// Retypes text entered with the wrong keyboard layout, in the direction
+/// given by the letters it contains.
+fn switch_layout(query: &str) -> String {
+    let to_cyrillic = !query.chars().any(is_cyrillic);
+    let (from, to) = if to_cyrillic {
+        (QWERTY, JCUKEN)
+    } else {
+        (JCUKEN, QWERTY)
+    };
+
+    query
+        .chars()
+        .map(|c| match from.chars().position(|f| f == c) {
+            Some(index) => to.chars().nth(index).unwrap_or(c),
+            None => c,
+        })
+        .collect()
+}
+
+const LATIN_TO_CYRILLIC: [(&str, &str); 10] = [
+    ("shch", "щ"),
+    ("sch", "щ"),
+    ("zh", "ж"),
+    ("kh", "х"),
+    ("ts", "ц"),
+    ("ch", "ч"),
+    ("sh", "ш"),
+    ("yu", "ю"),
+    ("ya", "я"),
+    ("yo", "ё"),
+];
+
+fn latin_letter_to_cyrillic(c: char, after_vowel: bool) -> Option<&'static str> {
+    let letter = match c {
+        'a' => "а",
+        'b' => "б",
+        'c' => "ц",
+        'd' => "д",
+        'e' => "е",
+        'f' => "ф",
+        'g' => "г",
+        'h' => "х",
+        'i' => "и",
+        'j' => "й",
+        'k' | 'q' => "к",
+        'l' => "л",
+        'm' => "м",
+        'n' => "н",
+        'o' => "о",
+        'p' => "п",
+        'r' => "р",
+        's' => "с",
+        't' => "т",
+        'u' => "у",
+        'v' | 'w' => "в",
+        'x' => "кс",
+        'y' if after_vowel => "й",
+        'y' => "ы",
+        'z' => "з",
+        '\'' => "ь",
+        _ => return None,
+    };
+
+    Some(letter)
+}
+
+fn cyrillic_letter_to_latin(c: char) -> Option<&'static str> {
+    let letter = match c {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' => "g",
+        'д' => "d",
+        'е' | 'ё' | 'э' => "e",
+        'ж' => "zh",
+        'з' => "z",
+        'и' => "i",
+        'й' | 'ы' => "y",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ю' => "yu",
+        'я' => "ya",
+        _ => return None,
+    };
+
+    Some(letter)
+}
+
+/// Transliterates Latin to Cyrillic or back, depending on the letters the
+/// query contains.
+fn transliterate(query: &str) -> String {
+    if query.chars().any(is_cyrillic) {
+        return query
+            .chars()
+            .map(|c| match cyrillic_letter_to_latin(c) {
+                Some(latin) => latin.to_string(),
+                None => c.to_string(),
+            })
+            .collect();
+    }
+
+    let mut result = String::new();
+    let mut rest = query;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some((latin, cyrillic)) = LATIN_TO_CYRILLIC
+            .iter()
+            .find(|(latin, _)| rest.starts_with(latin))
+        {
+            result.push_str(cyrillic);
+            rest = &rest[latin.len()..];
+            continue;
+        }
+
+        let after_vowel = result
+            .chars()
+            .last()
+            .is_some_and(|last| "аеёиоуыэюя".contains(last));
+        match latin_letter_to_cyrillic(c, after_vowel) {
+            Some(cyrillic) => result.push_str(cyrillic),
+            None => result.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    result
+}
+
+/// `ё` → `е`, punctuation to spaces, whitespace collapsed.
+fn clean(query: &str) -> String {
+    query
+        .replace('ё', "е")
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Spellings to retry a query that found nothing with, most likely first:
+/// the query cleaned up, retyped in the other keyboard layout and
+/// transliterated. Every variant is cleaned; the query itself and empty
+/// or repeated variants are left out.
+pub fn query_variants(query: &str) -> Vec<String> {
+    let lowercase = query.to_lowercase();
+    let normalizers: [fn(&str) -> String; 3] =
+        [|query| query.to_string(), switch_layout, transliterate];
+
+    let mut variants: Vec<String> = vec![];
+
+    for normalize in normalizers {
+        let variant = clean(&normalize(&lowercase));
+
+        if !variant.is_empty() && variant != lowercase && !variants.contains(&variant) {
+            variants.push(variant);
+        }
+    }
+
+    variants
+}
+
 #[cfg(test)]
 mod tests {
-    use super::get_query;
+    use super::{
+        corrected_query_header, get_corrected_query_from_text, get_deep_link_query, get_query,
+        query_variants, switch_layout, transliterate,
+    };
+    use crate::bots::approved_bot::modules::utils::deep_link::DeepLink;
     use teloxide::types::{CallbackQuery, MaybeInaccessibleMessage};
 
     fn make_cq(message: Option<MaybeInaccessibleMessage>) -> CallbackQuery {
@@ -34,9 +281,60 @@ mod tests {
         .unwrap()
     }
 
+    #[test]
+    fn deep_link_query_is_decoded() {
+        let payload = DeepLink::Search {
+            query: "война и мир".to_string(),
+        }
+        .to_string();
+
+        assert_eq!(
+            get_deep_link_query(&format!("/start {payload}")).as_deref(),
+            Some("война и мир")
+        );
+        assert_eq!(get_deep_link_query("/start b_1"), None);
+        assert_eq!(get_deep_link_query("война и мир"), None);
+    }
+
     #[test]
     fn returns_none_when_message_missing() {
         let cq = make_cq(None);
         assert_eq!(get_query(&cq), None);
     }
+
+    #[test]
+    fn corrected_query_round_trip() {
+        let text = format!("{}1. Война и мир", corrected_query_header("война и мир"));
+
+        assert_eq!(
+            get_corrected_query_from_text(&text).as_deref(),
+            Some("война и мир")
+        );
+        assert_eq!(get_corrected_query_from_text("1. Война и мир"), None);
+    }
+
+    #[test]
+    fn switches_keyboard_layout() {
+        assert_eq!(switch_layout("djqyf b vbh"), "война и мир");
+        assert_eq!(switch_layout("ящдфкшы"), "zolaris");
+        assert_eq!(switch_layout("ghbdtn? vbh"), "привет? мир");
+    }
+
+    #[test]
+    fn transliterates_both_ways() {
+        assert_eq!(transliterate("voyna i mir"), "война и мир");
+        assert_eq!(transliterate("shchuka zhuk"), "щука жук");
+        assert_eq!(transliterate("гарри поттер"), "garri potter");
+    }
+
+    #[test]
+    fn variants_are_cleaned_and_distinct() {
+        assert_eq!(
+            query_variants("djqyf b vbh"),
+            ["война и мир", "дйкыф б вбх"]
+        );
+        assert_eq!(query_variants("Ёлки-палки!")[0], "елки палки");
+        assert!(query_variants("мир").iter().all(|v| v != "мир"));
+        assert!(query_variants("!!!").is_empty());
+    }
 }
@@ -0,0 +1,231 @@
+use std::{fmt::Display, str::FromStr};
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::bots::approved_bot::modules::utils::{
+    errors::CallbackQueryParseError, pagination::GetPaginationCallbackData,
+};
+
+static RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^sel_(?:new_(?:b_(?P<qid>[0-9a-z]+)|(?P<list>[ats])_(?P<list_id>\d+))_(?P<start_page>\d+)|(?P<sid>[0-9a-z]+)_(?:(?P<page>\d+)|t_(?P<book_id>\d+)_(?P<toggle_page>\d+)|da|da_(?P<file_type>\w+)))$",
+    )
+    .unwrap()
+});
+
+/// A listing that can be switched into select mode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SelectionList {
+    /// Book search for the query stored under `qid`, see
+    /// `services::search_queries`.
+    Search {
+        qid: String,
+    },
+    Author {
+        id: u32,
+    },
+    Translator {
+        id: u32,
+    },
+    Sequence {
+        id: u32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SelectionCallbackData {
+    /// "☑️ Выбрать" under a listing: starts a selection on `page`.
+    Start {
+        list: SelectionList,
+        page: u32,
+    },
+    /// A page of the listing in select mode.
+    Page {
+        sid: String,
+        page: u32,
+    },
+    /// Checkbox of a book shown on `page`.
+    Toggle {
+        sid: String,
+        book_id: u32,
+        page: u32,
+    },
+    /// Format choice for the archive of the selected books.
+    ArchiveMenu {
+        sid: String,
+    },
+    Archive {
+        sid: String,
+        file_type: String,
+    },
+}
+
+impl FromStr for SelectionCallbackData {
+    type Err = CallbackQueryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let caps = RE.captures(s).ok_or(CallbackQueryParseError)?;
+
+        let number = |name: &str| -> Result<u32, CallbackQueryParseError> {
+            caps[name].parse().map_err(|_| CallbackQueryParseError)
+        };
+
+        if let Some(qid) = caps.name("qid") {
+            return Ok(SelectionCallbackData::Start {
+                list: SelectionList::Search {
+                    qid: qid.as_str().to_string(),
+                },
+                page: std::cmp::max(1, number("start_page")?),
+            });
+        }
+
+        if let Some(list) = caps.name("list") {
+            let id = number("list_id")?;
+            let list = match list.as_str() {
+                "a" => SelectionList::Author { id },
+                "t" => SelectionList::Translator { id },
+                "s" => SelectionList::Sequence { id },
+                _ => return Err(CallbackQueryParseError),
+            };
+
+            return Ok(SelectionCallbackData::Start {
+                list,
+                page: std::cmp::max(1, number("start_page")?),
+            });
+        }
+
+        let sid = caps["sid"].to_string();
+
+        if caps.name("page").is_some() {
+            return Ok(SelectionCallbackData::Page {
+                sid,
+                page: std::cmp::max(1, number("page")?),
+            });
+        }
+
+        if caps.name("book_id").is_some() {
+            return Ok(SelectionCallbackData::Toggle {
+                sid,
+                book_id: number("book_id")?,
+                page: std::cmp::max(1, number("toggle_page")?),
+            });
+        }
+
+        match caps.name("file_type") {
+            Some(file_type) => Ok(SelectionCallbackData::Archive {
+                sid,
+                file_type: file_type.as_str().to_string(),
+            }),
+            None => Ok(SelectionCallbackData::ArchiveMenu { sid }),
+        }
+    }
+}
+
+impl Display for SelectionCallbackData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelectionCallbackData::Start { list, page } => {
+                let (kind, id) = match list {
+                    SelectionList::Search { qid } => ("b", qid.clone()),
+                    SelectionList::Author { id } => ("a", id.to_string()),
+                    SelectionList::Translator { id } => ("t", id.to_string()),
+                    SelectionList::Sequence { id } => ("s", id.to_string()),
+                };
+                write!(f, "sel_new_{kind}_{id}_{page}")
+            }
+            SelectionCallbackData::Page { sid, page } => write!(f, "sel_{sid}_{page}"),
+            SelectionCallbackData::Toggle { sid, book_id, page } => {
+                write!(f, "sel_{sid}_t_{book_id}_{page}")
+            }
+            SelectionCallbackData::ArchiveMenu { sid } => write!(f, "sel_{sid}_da"),
+            SelectionCallbackData::Archive { sid, file_type } => {
+                write!(f, "sel_{sid}_da_{file_type}")
+            }
+        }
+    }
+}
+
+impl GetPaginationCallbackData for SelectionCallbackData {
+    fn get_pagination_callback_data(&self, target_page: u32) -> String {
+        match self {
+            SelectionCallbackData::Page { sid, .. }
+            | SelectionCallbackData::Toggle { sid, .. }
+            | SelectionCallbackData::ArchiveMenu { sid }
+            | SelectionCallbackData::Archive { sid, .. } => SelectionCallbackData::Page {
+                sid: sid.clone(),
+                page: target_page,
+            }
+            .to_string(),
+            SelectionCallbackData::Start { list, .. } => SelectionCallbackData::Start {
+                list: list.clone(),
+                page: target_page,
+            }
+            .to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let sid = "sw9k2a".to_string();
+
+        for data in [
+            SelectionCallbackData::Start {
+                list: SelectionList::Search {
+                    qid: "k3x9a0q1".to_string(),
+                },
+                page: 2,
+            },
+            SelectionCallbackData::Start {
+                list: SelectionList::Author { id: 7 },
+                page: 1,
+            },
+            SelectionCallbackData::Start {
+                list: SelectionList::Sequence { id: 8 },
+                page: 3,
+            },
+            SelectionCallbackData::Page {
+                sid: sid.clone(),
+                page: 4,
+            },
+            SelectionCallbackData::Toggle {
+                sid: sid.clone(),
+                book_id: 12345678,
+                page: 2,
+            },
+            SelectionCallbackData::ArchiveMenu { sid: sid.clone() },
+            SelectionCallbackData::Archive {
+                sid,
+                file_type: "epub".to_string(),
+            },
+        ] {
+            assert_eq!(
+                SelectionCallbackData::from_str(&data.to_string()).unwrap(),
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn fits_telegram_callback_data_limit() {
+        let data = SelectionCallbackData::Toggle {
+            sid: "zzzzzzzzzzzzz".to_string(),
+            book_id: u32::MAX,
+            page: u32::MAX,
+        };
+
+        assert!(data.to_string().len() <= 64);
+    }
+
+    #[test]
+    fn rejects_foreign_data() {
+        assert!(SelectionCallbackData::from_str("sel_new_x_1_1").is_err());
+        assert!(SelectionCallbackData::from_str("shelf_1").is_err());
+        assert!(SelectionCallbackData::from_str("sel_ab_t_1").is_err());
+    }
+}
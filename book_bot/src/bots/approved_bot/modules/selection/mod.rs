@@ -0,0 +1,484 @@
+pub mod callback_data;
+
+use core::fmt::Debug;
+
+use book_bot_macros::log_handler;
+
+use teloxide::{
+    adaptors::{CacheMe, Throttle},
+    dispatching::UpdateFilterExt,
+    dptree,
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardButtonKind},
+};
+
+use crate::bots::{
+    approved_bot::{
+        services::{
+            batch_downloader::{TaskObjectType, MAX_TASK_BOOKS},
+            book_library::{
+                formatters::{Format, FormatTitle},
+                get_author_books, get_sequence_books, get_translator_books,
+                types::{AuthorBook, Page, SearchBook, SequenceBook, TranslatorBook},
+                BookListFilter,
+            },
+            selection::{
+                create_selection, get_selection, toggle_selected, Selection, SelectionSource,
+                ToggleOutcome,
+            },
+            user_settings::{
+                get_user_or_default_lang_codes, get_user_page_size, get_user_preferred_formats,
+                pick_preferred_format,
+            },
+        },
+        tools::filter_callback_query,
+    },
+    BotHandlerInternal,
+};
+
+use self::callback_data::{SelectionCallbackData, SelectionList};
+
+use super::{
+    download::{
+        archive::start_archive_task,
+        keyboards::{get_books_archive_format_keyboard, BOOKS_ARCHIVE_FORMATS},
+    },
+    search::{query::search_book_by_query, utils::resolve_query},
+    utils::{
+        constants::{CHOOSE_FORMAT, ERROR_TRY_LATER, NOT_FOUND, REPEAT_SEARCH},
+        keyboard::{replace_callback_button, short_title},
+        pagination::{paginate, PaginationTexts},
+        telegram_utils::{
+            safe_answer_callback_query, safe_answer_callback_query_with_text,
+            safe_edit_message_reply_markup, safe_send_message,
+        },
+    },
+};
+
+const SELECTION_HEADER: &str = "☑️ Отметьте книги, чтобы скачать их одним архивом:\n\n";
+const SELECTION_EXPIRED: &str = "Выбор устарел, начните заново";
+const SELECTION_IS_EMPTY: &str = "Сначала отметьте книги";
+
+const CHECKED: &str = "✅";
+const UNCHECKED: &str = "⬜";
+
+/// Books of any listing that can be switched into select mode.
+pub trait ListedBook {
+    fn id(&self) -> u32;
+    fn title(&self) -> &str;
+}
+
+macro_rules! impl_listed_book {
+    ($($t:ty),*) => {
+        $(impl ListedBook for $t {
+            fn id(&self) -> u32 {
+                self.id
+            }
+
+            fn title(&self) -> &str {
+                &self.title
+            }
+        })*
+    };
+}
+
+impl_listed_book!(SearchBook, AuthorBook, TranslatorBook, SequenceBook);
+
+fn callback_button(text: String, data: SelectionCallbackData) -> InlineKeyboardButton {
+    InlineKeyboardButton {
+        text,
+        kind: InlineKeyboardButtonKind::CallbackData(data.to_string()),
+    }
+}
+
+/// "☑️ Выбрать книги" under a listing, opening it in select mode on `page`.
+pub fn get_select_button(list: SelectionList, page: u32) -> InlineKeyboardButton {
+    callback_button(
+        "☑️ Выбрать книги".to_string(),
+        SelectionCallbackData::Start { list, page },
+    )
+}
+
+fn get_checkbox_button(
+    sid: &str,
+    book_id: u32,
+    title: &str,
+    page: u32,
+    checked: bool,
+) -> InlineKeyboardButton {
+    let mark = if checked { CHECKED } else { UNCHECKED };
+
+    callback_button(
+        format!("{mark} {title}"),
+        SelectionCallbackData::Toggle {
+            sid: sid.to_string(),
+            book_id,
+            page,
+        },
+    )
+}
+
+fn get_archive_button(sid: &str, count: usize) -> InlineKeyboardButton {
+    callback_button(
+        format!("📦 Скачать выбранные ({count})"),
+        SelectionCallbackData::ArchiveMenu {
+            sid: sid.to_string(),
+        },
+    )
+}
+
+fn get_selection_rows<T: ListedBook>(
+    sid: &str,
+    items: &[T],
+    selected: &[u32],
+    page: u32,
+) -> Vec<Vec<InlineKeyboardButton>> {
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = items
+        .iter()
+        .map(|item| {
+            vec![get_checkbox_button(
+                sid,
+                item.id(),
+                &short_title(item.title()),
+                page,
+                selected.contains(&item.id()),
+            )]
+        })
+        .collect();
+
+    rows.push(vec![get_archive_button(sid, selected.len())]);
+
+    rows
+}
+
+/// Title of a checkbox button without its mark.
+fn checkbox_title(text: &str) -> &str {
+    text.strip_prefix(CHECKED)
+        .or_else(|| text.strip_prefix(UNCHECKED))
+        .map(str::trim_start)
+        .unwrap_or(text)
+}
+
+async fn show_page<T, P, Fut>(
+    bot: &CacheMe<Throttle<Bot>>,
+    cq: CallbackQuery,
+    sid: &str,
+    selected: &[u32],
+    page: u32,
+    fetcher: impl Fn(u32) -> Fut,
+) -> BotHandlerInternal
+where
+    T: Format + ListedBook + Clone + Debug,
+    P: FormatTitle + Clone + Debug,
+    Fut: std::future::Future<Output = anyhow::Result<Option<Page<T, P>>>>,
+{
+    let Some(message) = cq.message.as_ref() else {
+        return Ok(());
+    };
+    let (chat_id, message_id) = (message.chat().id, message.id());
+
+    paginate(
+        bot,
+        chat_id,
+        message_id,
+        cq.message,
+        page,
+        SELECTION_HEADER,
+        fetcher,
+        SelectionCallbackData::Page {
+            sid: sid.to_string(),
+            page,
+        },
+        |items| get_selection_rows(sid, items, selected, page),
+        PaginationTexts {
+            not_found: NOT_FOUND,
+            no_items: NOT_FOUND,
+            error_try_later: Some(ERROR_TRY_LATER),
+        },
+    )
+    .await
+}
+
+async fn show_selection_page(
+    bot: &CacheMe<Throttle<Bot>>,
+    cq: CallbackQuery,
+    sid: &str,
+    selection: Selection,
+    page: u32,
+) -> BotHandlerInternal {
+    let allowed_langs = get_user_or_default_lang_codes(cq.from.id).await;
+    let page_size = get_user_page_size(cq.from.id).await;
+    let selected = &selection.book_ids;
+
+    match selection.source {
+        SelectionSource::Search { query } => {
+            show_page(bot, cq, sid, selected, page, |p| {
+                search_book_by_query(query.clone(), p, page_size, allowed_langs.clone())
+            })
+            .await
+        }
+        SelectionSource::Author { id } => {
+            show_page(bot, cq, sid, selected, page, |p| {
+                get_author_books(
+                    id,
+                    p,
+                    page_size,
+                    allowed_langs.clone(),
+                    BookListFilter::default(),
+                )
+            })
+            .await
+        }
+        SelectionSource::Translator { id } => {
+            show_page(bot, cq, sid, selected, page, |p| {
+                get_translator_books(
+                    id,
+                    p,
+                    page_size,
+                    allowed_langs.clone(),
+                    BookListFilter::default(),
+                )
+            })
+            .await
+        }
+        SelectionSource::Sequence { id } => {
+            show_page(bot, cq, sid, selected, page, |p| {
+                get_sequence_books(
+                    id,
+                    p,
+                    page_size,
+                    allowed_langs.clone(),
+                    BookListFilter::default(),
+                )
+            })
+            .await
+        }
+    }
+}
+
+async fn start_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    list: SelectionList,
+    page: u32,
+) -> BotHandlerInternal {
+    let source = match list {
+        SelectionList::Search { qid } => match resolve_query(&cq, Some(&qid)).await {
+            Some(query) => SelectionSource::Search { query },
+            None => {
+                return safe_answer_callback_query_with_text(&bot, cq.id, REPEAT_SEARCH, true)
+                    .await;
+            }
+        },
+        SelectionList::Author { id } => SelectionSource::Author { id },
+        SelectionList::Translator { id } => SelectionSource::Translator { id },
+        SelectionList::Sequence { id } => SelectionSource::Sequence { id },
+    };
+
+    let user_id = cq.from.id.0;
+    let sid = create_selection(user_id, source.clone()).await;
+
+    safe_answer_callback_query(&bot, cq.id.clone()).await?;
+
+    let selection = Selection {
+        user_id,
+        source,
+        book_ids: vec![],
+    };
+
+    show_selection_page(&bot, cq, &sid, selection, page).await
+}
+
+async fn toggle_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    callback_data: SelectionCallbackData,
+    sid: String,
+    book_id: u32,
+) -> BotHandlerInternal {
+    let selection = match toggle_selected(&sid, cq.from.id.0, book_id).await {
+        ToggleOutcome::Toggled(selection) => selection,
+        ToggleOutcome::Full => {
+            return safe_answer_callback_query_with_text(
+                &bot,
+                cq.id,
+                &format!("В один архив помещается не больше {MAX_TASK_BOOKS} книг"),
+                true,
+            )
+            .await;
+        }
+        ToggleOutcome::Missing => {
+            return safe_answer_callback_query_with_text(&bot, cq.id, SELECTION_EXPIRED, true)
+                .await;
+        }
+    };
+
+    let checked = selection.book_ids.contains(&book_id);
+    let count = selection.book_ids.len();
+
+    safe_answer_callback_query(&bot, cq.id.clone()).await?;
+
+    let Some(message) = cq.regular_message() else {
+        return Ok(());
+    };
+    let Some(keyboard) = message.reply_markup() else {
+        return Ok(());
+    };
+
+    let data = callback_data.to_string();
+    let title = keyboard
+        .inline_keyboard
+        .iter()
+        .flatten()
+        .find(|button| matches!(&button.kind, InlineKeyboardButtonKind::CallbackData(v) if *v == data))
+        .map(|button| checkbox_title(&button.text).to_string())
+        .unwrap_or_default();
+
+    let page = match callback_data {
+        SelectionCallbackData::Toggle { page, .. } => page,
+        _ => 1,
+    };
+
+    let keyboard = replace_callback_button(
+        keyboard,
+        &data,
+        get_checkbox_button(&sid, book_id, &title, page, checked),
+    );
+    let keyboard = replace_callback_button(
+        &keyboard,
+        &SelectionCallbackData::ArchiveMenu { sid: sid.clone() }.to_string(),
+        get_archive_button(&sid, count),
+    );
+
+    safe_edit_message_reply_markup(&bot, message.chat.id, message.id, keyboard).await
+}
+
+#[log_handler("selection")]
+async fn selection_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    callback_data: SelectionCallbackData,
+) -> BotHandlerInternal {
+    let sid = match &callback_data {
+        SelectionCallbackData::Start { list, page } => {
+            return start_handler(cq, bot, list.clone(), *page).await;
+        }
+        SelectionCallbackData::Page { sid, .. }
+        | SelectionCallbackData::Toggle { sid, .. }
+        | SelectionCallbackData::ArchiveMenu { sid }
+        | SelectionCallbackData::Archive { sid, .. } => sid.clone(),
+    };
+
+    let user_id = cq.from.id;
+
+    let Some(selection) = get_selection(&sid, user_id.0).await else {
+        return safe_answer_callback_query_with_text(&bot, cq.id, SELECTION_EXPIRED, true).await;
+    };
+
+    match callback_data {
+        SelectionCallbackData::Start { .. } => Ok(()),
+        SelectionCallbackData::Page { page, .. } => {
+            show_selection_page(&bot, cq, &sid, selection, page).await
+        }
+        SelectionCallbackData::Toggle { book_id, .. } => {
+            toggle_handler(cq, bot, callback_data, sid, book_id).await
+        }
+        SelectionCallbackData::ArchiveMenu { .. } => {
+            if selection.book_ids.is_empty() {
+                return safe_answer_callback_query_with_text(&bot, cq.id, SELECTION_IS_EMPTY, true)
+                    .await;
+            }
+
+            let Some(message) = cq.message else {
+                return Ok(());
+            };
+
+            let preferred_formats = get_user_preferred_formats(user_id).await;
+            let keyboard = get_books_archive_format_keyboard(
+                pick_preferred_format(&preferred_formats, &BOOKS_ARCHIVE_FORMATS).copied(),
+                |file_type| {
+                    SelectionCallbackData::Archive {
+                        sid: sid.clone(),
+                        file_type: file_type.to_string(),
+                    }
+                    .to_string()
+                },
+            );
+
+            safe_send_message(&bot, message.chat().id, CHOOSE_FORMAT, Some(keyboard)).await
+        }
+        SelectionCallbackData::Archive { file_type, .. } => {
+            if selection.book_ids.is_empty() {
+                return safe_answer_callback_query_with_text(&bot, cq.id, SELECTION_IS_EMPTY, true)
+                    .await;
+            }
+
+            let Some(message) = cq.message else {
+                return Ok(());
+            };
+
+            start_archive_task(
+                bot,
+                message,
+                user_id,
+                TaskObjectType::Books {
+                    ids: selection.book_ids,
+                },
+                file_type,
+            )
+            .await
+        }
+    }
+}
+
+pub fn get_selection_handler() -> crate::bots::BotHandler {
+    dptree::entry().branch(
+        Update::filter_callback_query()
+            .chain(filter_callback_query::<SelectionCallbackData>())
+            .endpoint(selection_handler),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestBook {
+        id: u32,
+        title: String,
+    }
+
+    impl_listed_book!(TestBook);
+
+    #[test]
+    fn rows_mark_selected_books_and_count_them() {
+        let items = vec![
+            TestBook {
+                id: 1,
+                title: "Первая".to_string(),
+            },
+            TestBook {
+                id: 2,
+                title: "Вторая".to_string(),
+            },
+        ];
+
+        let rows = get_selection_rows("abc", &items, &[2, 99], 3);
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0][0].text, "⬜ Первая");
+        assert_eq!(rows[1][0].text, "✅ Вторая");
+        assert_eq!(rows[2][0].text, "📦 Скачать выбранные (2)");
+        assert!(matches!(
+            &rows[1][0].kind,
+            InlineKeyboardButtonKind::CallbackData(data) if data == "sel_abc_t_2_3"
+        ));
+    }
+
+    #[test]
+    fn checkbox_title_strips_the_mark() {
+        assert_eq!(checkbox_title("✅ Книга"), "Книга");
+        assert_eq!(checkbox_title("⬜ Книга"), "Книга");
+        assert_eq!(checkbox_title("Книга"), "Книга");
+    }
+}
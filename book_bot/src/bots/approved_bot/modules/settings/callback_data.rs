@@ -4,6 +4,8 @@ use regex::Regex;
 use smartstring::alias::String as SmartString;
 use std::sync::LazyLock;
 
+use crate::bots::approved_bot::services::digests::DigestFrequency;
+
 static RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^lang_(?P<action>(off)|(on))_(?P<code>[a-zA-Z]+)$").unwrap());
 
@@ -34,6 +36,66 @@ pub enum SettingsCallbackData {
     },
     /// Return from file name language submenu to main settings
     FileNameLangBack,
+    /// Open "preferred format" submenu
+    PreferredFormatsMenu,
+    /// Add the format to the end of the preference list, or drop it if
+    /// already there
+    PreferredFormat {
+        value: SmartString,
+    },
+    /// Clear the preference list
+    PreferredFormatsReset,
+    /// Return from preferred format submenu to main settings
+    PreferredFormatsBack,
+    /// Open "next book of the series" submenu
+    SeriesSuggestionsMenu,
+    /// Turn the next-in-series suggestion after a download on or off
+    SeriesSuggestions {
+        enabled: bool,
+    },
+    /// Return from next book of the series submenu to main settings
+    SeriesSuggestionsBack,
+    /// Open page size submenu
+    PageSizeMenu,
+    /// Set items per listing page
+    PageSize {
+        size: u32,
+    },
+    /// Return from page size submenu to main settings
+    PageSizeBack,
+    /// Open time zone submenu
+    TimezoneMenu,
+    /// Set time zone: offset from UTC in minutes
+    Timezone {
+        utc_offset: i32,
+    },
+    /// Return from time zone submenu to main settings
+    TimezoneBack,
+    /// Open new-arrivals digest submenu
+    DigestMenu,
+    /// Turn the digest off or set how often it comes
+    DigestFrequency {
+        frequency: Option<DigestFrequency>,
+    },
+    /// Set the local hour the digest comes at
+    DigestHour {
+        hour: u32,
+    },
+    /// Genre metas to narrow the digest to
+    DigestGenreMetas,
+    /// Genres of the meta at `index` in `get_genre_metas`
+    DigestGenreMeta {
+        index: u32,
+    },
+    /// Narrow the digest to the genre; `meta` is needed to look up its name
+    DigestGenre {
+        meta: u32,
+        genre_id: u32,
+    },
+    /// Send books of every genre
+    DigestGenreReset,
+    /// Return from digest submenu to main settings
+    DigestBack,
 }
 
 impl FromStr for SettingsCallbackData {
@@ -58,6 +120,92 @@ impl FromStr for SettingsCallbackData {
         if s == "filename_lang_back" {
             return Ok(SettingsCallbackData::FileNameLangBack);
         }
+        if s == "formats" {
+            return Ok(SettingsCallbackData::PreferredFormatsMenu);
+        }
+        if s == "formats_reset" {
+            return Ok(SettingsCallbackData::PreferredFormatsReset);
+        }
+        if s == "formats_back" {
+            return Ok(SettingsCallbackData::PreferredFormatsBack);
+        }
+        if s == "series_next" {
+            return Ok(SettingsCallbackData::SeriesSuggestionsMenu);
+        }
+        if s == "series_next_back" {
+            return Ok(SettingsCallbackData::SeriesSuggestionsBack);
+        }
+        if s == "series_next_on" {
+            return Ok(SettingsCallbackData::SeriesSuggestions { enabled: true });
+        }
+        if s == "series_next_off" {
+            return Ok(SettingsCallbackData::SeriesSuggestions { enabled: false });
+        }
+        if s == "page_size" {
+            return Ok(SettingsCallbackData::PageSizeMenu);
+        }
+        if s == "page_size_back" {
+            return Ok(SettingsCallbackData::PageSizeBack);
+        }
+        if let Some(size) = s.strip_prefix("page_size_") {
+            let size = size
+                .parse()
+                .map_err(|_| strum::ParseError::VariantNotFound)?;
+            return Ok(SettingsCallbackData::PageSize { size });
+        }
+        if s == "tz" {
+            return Ok(SettingsCallbackData::TimezoneMenu);
+        }
+        if s == "tz_back" {
+            return Ok(SettingsCallbackData::TimezoneBack);
+        }
+        if let Some(utc_offset) = s.strip_prefix("tz_") {
+            let utc_offset = utc_offset
+                .parse()
+                .map_err(|_| strum::ParseError::VariantNotFound)?;
+            return Ok(SettingsCallbackData::Timezone { utc_offset });
+        }
+        if s == "digest" {
+            return Ok(SettingsCallbackData::DigestMenu);
+        }
+        if s == "digest_back" {
+            return Ok(SettingsCallbackData::DigestBack);
+        }
+        if s == "digest_off" {
+            return Ok(SettingsCallbackData::DigestFrequency { frequency: None });
+        }
+        if s == "digest_gm" {
+            return Ok(SettingsCallbackData::DigestGenreMetas);
+        }
+        if s == "digest_g_all" {
+            return Ok(SettingsCallbackData::DigestGenreReset);
+        }
+        if let Some(hour) = s.strip_prefix("digest_hour_") {
+            let hour = hour
+                .parse()
+                .map_err(|_| strum::ParseError::VariantNotFound)?;
+            return Ok(SettingsCallbackData::DigestHour { hour });
+        }
+        if let Some(index) = s.strip_prefix("digest_gm_") {
+            let index = index
+                .parse()
+                .map_err(|_| strum::ParseError::VariantNotFound)?;
+            return Ok(SettingsCallbackData::DigestGenreMeta { index });
+        }
+        if let Some(genre) = s.strip_prefix("digest_g_") {
+            let (meta, genre_id) = genre
+                .split_once('_')
+                .and_then(|(meta, genre_id)| Some((meta.parse().ok()?, genre_id.parse().ok()?)))
+                .ok_or(strum::ParseError::VariantNotFound)?;
+            return Ok(SettingsCallbackData::DigestGenre { meta, genre_id });
+        }
+        if let Some(code) = s.strip_prefix("digest_") {
+            let frequency =
+                DigestFrequency::from_code(code).ok_or(strum::ParseError::VariantNotFound)?;
+            return Ok(SettingsCallbackData::DigestFrequency {
+                frequency: Some(frequency),
+            });
+        }
         if let Some(value) = s.strip_prefix("defsearch_") {
             return Ok(SettingsCallbackData::DefaultSearch {
                 value: value.to_string().into(),
@@ -69,6 +217,12 @@ impl FromStr for SettingsCallbackData {
             });
         }
 
+        if let Some(value) = s.strip_prefix("formats_") {
+            return Ok(SettingsCallbackData::PreferredFormat {
+                value: value.to_string().into(),
+            });
+        }
+
         let caps = RE.captures(s).ok_or(strum::ParseError::VariantNotFound)?;
 
         let action = &caps["action"];
@@ -95,6 +249,37 @@ impl Display for SettingsCallbackData {
             SettingsCallbackData::FileNameLangMenu => write!(f, "filename_lang"),
             SettingsCallbackData::FileNameLang { value } => write!(f, "filename_lang_{value}"),
             SettingsCallbackData::FileNameLangBack => write!(f, "filename_lang_back"),
+            SettingsCallbackData::PreferredFormatsMenu => write!(f, "formats"),
+            SettingsCallbackData::PreferredFormat { value } => write!(f, "formats_{value}"),
+            SettingsCallbackData::PreferredFormatsReset => write!(f, "formats_reset"),
+            SettingsCallbackData::PreferredFormatsBack => write!(f, "formats_back"),
+            SettingsCallbackData::SeriesSuggestionsMenu => write!(f, "series_next"),
+            SettingsCallbackData::SeriesSuggestions { enabled: true } => {
+                write!(f, "series_next_on")
+            }
+            SettingsCallbackData::SeriesSuggestions { enabled: false } => {
+                write!(f, "series_next_off")
+            }
+            SettingsCallbackData::SeriesSuggestionsBack => write!(f, "series_next_back"),
+            SettingsCallbackData::PageSizeMenu => write!(f, "page_size"),
+            SettingsCallbackData::PageSize { size } => write!(f, "page_size_{size}"),
+            SettingsCallbackData::PageSizeBack => write!(f, "page_size_back"),
+            SettingsCallbackData::TimezoneMenu => write!(f, "tz"),
+            SettingsCallbackData::Timezone { utc_offset } => write!(f, "tz_{utc_offset}"),
+            SettingsCallbackData::TimezoneBack => write!(f, "tz_back"),
+            SettingsCallbackData::DigestMenu => write!(f, "digest"),
+            SettingsCallbackData::DigestFrequency { frequency: None } => write!(f, "digest_off"),
+            SettingsCallbackData::DigestFrequency {
+                frequency: Some(frequency),
+            } => write!(f, "digest_{}", frequency.code()),
+            SettingsCallbackData::DigestHour { hour } => write!(f, "digest_hour_{hour}"),
+            SettingsCallbackData::DigestGenreMetas => write!(f, "digest_gm"),
+            SettingsCallbackData::DigestGenreMeta { index } => write!(f, "digest_gm_{index}"),
+            SettingsCallbackData::DigestGenre { meta, genre_id } => {
+                write!(f, "digest_g_{meta}_{genre_id}")
+            }
+            SettingsCallbackData::DigestGenreReset => write!(f, "digest_g_all"),
+            SettingsCallbackData::DigestBack => write!(f, "digest_back"),
         }
     }
 }
@@ -202,6 +387,99 @@ mod tests {
         }
     }
 
+    #[test]
+    fn round_trip_preferred_formats() {
+        let cd = SettingsCallbackData::PreferredFormat {
+            value: "epub".into(),
+        };
+        match SettingsCallbackData::from_str(&cd.to_string()).unwrap() {
+            SettingsCallbackData::PreferredFormat { value } => assert_eq!(value, "epub"),
+            _ => panic!("wrong variant"),
+        }
+
+        for cd in [
+            SettingsCallbackData::PreferredFormatsMenu,
+            SettingsCallbackData::PreferredFormatsReset,
+            SettingsCallbackData::PreferredFormatsBack,
+        ] {
+            let parsed = SettingsCallbackData::from_str(&cd.to_string()).unwrap();
+            assert_eq!(parsed.to_string(), cd.to_string());
+        }
+    }
+
+    #[test]
+    fn round_trip_series_suggestions() {
+        for cd in [
+            SettingsCallbackData::SeriesSuggestionsMenu,
+            SettingsCallbackData::SeriesSuggestions { enabled: true },
+            SettingsCallbackData::SeriesSuggestions { enabled: false },
+            SettingsCallbackData::SeriesSuggestionsBack,
+        ] {
+            let parsed = SettingsCallbackData::from_str(&cd.to_string()).unwrap();
+            assert_eq!(parsed.to_string(), cd.to_string());
+        }
+    }
+
+    #[test]
+    fn round_trip_page_size() {
+        for cd in [
+            SettingsCallbackData::PageSizeMenu,
+            SettingsCallbackData::PageSize { size: 10 },
+            SettingsCallbackData::PageSizeBack,
+        ] {
+            let parsed = SettingsCallbackData::from_str(&cd.to_string()).unwrap();
+            assert_eq!(parsed.to_string(), cd.to_string());
+        }
+
+        assert!(SettingsCallbackData::from_str("page_size_x").is_err());
+    }
+
+    #[test]
+    fn round_trip_timezone() {
+        for cd in [
+            SettingsCallbackData::TimezoneMenu,
+            SettingsCallbackData::Timezone { utc_offset: 180 },
+            SettingsCallbackData::Timezone { utc_offset: -300 },
+            SettingsCallbackData::TimezoneBack,
+        ] {
+            let parsed = SettingsCallbackData::from_str(&cd.to_string()).unwrap();
+            assert_eq!(parsed.to_string(), cd.to_string());
+        }
+
+        assert!(SettingsCallbackData::from_str("tz_msk").is_err());
+    }
+
+    #[test]
+    fn round_trip_digest() {
+        use crate::bots::approved_bot::services::digests::DigestFrequency;
+
+        for cd in [
+            SettingsCallbackData::DigestMenu,
+            SettingsCallbackData::DigestFrequency { frequency: None },
+            SettingsCallbackData::DigestFrequency {
+                frequency: Some(DigestFrequency::Daily),
+            },
+            SettingsCallbackData::DigestFrequency {
+                frequency: Some(DigestFrequency::Weekly),
+            },
+            SettingsCallbackData::DigestHour { hour: 21 },
+            SettingsCallbackData::DigestGenreMetas,
+            SettingsCallbackData::DigestGenreMeta { index: 2 },
+            SettingsCallbackData::DigestGenre {
+                meta: 2,
+                genre_id: 118,
+            },
+            SettingsCallbackData::DigestGenreReset,
+            SettingsCallbackData::DigestBack,
+        ] {
+            let parsed = SettingsCallbackData::from_str(&cd.to_string()).unwrap();
+            assert_eq!(parsed.to_string(), cd.to_string());
+        }
+
+        assert!(SettingsCallbackData::from_str("digest_x").is_err());
+        assert!(SettingsCallbackData::from_str("digest_g_1").is_err());
+    }
+
     #[test]
     fn accepts_multi_letter_language_code() {
         match SettingsCallbackData::from_str("lang_on_eng").unwrap() {
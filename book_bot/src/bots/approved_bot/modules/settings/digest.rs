@@ -0,0 +1,304 @@
+use teloxide::{
+    adaptors::{CacheMe, Throttle},
+    prelude::*,
+    types::{CallbackQueryId, InlineKeyboardButton, InlineKeyboardButtonKind, Me, MessageId},
+};
+
+use crate::bots::{
+    approved_bot::{
+        modules::{
+            genres::keyboards::{get_genre_metas_keyboard, get_genres_keyboard},
+            utils::{
+                constants::{ERROR_TRY_LATER, NOT_FOUND},
+                telegram_utils::{
+                    safe_answer_callback_query, safe_answer_callback_query_with_text,
+                    safe_edit_message_text,
+                },
+            },
+        },
+        services::{
+            book_library,
+            digests::{Digest, DigestFrequency, DIGESTS},
+            user_settings::{get_user_settings, UserPreferences},
+        },
+    },
+    BotHandlerInternal,
+};
+
+use super::{
+    callback_data::SettingsCallbackData,
+    keyboards::{format_utc_offset, get_digest_keyboard},
+};
+
+/// Local hour a newly enabled digest comes at.
+const DEFAULT_DIGEST_HOUR: u32 = 9;
+
+fn digest_text(digest: Option<&Digest>) -> String {
+    let status = match digest {
+        None => "выключена".to_string(),
+        Some(digest) => {
+            let frequency = match digest.frequency {
+                DigestFrequency::Daily => "каждый день",
+                DigestFrequency::Weekly => "раз в неделю",
+            };
+            let genre = match &digest.genre_name {
+                Some(name) => format!("жанр «{name}»"),
+                None => "все жанры".to_string(),
+            };
+
+            format!(
+                "{frequency} в {:02}:00 ({}), {genre}",
+                digest.hour,
+                format_utc_offset(digest.utc_offset)
+            )
+        }
+    };
+
+    format!(
+        "Подборка новинок: {status}\n\nНовые книги на выбранных языках приходят одним сообщением. \
+         Время считается по часовому поясу из настроек."
+    )
+}
+
+async fn show_digest(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    digest: Option<&Digest>,
+) -> BotHandlerInternal {
+    safe_edit_message_text(
+        bot,
+        chat_id,
+        message_id,
+        digest_text(digest),
+        Some(get_digest_keyboard(digest)),
+    )
+    .await
+}
+
+/// Applies `change` to the stored digest and shows the result. Without a
+/// digest only the callback is answered: the buttons are stale.
+async fn change_digest(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    cq_id: CallbackQueryId,
+    (bot_id, user_id): (u64, u64),
+    change: impl FnOnce(Digest) -> Digest,
+) -> BotHandlerInternal {
+    let Some(digest) = DIGESTS.get(bot_id, user_id).await? else {
+        return safe_answer_callback_query(bot, cq_id).await;
+    };
+
+    let digest = change(digest);
+    DIGESTS.save(bot_id, user_id, digest.clone()).await?;
+
+    show_digest(bot, chat_id, message_id, Some(&digest)).await?;
+    safe_answer_callback_query_with_text(bot, cq_id, "Готово", false).await
+}
+
+async fn set_frequency(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    cq_id: CallbackQueryId,
+    (bot_id, user_id): (u64, u64),
+    frequency: Option<DigestFrequency>,
+) -> BotHandlerInternal {
+    let Some(frequency) = frequency else {
+        DIGESTS.disable(bot_id, user_id).await?;
+        show_digest(bot, chat_id, message_id, None).await?;
+        return safe_answer_callback_query_with_text(bot, cq_id, "Готово", false).await;
+    };
+
+    let digest = match DIGESTS.get(bot_id, user_id).await? {
+        Some(digest) => Digest {
+            frequency,
+            ..digest
+        },
+        None => {
+            let settings = get_user_settings(UserId(user_id)).await.ok().flatten();
+
+            Digest {
+                frequency,
+                hour: DEFAULT_DIGEST_HOUR,
+                utc_offset: UserPreferences::from_settings(settings.as_ref()).utc_offset,
+                genre_id: None,
+                genre_name: None,
+            }
+        }
+    };
+    DIGESTS.save(bot_id, user_id, digest.clone()).await?;
+
+    show_digest(bot, chat_id, message_id, Some(&digest)).await?;
+    safe_answer_callback_query_with_text(bot, cq_id, "Готово", false).await
+}
+
+async fn show_genre_metas(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    cq_id: CallbackQueryId,
+) -> BotHandlerInternal {
+    let metas = match book_library::get_genre_metas().await {
+        Ok(v) => v.unwrap_or_default(),
+        Err(err) => {
+            safe_answer_callback_query_with_text(bot, cq_id, ERROR_TRY_LATER, true).await?;
+            return Err(err);
+        }
+    };
+
+    let mut keyboard = get_genre_metas_keyboard(metas, |index| {
+        SettingsCallbackData::DigestGenreMeta { index }.to_string()
+    });
+    keyboard.inline_keyboard.push(vec![InlineKeyboardButton {
+        text: "← Назад".to_string(),
+        kind: InlineKeyboardButtonKind::CallbackData(SettingsCallbackData::DigestMenu.to_string()),
+    }]);
+
+    safe_edit_message_text(
+        bot,
+        chat_id,
+        message_id,
+        "Подборка новинок\n\nВыбери раздел:",
+        Some(keyboard),
+    )
+    .await?;
+    safe_answer_callback_query(bot, cq_id).await
+}
+
+async fn show_genres(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    cq_id: CallbackQueryId,
+    index: u32,
+) -> BotHandlerInternal {
+    let (meta, genres) = match get_meta_genres(index).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            return safe_answer_callback_query_with_text(bot, cq_id, NOT_FOUND, true).await;
+        }
+        Err(err) => {
+            safe_answer_callback_query_with_text(bot, cq_id, ERROR_TRY_LATER, true).await?;
+            return Err(err);
+        }
+    };
+
+    let keyboard = get_genres_keyboard(
+        genres,
+        |genre_id| {
+            SettingsCallbackData::DigestGenre {
+                meta: index,
+                genre_id,
+            }
+            .to_string()
+        },
+        SettingsCallbackData::DigestGenreMetas.to_string(),
+    );
+
+    safe_edit_message_text(
+        bot,
+        chat_id,
+        message_id,
+        format!("Подборка новинок\n\n🗂 {meta}. Выбери жанр:"),
+        Some(keyboard),
+    )
+    .await?;
+    safe_answer_callback_query(bot, cq_id).await
+}
+
+async fn get_meta_genres(
+    index: u32,
+) -> anyhow::Result<Option<(String, Vec<book_library::types::Genre>)>> {
+    let metas = book_library::get_genre_metas().await?.unwrap_or_default();
+
+    let Some(meta) = metas.into_iter().nth(index as usize) else {
+        return Ok(None);
+    };
+
+    let genres = book_library::get_genres(meta.as_str().into()).await?;
+
+    Ok(genres.map(|genres| (meta, genres.items)))
+}
+
+/// Handles the `Digest*` callbacks of `/settings`.
+pub async fn digest_callback_handler(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    cq_id: CallbackQueryId,
+    user: &teloxide::types::User,
+    me: &Me,
+    callback_data: &SettingsCallbackData,
+) -> BotHandlerInternal {
+    let key = (me.id.0, user.id.0);
+
+    match *callback_data {
+        SettingsCallbackData::DigestMenu => {
+            let digest = DIGESTS.get(key.0, key.1).await?;
+            show_digest(bot, chat_id, message_id, digest.as_ref()).await?;
+            safe_answer_callback_query(bot, cq_id).await
+        }
+        SettingsCallbackData::DigestFrequency { frequency } => {
+            set_frequency(bot, chat_id, message_id, cq_id, key, frequency).await
+        }
+        SettingsCallbackData::DigestHour { hour } if hour < 24 => {
+            change_digest(bot, chat_id, message_id, cq_id, key, |digest| Digest {
+                hour,
+                ..digest
+            })
+            .await
+        }
+        SettingsCallbackData::DigestGenreMetas => {
+            show_genre_metas(bot, chat_id, message_id, cq_id).await
+        }
+        SettingsCallbackData::DigestGenreMeta { index } => {
+            show_genres(bot, chat_id, message_id, cq_id, index).await
+        }
+        SettingsCallbackData::DigestGenre { meta, genre_id } => {
+            let genre_name = get_meta_genres(meta)
+                .await
+                .ok()
+                .flatten()
+                .and_then(|(_, genres)| genres.into_iter().find(|genre| genre.id == genre_id))
+                .map(|genre| genre.description);
+
+            change_digest(bot, chat_id, message_id, cq_id, key, |digest| Digest {
+                genre_id: Some(genre_id),
+                genre_name,
+                ..digest
+            })
+            .await
+        }
+        SettingsCallbackData::DigestGenreReset => {
+            change_digest(bot, chat_id, message_id, cq_id, key, |digest| Digest {
+                genre_id: None,
+                genre_name: None,
+                ..digest
+            })
+            .await
+        }
+        _ => safe_answer_callback_query(bot, cq_id).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_describes_the_digest() {
+        assert!(digest_text(None).starts_with("Подборка новинок: выключена"));
+
+        let digest = Digest {
+            frequency: DigestFrequency::Weekly,
+            hour: 8,
+            utc_offset: 180,
+            genre_id: Some(5),
+            genre_name: Some("Фантастика".to_string()),
+        };
+        assert!(digest_text(Some(&digest))
+            .starts_with("Подборка новинок: раз в неделю в 08:00 (UTC+3), жанр «Фантастика»"));
+    }
+}
@@ -3,10 +3,16 @@ use std::collections::HashSet;
 use smartstring::alias::String as SmartString;
 use teloxide::types::{InlineKeyboardButton, InlineKeyboardButtonKind, InlineKeyboardMarkup};
 
-use crate::bots::approved_bot::services::user_settings::{DefaultSearchType, FileNameLang, Lang};
+use crate::bots::approved_bot::services::{
+    digests::{Digest, DigestFrequency},
+    user_settings::{DefaultSearchType, FileNameLang, Lang, PAGE_SIZES, UTC_OFFSETS},
+};
 
 use super::callback_data::SettingsCallbackData;
 
+/// Formats offered in the "preferred format" submenu.
+pub const PREFERRED_FORMAT_CHOICES: [&str; 5] = ["fb2", "epub", "mobi", "pdf", "djvu"];
+
 pub fn get_main_settings_keyboard() -> InlineKeyboardMarkup {
     InlineKeyboardMarkup {
         inline_keyboard: vec![
@@ -28,6 +34,36 @@ pub fn get_main_settings_keyboard() -> InlineKeyboardMarkup {
                     SettingsCallbackData::FileNameLangMenu.to_string(),
                 ),
             }],
+            vec![InlineKeyboardButton {
+                text: "Формат скачивания".to_string(),
+                kind: InlineKeyboardButtonKind::CallbackData(
+                    SettingsCallbackData::PreferredFormatsMenu.to_string(),
+                ),
+            }],
+            vec![InlineKeyboardButton {
+                text: "Следующая книга серии".to_string(),
+                kind: InlineKeyboardButtonKind::CallbackData(
+                    SettingsCallbackData::SeriesSuggestionsMenu.to_string(),
+                ),
+            }],
+            vec![InlineKeyboardButton {
+                text: "Размер страницы".to_string(),
+                kind: InlineKeyboardButtonKind::CallbackData(
+                    SettingsCallbackData::PageSizeMenu.to_string(),
+                ),
+            }],
+            vec![InlineKeyboardButton {
+                text: "Часовой пояс".to_string(),
+                kind: InlineKeyboardButtonKind::CallbackData(
+                    SettingsCallbackData::TimezoneMenu.to_string(),
+                ),
+            }],
+            vec![InlineKeyboardButton {
+                text: "Подборка новинок".to_string(),
+                kind: InlineKeyboardButtonKind::CallbackData(
+                    SettingsCallbackData::DigestMenu.to_string(),
+                ),
+            }],
         ],
     }
 }
@@ -109,6 +145,15 @@ pub fn get_default_search_keyboard(current: Option<DefaultSearchType>) -> Inline
                     .to_string(),
                 ),
             }],
+            vec![InlineKeyboardButton {
+                text: format!("Всё сразу{}", check(DefaultSearchType::All)),
+                kind: InlineKeyboardButtonKind::CallbackData(
+                    SettingsCallbackData::DefaultSearch {
+                        value: "all".into(),
+                    }
+                    .to_string(),
+                ),
+            }],
             vec![InlineKeyboardButton {
                 text: format!("Не выбрано{}", if current.is_none() { " ✓" } else { "" }),
                 kind: InlineKeyboardButtonKind::CallbackData(
@@ -159,3 +204,201 @@ pub fn get_file_name_lang_keyboard(current: FileNameLang) -> InlineKeyboardMarku
         ],
     }
 }
+
+pub fn get_series_suggestions_keyboard(current: bool) -> InlineKeyboardMarkup {
+    let check = |v: bool| if current == v { " ✓" } else { "" };
+    InlineKeyboardMarkup {
+        inline_keyboard: vec![
+            vec![InlineKeyboardButton {
+                text: format!("Предлагать{}", check(true)),
+                kind: InlineKeyboardButtonKind::CallbackData(
+                    SettingsCallbackData::SeriesSuggestions { enabled: true }.to_string(),
+                ),
+            }],
+            vec![InlineKeyboardButton {
+                text: format!("Не предлагать{}", check(false)),
+                kind: InlineKeyboardButtonKind::CallbackData(
+                    SettingsCallbackData::SeriesSuggestions { enabled: false }.to_string(),
+                ),
+            }],
+            vec![InlineKeyboardButton {
+                text: "← Назад".to_string(),
+                kind: InlineKeyboardButtonKind::CallbackData(
+                    SettingsCallbackData::SeriesSuggestionsBack.to_string(),
+                ),
+            }],
+        ],
+    }
+}
+
+pub fn get_page_size_keyboard(current: u32) -> InlineKeyboardMarkup {
+    let mut inline_keyboard: Vec<Vec<InlineKeyboardButton>> = vec![PAGE_SIZES
+        .iter()
+        .map(|size| InlineKeyboardButton {
+            text: if *size == current {
+                format!("{size} ✓")
+            } else {
+                size.to_string()
+            },
+            kind: InlineKeyboardButtonKind::CallbackData(
+                SettingsCallbackData::PageSize { size: *size }.to_string(),
+            ),
+        })
+        .collect()];
+
+    inline_keyboard.push(vec![InlineKeyboardButton {
+        text: "← Назад".to_string(),
+        kind: InlineKeyboardButtonKind::CallbackData(
+            SettingsCallbackData::PageSizeBack.to_string(),
+        ),
+    }]);
+
+    InlineKeyboardMarkup { inline_keyboard }
+}
+
+/// "UTC+3", "UTC-5", "UTC+5:30".
+pub fn format_utc_offset(utc_offset: i32) -> String {
+    if utc_offset == 0 {
+        return "UTC".to_string();
+    }
+
+    let sign = if utc_offset > 0 { '+' } else { '-' };
+    let (hours, minutes) = (utc_offset.abs() / 60, utc_offset.abs() % 60);
+
+    match minutes {
+        0 => format!("UTC{sign}{hours}"),
+        _ => format!("UTC{sign}{hours}:{minutes:02}"),
+    }
+}
+
+/// Whole hour offsets, six per row.
+pub fn get_timezone_keyboard(current: i32) -> InlineKeyboardMarkup {
+    let buttons: Vec<InlineKeyboardButton> = UTC_OFFSETS
+        .step_by(60)
+        .map(|utc_offset| InlineKeyboardButton {
+            text: if utc_offset == current {
+                format!("{} ✓", format_utc_offset(utc_offset))
+            } else {
+                format_utc_offset(utc_offset)
+            },
+            kind: InlineKeyboardButtonKind::CallbackData(
+                SettingsCallbackData::Timezone { utc_offset }.to_string(),
+            ),
+        })
+        .collect();
+
+    let mut inline_keyboard: Vec<Vec<InlineKeyboardButton>> =
+        buttons.chunks(6).map(|row| row.to_vec()).collect();
+
+    inline_keyboard.push(vec![InlineKeyboardButton {
+        text: "← Назад".to_string(),
+        kind: InlineKeyboardButtonKind::CallbackData(
+            SettingsCallbackData::TimezoneBack.to_string(),
+        ),
+    }]);
+
+    InlineKeyboardMarkup { inline_keyboard }
+}
+
+/// Frequency switch; the hour and genre choice only while the digest is on.
+pub fn get_digest_keyboard(current: Option<&Digest>) -> InlineKeyboardMarkup {
+    let button = |text: String, data: SettingsCallbackData| InlineKeyboardButton {
+        text,
+        kind: InlineKeyboardButtonKind::CallbackData(data.to_string()),
+    };
+    let frequency = current.map(|digest| digest.frequency);
+
+    let mut inline_keyboard: Vec<Vec<InlineKeyboardButton>> = vec![[
+        (None, "Выключена"),
+        (Some(DigestFrequency::Daily), "Каждый день"),
+        (Some(DigestFrequency::Weekly), "Раз в неделю"),
+    ]
+    .into_iter()
+    .map(|(value, text)| {
+        let text = if value == frequency {
+            format!("{text} ✓")
+        } else {
+            text.to_string()
+        };
+
+        button(
+            text,
+            SettingsCallbackData::DigestFrequency { frequency: value },
+        )
+    })
+    .collect()];
+
+    if let Some(digest) = current {
+        let hours: Vec<InlineKeyboardButton> = (0..24)
+            .map(|hour| {
+                let text = if hour == digest.hour {
+                    format!("{hour:02}:00 ✓")
+                } else {
+                    format!("{hour:02}:00")
+                };
+
+                button(text, SettingsCallbackData::DigestHour { hour })
+            })
+            .collect();
+        inline_keyboard.extend(hours.chunks(6).map(|row| row.to_vec()));
+
+        let mut genre_row = vec![button(
+            "🗂 Жанр".to_string(),
+            SettingsCallbackData::DigestGenreMetas,
+        )];
+        if digest.genre_id.is_some() {
+            genre_row.push(button(
+                "✖️ Все жанры".to_string(),
+                SettingsCallbackData::DigestGenreReset,
+            ));
+        }
+        inline_keyboard.push(genre_row);
+    }
+
+    inline_keyboard.push(vec![button(
+        "← Назад".to_string(),
+        SettingsCallbackData::DigestBack,
+    )]);
+
+    InlineKeyboardMarkup { inline_keyboard }
+}
+
+/// One button per format; chosen ones carry their position in the list.
+pub fn get_preferred_formats_keyboard(current: &[SmartString]) -> InlineKeyboardMarkup {
+    let mut buttons: Vec<Vec<InlineKeyboardButton>> = PREFERRED_FORMAT_CHOICES
+        .iter()
+        .map(|format| {
+            let text = match current.iter().position(|v| v == format) {
+                Some(index) => format!("{}. {format}", index + 1),
+                None => format.to_string(),
+            };
+
+            vec![InlineKeyboardButton {
+                text,
+                kind: InlineKeyboardButtonKind::CallbackData(
+                    SettingsCallbackData::PreferredFormat {
+                        value: (*format).into(),
+                    }
+                    .to_string(),
+                ),
+            }]
+        })
+        .collect();
+
+    buttons.push(vec![InlineKeyboardButton {
+        text: "Сбросить".to_string(),
+        kind: InlineKeyboardButtonKind::CallbackData(
+            SettingsCallbackData::PreferredFormatsReset.to_string(),
+        ),
+    }]);
+    buttons.push(vec![InlineKeyboardButton {
+        text: "← Назад".to_string(),
+        kind: InlineKeyboardButtonKind::CallbackData(
+            SettingsCallbackData::PreferredFormatsBack.to_string(),
+        ),
+    }]);
+
+    InlineKeyboardMarkup {
+        inline_keyboard: buttons,
+    }
+}
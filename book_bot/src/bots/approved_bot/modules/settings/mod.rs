@@ -1,5 +1,6 @@
 pub mod callback_data;
 pub mod commands;
+pub mod digest;
 pub mod keyboards;
 
 use book_bot_macros::log_handler;
@@ -15,9 +16,12 @@ use crate::bots::{
             safe_answer_callback_query, safe_answer_callback_query_with_text,
             safe_edit_message_reply_markup, safe_edit_message_text, safe_send_message,
         },
-        services::user_settings::{
-            get_langs, get_user_or_default_lang_codes, get_user_settings, save_user_settings,
-            DefaultSearchType, FileNameLang,
+        services::{
+            digests::DIGESTS,
+            user_settings::{
+                get_langs, get_user_or_default_lang_codes, get_user_settings, save_user_settings,
+                DefaultSearchType, FileNameLang, UserPreferences, PAGE_SIZES, UTC_OFFSETS,
+            },
         },
         tools::filter_callback_query,
     },
@@ -30,12 +34,17 @@ use teloxide::{
     types::{CallbackQueryId, Me, MessageId},
 };
 
+use tracing::log;
+
 use self::{
     callback_data::SettingsCallbackData,
     commands::SettingsCommand,
+    digest::digest_callback_handler,
     keyboards::{
-        get_default_search_keyboard, get_file_name_lang_keyboard, get_lang_keyboard,
-        get_main_settings_keyboard,
+        format_utc_offset, get_default_search_keyboard, get_file_name_lang_keyboard,
+        get_lang_keyboard, get_main_settings_keyboard, get_page_size_keyboard,
+        get_preferred_formats_keyboard, get_series_suggestions_keyboard, get_timezone_keyboard,
+        PREFERRED_FORMAT_CHOICES,
     },
 };
 
@@ -110,6 +119,256 @@ async fn show_file_name_lang_menu(
     Ok(())
 }
 
+const SERIES_SUGGESTIONS_TEXT: &str =
+    "Следующая книга серии\n\nПосле скачивания книги из серии предлагать следующую.";
+
+async fn show_series_suggestions_menu(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    cq_id: CallbackQueryId,
+    user_id: UserId,
+) -> BotHandlerInternal {
+    let current = get_user_settings(user_id).await.ok().flatten();
+    let preferences = UserPreferences::from_settings(current.as_ref());
+    let keyboard = get_series_suggestions_keyboard(preferences.series_suggestions);
+    safe_edit_message_text(
+        bot,
+        chat_id,
+        message_id,
+        SERIES_SUGGESTIONS_TEXT,
+        Some(keyboard),
+    )
+    .await?;
+    safe_answer_callback_query(bot, cq_id).await?;
+    Ok(())
+}
+
+async fn handle_series_suggestions(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    cq_id: CallbackQueryId,
+    user: &teloxide::types::User,
+    me: &Me,
+    enabled: bool,
+) -> BotHandlerInternal {
+    let current = get_user_settings(user.id).await.ok().flatten();
+    let allowed_langs: SmallVec<[SmartString; 3]> = match current.as_ref() {
+        Some(s) => s.allowed_langs.iter().map(|l| l.code.clone()).collect(),
+        None => get_user_or_default_lang_codes(user.id).await,
+    };
+    let preferences = UserPreferences {
+        series_suggestions: enabled,
+        ..UserPreferences::from_settings(current.as_ref())
+    };
+
+    if save_user_settings(user, me, allowed_langs, preferences)
+        .await
+        .is_err()
+    {
+        safe_answer_callback_query_with_text(bot, cq_id, "Ошибка! Попробуйте заново(", true)
+            .await?;
+        return Ok(());
+    }
+
+    safe_edit_message_text(
+        bot,
+        chat_id,
+        message_id,
+        "Настройки",
+        Some(get_main_settings_keyboard()),
+    )
+    .await?;
+    safe_answer_callback_query_with_text(bot, cq_id, "Готово", false).await?;
+    Ok(())
+}
+
+const PAGE_SIZE_TEXT: &str =
+    "Размер страницы\n\nСколько книг, авторов или серий показывать на одной странице списка.";
+
+async fn show_page_size_menu(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    cq_id: CallbackQueryId,
+    user_id: UserId,
+) -> BotHandlerInternal {
+    let current = get_user_settings(user_id).await.ok().flatten();
+    let preferences = UserPreferences::from_settings(current.as_ref());
+    let keyboard = get_page_size_keyboard(preferences.page_size);
+    safe_edit_message_text(bot, chat_id, message_id, PAGE_SIZE_TEXT, Some(keyboard)).await?;
+    safe_answer_callback_query(bot, cq_id).await?;
+    Ok(())
+}
+
+async fn handle_page_size(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    cq_id: CallbackQueryId,
+    user: &teloxide::types::User,
+    me: &Me,
+    size: u32,
+) -> BotHandlerInternal {
+    if !PAGE_SIZES.contains(&size) {
+        safe_answer_callback_query(bot, cq_id).await?;
+        return Ok(());
+    }
+
+    let current = get_user_settings(user.id).await.ok().flatten();
+    let allowed_langs: SmallVec<[SmartString; 3]> = match current.as_ref() {
+        Some(s) => s.allowed_langs.iter().map(|l| l.code.clone()).collect(),
+        None => get_user_or_default_lang_codes(user.id).await,
+    };
+    let preferences = UserPreferences {
+        page_size: size,
+        ..UserPreferences::from_settings(current.as_ref())
+    };
+
+    if save_user_settings(user, me, allowed_langs, preferences)
+        .await
+        .is_err()
+    {
+        safe_answer_callback_query_with_text(bot, cq_id, "Ошибка! Попробуйте заново(", true)
+            .await?;
+        return Ok(());
+    }
+
+    safe_edit_message_text(
+        bot,
+        chat_id,
+        message_id,
+        PAGE_SIZE_TEXT,
+        Some(get_page_size_keyboard(size)),
+    )
+    .await?;
+    safe_answer_callback_query_with_text(bot, cq_id, "Готово", false).await?;
+    Ok(())
+}
+
+fn timezone_text(utc_offset: i32) -> String {
+    format!(
+        "Часовой пояс: {}\n\nПо нему считаются «сегодня» и периоды в /update_log.",
+        format_utc_offset(utc_offset)
+    )
+}
+
+async fn show_timezone_menu(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    cq_id: CallbackQueryId,
+    user_id: UserId,
+) -> BotHandlerInternal {
+    let current = get_user_settings(user_id).await.ok().flatten();
+    let preferences = UserPreferences::from_settings(current.as_ref());
+    safe_edit_message_text(
+        bot,
+        chat_id,
+        message_id,
+        timezone_text(preferences.utc_offset),
+        Some(get_timezone_keyboard(preferences.utc_offset)),
+    )
+    .await?;
+    safe_answer_callback_query(bot, cq_id).await?;
+    Ok(())
+}
+
+async fn handle_timezone(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    cq_id: CallbackQueryId,
+    user: &teloxide::types::User,
+    me: &Me,
+    utc_offset: i32,
+) -> BotHandlerInternal {
+    if !UTC_OFFSETS.contains(&utc_offset) {
+        safe_answer_callback_query(bot, cq_id).await?;
+        return Ok(());
+    }
+
+    let current = get_user_settings(user.id).await.ok().flatten();
+    let allowed_langs: SmallVec<[SmartString; 3]> = match current.as_ref() {
+        Some(s) => s.allowed_langs.iter().map(|l| l.code.clone()).collect(),
+        None => get_user_or_default_lang_codes(user.id).await,
+    };
+    let preferences = UserPreferences {
+        utc_offset,
+        ..UserPreferences::from_settings(current.as_ref())
+    };
+
+    if save_user_settings(user, me, allowed_langs, preferences)
+        .await
+        .is_err()
+    {
+        safe_answer_callback_query_with_text(bot, cq_id, "Ошибка! Попробуйте заново(", true)
+            .await?;
+        return Ok(());
+    }
+
+    // The digest keeps its own copy to be scheduled without the settings.
+    if let Err(err) = DIGESTS.set_utc_offset(me.id.0, user.id.0, utc_offset).await {
+        log::warn!("Failed to update digest time zone: {err:?}");
+    }
+
+    safe_edit_message_text(
+        bot,
+        chat_id,
+        message_id,
+        timezone_text(utc_offset),
+        Some(get_timezone_keyboard(utc_offset)),
+    )
+    .await?;
+    safe_answer_callback_query_with_text(bot, cq_id, "Готово", false).await?;
+    Ok(())
+}
+
+fn preferred_formats_text(current: &[SmartString]) -> String {
+    let current = match current.is_empty() {
+        true => "не выбран — /d_ показывает все форматы".to_string(),
+        false => current.join(" > "),
+    };
+
+    format!(
+        "Формат скачивания: {current}\n\n\
+        Выбирайте форматы в порядке предпочтения, /d_ будет сразу присылать первый доступный."
+    )
+}
+
+/// Appends `value` to the preference list, or removes it if already chosen.
+fn toggle_preferred_format(
+    current: &[SmartString],
+    value: &SmartString,
+) -> SmallVec<[SmartString; 3]> {
+    match current.contains(value) {
+        true => current.iter().filter(|v| *v != value).cloned().collect(),
+        false => current.iter().chain([value]).cloned().collect(),
+    }
+}
+
+async fn show_preferred_formats_menu(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    cq_id: CallbackQueryId,
+    user_id: UserId,
+) -> BotHandlerInternal {
+    let current = get_user_settings(user_id).await.ok().flatten();
+    let preferred_formats = current.map(|s| s.preferred_formats).unwrap_or_default();
+    safe_edit_message_text(
+        bot,
+        chat_id,
+        message_id,
+        preferred_formats_text(&preferred_formats),
+        Some(get_preferred_formats_keyboard(&preferred_formats)),
+    )
+    .await?;
+    safe_answer_callback_query(bot, cq_id).await?;
+    Ok(())
+}
+
 async fn handle_default_search(
     bot: &CacheMe<Throttle<Bot>>,
     chat_id: ChatId,
@@ -132,12 +391,12 @@ async fn handle_default_search(
         safe_answer_callback_query(bot, cq_id).await?;
         return Ok(());
     };
-    let file_name_lang = current
-        .as_ref()
-        .map(|s| s.file_name_lang)
-        .unwrap_or_default();
+    let preferences = UserPreferences {
+        default_search,
+        ..UserPreferences::from_settings(current.as_ref())
+    };
 
-    if save_user_settings(user, me, allowed_langs, default_search, file_name_lang)
+    if save_user_settings(user, me, allowed_langs, preferences)
         .await
         .is_err()
     {
@@ -179,9 +438,12 @@ async fn handle_file_name_lang(
         Some(s) => s.allowed_langs.iter().map(|l| l.code.clone()).collect(),
         None => get_user_or_default_lang_codes(user.id).await,
     };
-    let default_search = current.as_ref().and_then(|s| s.default_search);
+    let preferences = UserPreferences {
+        file_name_lang,
+        ..UserPreferences::from_settings(current.as_ref())
+    };
 
-    if save_user_settings(user, me, allowed_langs, default_search, file_name_lang)
+    if save_user_settings(user, me, allowed_langs, preferences)
         .await
         .is_err()
     {
@@ -202,6 +464,57 @@ async fn handle_file_name_lang(
     Ok(())
 }
 
+async fn handle_preferred_formats(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    message_id: MessageId,
+    cq_id: CallbackQueryId,
+    user: &teloxide::types::User,
+    me: &Me,
+    callback_data: &SettingsCallbackData,
+) -> BotHandlerInternal {
+    let current = get_user_settings(user.id).await.ok().flatten();
+    let allowed_langs: SmallVec<[SmartString; 3]> = match current.as_ref() {
+        Some(s) => s.allowed_langs.iter().map(|l| l.code.clone()).collect(),
+        None => get_user_or_default_lang_codes(user.id).await,
+    };
+    let mut preferences = UserPreferences::from_settings(current.as_ref());
+
+    preferences.preferred_formats = match callback_data {
+        SettingsCallbackData::PreferredFormat { value }
+            if PREFERRED_FORMAT_CHOICES.contains(&value.as_str()) =>
+        {
+            toggle_preferred_format(&preferences.preferred_formats, value)
+        }
+        SettingsCallbackData::PreferredFormatsReset => SmallVec::new(),
+        _ => {
+            safe_answer_callback_query(bot, cq_id).await?;
+            return Ok(());
+        }
+    };
+    let preferred_formats = preferences.preferred_formats.clone();
+
+    if save_user_settings(user, me, allowed_langs, preferences)
+        .await
+        .is_err()
+    {
+        safe_answer_callback_query_with_text(bot, cq_id, "Ошибка! Попробуйте заново(", true)
+            .await?;
+        return Ok(());
+    }
+
+    safe_edit_message_text(
+        bot,
+        chat_id,
+        message_id,
+        preferred_formats_text(&preferred_formats),
+        Some(get_preferred_formats_keyboard(&preferred_formats)),
+    )
+    .await?;
+    safe_answer_callback_query_with_text(bot, cq_id, "Готово", false).await?;
+    Ok(())
+}
+
 async fn handle_lang_toggle(
     bot: &CacheMe<Throttle<Bot>>,
     chat_id: ChatId,
@@ -242,18 +555,12 @@ async fn handle_lang_toggle(
     }
 
     let current_settings = get_user_settings(user.id).await.ok().flatten();
-    let default_search = current_settings.as_ref().and_then(|s| s.default_search);
-    let file_name_lang = current_settings
-        .as_ref()
-        .map(|s| s.file_name_lang)
-        .unwrap_or_default();
 
     if let Err(err) = save_user_settings(
         user,
         me,
         allowed_langs_set.clone().into_iter().collect(),
-        default_search,
-        file_name_lang,
+        UserPreferences::from_settings(current_settings.as_ref()),
     )
     .await
     {
@@ -301,12 +608,53 @@ async fn settings_callback_handler(
         }
         SettingsCallbackData::DefaultSearchBack
         | SettingsCallbackData::FileNameLangBack
-        | SettingsCallbackData::LangSettingsBack => {
+        | SettingsCallbackData::LangSettingsBack
+        | SettingsCallbackData::PreferredFormatsBack
+        | SettingsCallbackData::SeriesSuggestionsBack
+        | SettingsCallbackData::PageSizeBack
+        | SettingsCallbackData::TimezoneBack
+        | SettingsCallbackData::DigestBack => {
             show_main_menu(&bot, chat_id, message_id, cq.id).await
         }
         SettingsCallbackData::FileNameLangMenu => {
             show_file_name_lang_menu(&bot, chat_id, message_id, cq.id, user.id).await
         }
+        SettingsCallbackData::PreferredFormatsMenu => {
+            show_preferred_formats_menu(&bot, chat_id, message_id, cq.id, user.id).await
+        }
+        SettingsCallbackData::SeriesSuggestionsMenu => {
+            show_series_suggestions_menu(&bot, chat_id, message_id, cq.id, user.id).await
+        }
+        SettingsCallbackData::PageSizeMenu => {
+            show_page_size_menu(&bot, chat_id, message_id, cq.id, user.id).await
+        }
+        SettingsCallbackData::PageSize { size } => {
+            handle_page_size(&bot, chat_id, message_id, cq.id, &user, &me, *size).await
+        }
+        SettingsCallbackData::TimezoneMenu => {
+            show_timezone_menu(&bot, chat_id, message_id, cq.id, user.id).await
+        }
+        SettingsCallbackData::Timezone { utc_offset } => {
+            handle_timezone(&bot, chat_id, message_id, cq.id, &user, &me, *utc_offset).await
+        }
+        SettingsCallbackData::DigestMenu
+        | SettingsCallbackData::DigestFrequency { .. }
+        | SettingsCallbackData::DigestHour { .. }
+        | SettingsCallbackData::DigestGenreMetas
+        | SettingsCallbackData::DigestGenreMeta { .. }
+        | SettingsCallbackData::DigestGenre { .. }
+        | SettingsCallbackData::DigestGenreReset => {
+            digest_callback_handler(&bot, chat_id, message_id, cq.id, &user, &me, &callback_data)
+                .await
+        }
+        SettingsCallbackData::SeriesSuggestions { enabled } => {
+            handle_series_suggestions(&bot, chat_id, message_id, cq.id, &user, &me, *enabled).await
+        }
+        SettingsCallbackData::PreferredFormat { .. }
+        | SettingsCallbackData::PreferredFormatsReset => {
+            handle_preferred_formats(&bot, chat_id, message_id, cq.id, &user, &me, &callback_data)
+                .await
+        }
         SettingsCallbackData::DefaultSearch { value } => {
             handle_default_search(&bot, chat_id, message_id, cq.id, &user, &me, value).await
         }
@@ -336,3 +684,39 @@ pub fn get_settings_handler() -> crate::bots::BotHandler {
                 .endpoint(settings_callback_handler),
         )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formats(values: &[&str]) -> Vec<SmartString> {
+        values.iter().map(|v| (*v).into()).collect()
+    }
+
+    #[test]
+    fn toggle_appends_new_and_removes_chosen_formats() {
+        let current = formats(&["fb2", "epub"]);
+
+        assert_eq!(
+            toggle_preferred_format(&current, &"mobi".into()).into_vec(),
+            formats(&["fb2", "epub", "mobi"])
+        );
+        assert_eq!(
+            toggle_preferred_format(&current, &"fb2".into()).into_vec(),
+            formats(&["epub"])
+        );
+    }
+
+    #[test]
+    fn timezone_text_names_the_offset() {
+        assert!(timezone_text(180).contains("UTC+3"));
+        assert!(timezone_text(-330).contains("UTC-5:30"));
+        assert!(timezone_text(0).contains("UTC\n"));
+    }
+
+    #[test]
+    fn text_shows_preference_order() {
+        assert!(preferred_formats_text(&formats(&["fb2", "epub"])).contains("fb2 > epub"));
+        assert!(preferred_formats_text(&[]).contains("не выбран"));
+    }
+}
@@ -0,0 +1,142 @@
+use std::{fmt::Display, str::FromStr};
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::bots::approved_bot::modules::utils::{
+    errors::CallbackQueryParseError, pagination::GetPaginationCallbackData,
+};
+
+static RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^shelf_(?:(?P<page>\d+)|add_(?P<add_id>\d+)|tg_(?P<toggle_id>\d+)|rm_(?P<remove_id>\d+)_(?P<remove_page>\d+)|da|da_(?P<file_type>\w+))$",
+    )
+    .unwrap()
+});
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShelfCallbackData {
+    /// A page of the shelf listing.
+    Page {
+        page: u32,
+    },
+    /// "⭐" on a search result: adds the book.
+    Add {
+        book_id: u32,
+    },
+    /// "⭐"/"❌" on a book card: flips the book's shelf state.
+    Toggle {
+        book_id: u32,
+    },
+    /// "❌" on the shelf listing: removes the book and redraws `page`.
+    Remove {
+        book_id: u32,
+        page: u32,
+    },
+    /// Format choice for the whole-shelf archive.
+    ArchiveMenu,
+    Archive {
+        file_type: String,
+    },
+}
+
+impl FromStr for ShelfCallbackData {
+    type Err = CallbackQueryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let caps = RE.captures(s).ok_or(CallbackQueryParseError)?;
+
+        let number = |name: &str| -> Result<Option<u32>, CallbackQueryParseError> {
+            caps.name(name)
+                .map(|m| m.as_str().parse().map_err(|_| CallbackQueryParseError))
+                .transpose()
+        };
+
+        if let Some(page) = number("page")? {
+            return Ok(ShelfCallbackData::Page {
+                page: std::cmp::max(1, page),
+            });
+        }
+        if let Some(book_id) = number("add_id")? {
+            return Ok(ShelfCallbackData::Add { book_id });
+        }
+        if let Some(book_id) = number("toggle_id")? {
+            return Ok(ShelfCallbackData::Toggle { book_id });
+        }
+        if let (Some(book_id), Some(page)) = (number("remove_id")?, number("remove_page")?) {
+            return Ok(ShelfCallbackData::Remove {
+                book_id,
+                page: std::cmp::max(1, page),
+            });
+        }
+        if let Some(file_type) = caps.name("file_type") {
+            return Ok(ShelfCallbackData::Archive {
+                file_type: file_type.as_str().to_string(),
+            });
+        }
+
+        Ok(ShelfCallbackData::ArchiveMenu)
+    }
+}
+
+impl Display for ShelfCallbackData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShelfCallbackData::Page { page } => write!(f, "shelf_{page}"),
+            ShelfCallbackData::Add { book_id } => write!(f, "shelf_add_{book_id}"),
+            ShelfCallbackData::Toggle { book_id } => write!(f, "shelf_tg_{book_id}"),
+            ShelfCallbackData::Remove { book_id, page } => {
+                write!(f, "shelf_rm_{book_id}_{page}")
+            }
+            ShelfCallbackData::ArchiveMenu => write!(f, "shelf_da"),
+            ShelfCallbackData::Archive { file_type } => write!(f, "shelf_da_{file_type}"),
+        }
+    }
+}
+
+impl GetPaginationCallbackData for ShelfCallbackData {
+    fn get_pagination_callback_data(&self, target_page: u32) -> String {
+        ShelfCallbackData::Page { page: target_page }.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_all_variants() {
+        for data in [
+            ShelfCallbackData::Page { page: 3 },
+            ShelfCallbackData::Add { book_id: 10 },
+            ShelfCallbackData::Toggle { book_id: 11 },
+            ShelfCallbackData::Remove {
+                book_id: 12,
+                page: 2,
+            },
+            ShelfCallbackData::ArchiveMenu,
+            ShelfCallbackData::Archive {
+                file_type: "fb2".to_string(),
+            },
+        ] {
+            assert_eq!(
+                ShelfCallbackData::from_str(&data.to_string()).unwrap(),
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn page_zero_normalized_to_one() {
+        assert_eq!(
+            ShelfCallbackData::from_str("shelf_0").unwrap(),
+            ShelfCallbackData::Page { page: 1 }
+        );
+    }
+
+    #[test]
+    fn rejects_foreign_data() {
+        assert!(ShelfCallbackData::from_str("shelf_").is_err());
+        assert!(ShelfCallbackData::from_str("sb_1").is_err());
+    }
+}
@@ -0,0 +1,491 @@
+pub mod callback_data;
+pub mod commands;
+
+use book_bot_macros::log_handler;
+
+use teloxide::{
+    adaptors::{CacheMe, Throttle},
+    dispatching::UpdateFilterExt,
+    dptree,
+    prelude::*,
+    types::{CallbackQueryId, InlineKeyboardButton, InlineKeyboardButtonKind, ReplyParameters},
+};
+
+use crate::bots::{
+    approved_bot::{
+        services::{
+            batch_downloader::{TaskObjectType, MAX_TASK_BOOKS},
+            book_library::{
+                formatters::{Format, FormatResult},
+                get_book,
+                types::{Book, Empty, Page, SearchBook},
+            },
+            shelf::SHELF,
+            user_settings::{
+                get_user_page_size, get_user_preferred_formats, pick_preferred_format,
+            },
+        },
+        tools::filter_callback_query,
+    },
+    BotHandlerInternal,
+};
+
+use self::{callback_data::ShelfCallbackData, commands::ShelfCommand};
+
+use super::{
+    download::{
+        archive::start_archive_task,
+        keyboards::{get_books_archive_format_keyboard, BOOKS_ARCHIVE_FORMATS},
+    },
+    utils::{
+        constants::{CHOOSE_FORMAT, ERROR_TRY_LATER, REPEAT_REQUEST, TELEGRAM_MESSAGE_MAX_LENGTH},
+        keyboard::{replace_callback_button, short_title},
+        pagination::{generic_get_pagination_keyboard, paginate, PaginationTexts},
+        telegram_utils::{
+            safe_answer_callback_query_with_text, safe_edit_message_reply_markup,
+            safe_send_message, safe_send_message_with_reply,
+        },
+    },
+};
+
+const SHELF_HEADER: &str = "⭐ Книжная полка:\n\n";
+const SHELF_IS_EMPTY: &str =
+    "Полка пуста. Добавляйте книги кнопкой ⭐ в поиске или на карточке книги.";
+const SHELF_IS_FULL: &str = "Полка заполнена, уберите с неё что-нибудь";
+const ADDED_TO_SHELF: &str = "⭐ Добавлено на полку";
+const ALREADY_ON_SHELF: &str = "Книга уже на полке";
+const REMOVED_FROM_SHELF: &str = "❌ Убрано с полки";
+
+fn callback_button(text: String, data: ShelfCallbackData) -> InlineKeyboardButton {
+    InlineKeyboardButton {
+        text,
+        kind: InlineKeyboardButtonKind::CallbackData(data.to_string()),
+    }
+}
+
+/// "⭐ <title>" row for every book of a search results page.
+pub fn get_add_to_shelf_rows(books: &[SearchBook]) -> Vec<Vec<InlineKeyboardButton>> {
+    books
+        .iter()
+        .map(|book| {
+            vec![callback_button(
+                format!("⭐ {}", short_title(&book.title)),
+                ShelfCallbackData::Add { book_id: book.id },
+            )]
+        })
+        .collect()
+}
+
+pub fn get_shelf_toggle_button(book_id: u32, on_shelf: bool) -> InlineKeyboardButton {
+    let text = if on_shelf {
+        "❌ Убрать с полки"
+    } else {
+        "⭐ На полку"
+    };
+
+    callback_button(text.to_string(), ShelfCallbackData::Toggle { book_id })
+}
+
+/// Toggle button reflecting whether the book is already on the user's shelf.
+pub async fn get_user_shelf_toggle_button(user_id: UserId, book_id: u32) -> InlineKeyboardButton {
+    let on_shelf = match SHELF.list(user_id.0).await {
+        Ok(ids) => ids.contains(&book_id),
+        Err(err) => {
+            tracing::warn!("Failed to load shelf: {err:?}");
+            false
+        }
+    };
+
+    get_shelf_toggle_button(book_id, on_shelf)
+}
+
+/// A shelved book, or the id of one the library no longer has.
+#[derive(Clone, Debug)]
+enum ShelfItem {
+    Book(Box<Book>),
+    Unavailable { id: u32 },
+}
+
+impl ShelfItem {
+    fn id(&self) -> u32 {
+        match self {
+            ShelfItem::Book(book) => book.id,
+            ShelfItem::Unavailable { id } => *id,
+        }
+    }
+
+    fn title(&self) -> String {
+        match self {
+            ShelfItem::Book(book) => short_title(&book.title),
+            ShelfItem::Unavailable { id } => format!("Книга {id} (недоступна)"),
+        }
+    }
+}
+
+impl Format for ShelfItem {
+    fn format(&self, max_size: usize) -> FormatResult {
+        match self {
+            ShelfItem::Book(book) => book.format(max_size),
+            ShelfItem::Unavailable { id } => {
+                let result = format!("📕 Книга {id} больше недоступна в библиотеке");
+
+                FormatResult {
+                    current_size: result.len(),
+                    max_size: result.len(),
+                    result,
+                }
+            }
+        }
+    }
+}
+
+fn get_shelf_rows(items: &[ShelfItem], page: u32) -> Vec<Vec<InlineKeyboardButton>> {
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = items
+        .iter()
+        .map(|item| {
+            vec![callback_button(
+                format!("❌ {}", item.title()),
+                ShelfCallbackData::Remove {
+                    book_id: item.id(),
+                    page,
+                },
+            )]
+        })
+        .collect();
+
+    rows.push(vec![callback_button(
+        "📦 Скачать всё".to_string(),
+        ShelfCallbackData::ArchiveMenu,
+    )]);
+
+    rows
+}
+
+fn shelf_pages(total: usize, page_size: u32) -> u32 {
+    total.div_ceil(page_size as usize) as u32
+}
+
+fn page_ids(ids: &[u32], page: u32, page_size: u32) -> &[u32] {
+    let page_size = page_size as usize;
+    let start = (page.saturating_sub(1) as usize * page_size).min(ids.len());
+    let end = (start + page_size).min(ids.len());
+
+    &ids[start..end]
+}
+
+/// The shelved book, `None` if it could not be fetched. One failing book
+/// does not take the rest of the page down with it.
+async fn get_shelf_item(id: u32) -> Option<ShelfItem> {
+    match get_book(id).await {
+        Ok(Some(book)) => Some(ShelfItem::Book(Box::new(book))),
+        Ok(None) => Some(ShelfItem::Unavailable { id }),
+        Err(err) => {
+            tracing::warn!("Failed to get shelved book {id}: {err:?}");
+            None
+        }
+    }
+}
+
+async fn get_shelf_page(
+    user_id: UserId,
+    page: u32,
+) -> anyhow::Result<Option<Page<ShelfItem, Empty>>> {
+    let ids = SHELF.list(user_id.0).await?;
+    let page_size = get_user_page_size(user_id).await;
+
+    let items = futures::future::join_all(
+        page_ids(&ids, page, page_size)
+            .iter()
+            .map(|id| get_shelf_item(*id)),
+    )
+    .await
+    .into_iter()
+    .flatten()
+    .collect();
+
+    Ok(Some(Page {
+        items,
+        total: ids.len() as u32,
+        pages: shelf_pages(ids.len(), page_size),
+        parent_item: None,
+    }))
+}
+
+/// Adds the book unless the shelf is full. Returns the toast to show.
+async fn add_to_shelf(user_id: UserId, book_id: u32, ids: &[u32]) -> anyhow::Result<&'static str> {
+    if ids.contains(&book_id) {
+        return Ok(ALREADY_ON_SHELF);
+    }
+
+    if ids.len() >= MAX_TASK_BOOKS {
+        return Ok(SHELF_IS_FULL);
+    }
+
+    SHELF.add(user_id.0, book_id).await?;
+
+    Ok(ADDED_TO_SHELF)
+}
+
+/// Passes `result` through, answering the callback with `ERROR_TRY_LATER`
+/// first when it failed, so the button does not keep spinning.
+async fn answer_on_error<T>(
+    bot: &CacheMe<Throttle<Bot>>,
+    cq_id: &CallbackQueryId,
+    result: anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    if result.is_err() {
+        let _ =
+            safe_answer_callback_query_with_text(bot, cq_id.clone(), ERROR_TRY_LATER, true).await;
+    }
+
+    result
+}
+
+#[log_handler("shelf")]
+async fn shelf_command_handler(
+    message: Message,
+    bot: CacheMe<Throttle<Bot>>,
+) -> BotHandlerInternal {
+    let Some(user_id) = message.from.as_ref().map(|user| user.id) else {
+        return safe_send_message_with_reply(
+            &bot,
+            message.chat.id,
+            REPEAT_REQUEST,
+            ReplyParameters::new(message.id),
+            None,
+        )
+        .await;
+    };
+
+    let items_page = match get_shelf_page(user_id, 1).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return Ok(()),
+        Err(err) => {
+            safe_send_message(&bot, message.chat.id, ERROR_TRY_LATER, None).await?;
+            return Err(err);
+        }
+    };
+
+    if items_page.pages == 0 {
+        return safe_send_message_with_reply(
+            &bot,
+            message.chat.id,
+            SHELF_IS_EMPTY,
+            ReplyParameters::new(message.id),
+            None,
+        )
+        .await;
+    }
+
+    let formatted_page = items_page.format(
+        1,
+        TELEGRAM_MESSAGE_MAX_LENGTH.saturating_sub(SHELF_HEADER.len()),
+    );
+
+    let mut keyboard =
+        generic_get_pagination_keyboard(1, items_page.pages, ShelfCallbackData::Page { page: 1 });
+    keyboard
+        .inline_keyboard
+        .extend(get_shelf_rows(&items_page.items, 1));
+
+    safe_send_message_with_reply(
+        &bot,
+        message.chat.id,
+        format!("{SHELF_HEADER}{formatted_page}"),
+        ReplyParameters::new(message.id),
+        Some(keyboard),
+    )
+    .await
+}
+
+async fn shelf_pagination_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    page: u32,
+) -> BotHandlerInternal {
+    let Some(message) = cq.message.as_ref() else {
+        return Ok(());
+    };
+    let (chat_id, message_id) = (message.chat().id, message.id());
+    let user_id = cq.from.id;
+
+    paginate(
+        &bot,
+        chat_id,
+        message_id,
+        cq.message,
+        page,
+        SHELF_HEADER,
+        |p| get_shelf_page(user_id, p),
+        ShelfCallbackData::Page { page },
+        |items| get_shelf_rows(items, page),
+        PaginationTexts {
+            not_found: SHELF_IS_EMPTY,
+            no_items: SHELF_IS_EMPTY,
+            error_try_later: Some(ERROR_TRY_LATER),
+        },
+    )
+    .await
+}
+
+async fn shelf_toggle_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    callback_data: ShelfCallbackData,
+    book_id: u32,
+) -> BotHandlerInternal {
+    let user_id = cq.from.id;
+    let ids = answer_on_error(&bot, &cq.id, SHELF.list(user_id.0).await).await?;
+
+    let (text, on_shelf) = if ids.contains(&book_id) {
+        answer_on_error(&bot, &cq.id, SHELF.remove(user_id.0, book_id).await).await?;
+        (REMOVED_FROM_SHELF, false)
+    } else {
+        let text =
+            answer_on_error(&bot, &cq.id, add_to_shelf(user_id, book_id, &ids).await).await?;
+        (text, text == ADDED_TO_SHELF)
+    };
+
+    safe_answer_callback_query_with_text(&bot, cq.id.clone(), text, false).await?;
+
+    let Some(message) = cq.regular_message() else {
+        return Ok(());
+    };
+    let Some(keyboard) = message.reply_markup() else {
+        return Ok(());
+    };
+
+    let keyboard = replace_callback_button(
+        keyboard,
+        &callback_data.to_string(),
+        get_shelf_toggle_button(book_id, on_shelf),
+    );
+
+    safe_edit_message_reply_markup(&bot, message.chat.id, message.id, keyboard).await
+}
+
+#[log_handler("shelf")]
+async fn shelf_callback_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    callback_data: ShelfCallbackData,
+) -> BotHandlerInternal {
+    let user_id = cq.from.id;
+
+    match callback_data {
+        ShelfCallbackData::Page { page } => shelf_pagination_handler(cq, bot, page).await,
+        ShelfCallbackData::Add { book_id } => {
+            let ids = answer_on_error(&bot, &cq.id, SHELF.list(user_id.0).await).await?;
+            let text =
+                answer_on_error(&bot, &cq.id, add_to_shelf(user_id, book_id, &ids).await).await?;
+
+            safe_answer_callback_query_with_text(&bot, cq.id, text, false).await
+        }
+        ShelfCallbackData::Toggle { book_id } => {
+            shelf_toggle_handler(cq, bot, callback_data, book_id).await
+        }
+        ShelfCallbackData::Remove { book_id, page } => {
+            answer_on_error(&bot, &cq.id, SHELF.remove(user_id.0, book_id).await).await?;
+            safe_answer_callback_query_with_text(&bot, cq.id.clone(), REMOVED_FROM_SHELF, false)
+                .await?;
+
+            shelf_pagination_handler(cq, bot, page).await
+        }
+        ShelfCallbackData::ArchiveMenu => {
+            let Some(message) = cq.message else {
+                return Ok(());
+            };
+
+            let preferred_formats = get_user_preferred_formats(user_id).await;
+            let keyboard = get_books_archive_format_keyboard(
+                pick_preferred_format(&preferred_formats, &BOOKS_ARCHIVE_FORMATS).copied(),
+                |file_type| {
+                    ShelfCallbackData::Archive {
+                        file_type: file_type.to_string(),
+                    }
+                    .to_string()
+                },
+            );
+
+            safe_send_message(&bot, message.chat().id, CHOOSE_FORMAT, Some(keyboard)).await
+        }
+        ShelfCallbackData::Archive { file_type } => {
+            let mut ids = answer_on_error(&bot, &cq.id, SHELF.list(user_id.0).await).await?;
+
+            if ids.is_empty() {
+                return safe_answer_callback_query_with_text(&bot, cq.id, SHELF_IS_EMPTY, true)
+                    .await;
+            }
+
+            // Shelves filled before the limit was lowered keep their books,
+            // but only the most recent ones fit into the archive.
+            ids.truncate(MAX_TASK_BOOKS);
+
+            let Some(message) = cq.message else {
+                return Ok(());
+            };
+
+            start_archive_task(
+                bot,
+                message,
+                user_id,
+                TaskObjectType::Books { ids },
+                file_type,
+            )
+            .await
+        }
+    }
+}
+
+pub fn get_shelf_handler() -> crate::bots::BotHandler {
+    dptree::entry()
+        .branch(
+            Update::filter_message().branch(
+                dptree::entry()
+                    .filter_command::<ShelfCommand>()
+                    .endpoint(shelf_command_handler),
+            ),
+        )
+        .branch(
+            Update::filter_callback_query()
+                .chain(filter_callback_query::<ShelfCallbackData>())
+                .endpoint(shelf_callback_handler),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_split_ids_by_page_size() {
+        let ids: Vec<u32> = (1..=12).collect();
+
+        assert_eq!(shelf_pages(0, 5), 0);
+        assert_eq!(shelf_pages(ids.len(), 5), 3);
+        assert_eq!(page_ids(&ids, 1, 5), &[1, 2, 3, 4, 5]);
+        assert_eq!(page_ids(&ids, 3, 5), &[11, 12]);
+        assert!(page_ids(&ids, 4, 5).is_empty());
+
+        assert_eq!(shelf_pages(ids.len(), 10), 2);
+        assert_eq!(page_ids(&ids, 2, 10), &[11, 12]);
+    }
+
+    #[test]
+    fn unavailable_books_can_be_removed() {
+        let rows = get_shelf_rows(&[ShelfItem::Unavailable { id: 7 }], 2);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0][0].text, "❌ Книга 7 (недоступна)");
+        assert_eq!(
+            rows[0][0].kind,
+            InlineKeyboardButtonKind::CallbackData(
+                ShelfCallbackData::Remove {
+                    book_id: 7,
+                    page: 2
+                }
+                .to_string()
+            )
+        );
+    }
+}
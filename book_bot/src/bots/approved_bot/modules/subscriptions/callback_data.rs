@@ -0,0 +1,63 @@
+use std::{fmt::Display, str::FromStr};
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::bots::approved_bot::{
+    modules::utils::errors::CallbackQueryParseError, services::subscriptions::SubscriptionTarget,
+};
+
+static RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^follow_(?P<target_type>[ats])_(?P<id>\d+)$").unwrap());
+
+/// Toggles the user's subscription to the target.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FollowCallbackData {
+    pub target: SubscriptionTarget,
+}
+
+impl FromStr for FollowCallbackData {
+    type Err = CallbackQueryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let caps = RE.captures(s).ok_or(CallbackQueryParseError)?;
+
+        let id: u32 = caps["id"].parse().map_err(|_| CallbackQueryParseError)?;
+        let target = SubscriptionTarget::from_parts(&caps["target_type"], id)
+            .ok_or(CallbackQueryParseError)?;
+
+        Ok(FollowCallbackData { target })
+    }
+}
+
+impl Display for FollowCallbackData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "follow_{}_{}", self.target.kind(), self.target.id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for target in [
+            SubscriptionTarget::Author { id: 1 },
+            SubscriptionTarget::Translator { id: 2 },
+            SubscriptionTarget::Sequence { id: 3 },
+        ] {
+            let data = FollowCallbackData { target };
+            assert_eq!(
+                FollowCallbackData::from_str(&data.to_string()).unwrap(),
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_foreign_data() {
+        assert!(FollowCallbackData::from_str("follow_x_1").is_err());
+        assert!(FollowCallbackData::from_str("ba_1_1").is_err());
+    }
+}
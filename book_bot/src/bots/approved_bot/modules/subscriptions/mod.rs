@@ -0,0 +1,119 @@
+pub mod callback_data;
+
+use book_bot_macros::log_handler;
+
+use teloxide::{
+    adaptors::{CacheMe, Throttle},
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardButtonKind},
+};
+
+use crate::bots::{
+    approved_bot::{
+        modules::utils::{
+            constants::ERROR_TRY_LATER,
+            keyboard::replace_callback_button,
+            telegram_utils::{
+                safe_answer_callback_query_with_text, safe_edit_message_reply_markup,
+            },
+        },
+        services::subscriptions::{SubscriptionTarget, SUBSCRIPTIONS},
+        tools::filter_callback_query,
+    },
+    BotHandlerInternal,
+};
+
+use self::callback_data::FollowCallbackData;
+
+pub fn get_follow_button(target: SubscriptionTarget, subscribed: bool) -> InlineKeyboardButton {
+    let text = if subscribed {
+        "🔕 Отписаться"
+    } else {
+        "🔔 Подписаться"
+    };
+
+    InlineKeyboardButton {
+        text: text.to_string(),
+        kind: InlineKeyboardButtonKind::CallbackData(FollowCallbackData { target }.to_string()),
+    }
+}
+
+/// Follow button reflecting the user's current subscription state.
+pub async fn get_user_follow_button(
+    bot: &CacheMe<Throttle<Bot>>,
+    user_id: UserId,
+    target: SubscriptionTarget,
+) -> Option<InlineKeyboardButton> {
+    let me = bot.get_me().await.ok()?;
+
+    let subscribed = SUBSCRIPTIONS
+        .is_subscribed(me.id.0, user_id.0, target)
+        .await
+        .unwrap_or_else(|err| {
+            tracing::warn!("Failed to check subscription: {err:?}");
+            false
+        });
+
+    Some(get_follow_button(target, subscribed))
+}
+
+#[log_handler("subscriptions")]
+async fn follow_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    callback_data: FollowCallbackData,
+) -> BotHandlerInternal {
+    let target = callback_data.target;
+    let bot_id = bot.get_me().await?.id.0;
+    let user_id = cq.from.id.0;
+
+    let result = match SUBSCRIPTIONS.is_subscribed(bot_id, user_id, target).await {
+        Ok(true) => SUBSCRIPTIONS
+            .unsubscribe(bot_id, user_id, target)
+            .await
+            .map(|_| false),
+        Ok(false) => SUBSCRIPTIONS
+            .subscribe(bot_id, user_id, target)
+            .await
+            .map(|_| true),
+        Err(err) => Err(err),
+    };
+
+    let subscribed = match result {
+        Ok(v) => v,
+        Err(err) => {
+            safe_answer_callback_query_with_text(&bot, cq.id, ERROR_TRY_LATER, false).await?;
+            return Err(err);
+        }
+    };
+
+    let text = if subscribed {
+        "🔔 Вы будете получать уведомления о новых книгах"
+    } else {
+        "🔕 Подписка отменена"
+    };
+    safe_answer_callback_query_with_text(&bot, cq.id.clone(), text, false).await?;
+
+    let Some(message) = cq.regular_message() else {
+        return Ok(());
+    };
+    let Some(keyboard) = message.reply_markup() else {
+        return Ok(());
+    };
+
+    let keyboard = replace_callback_button(
+        keyboard,
+        &callback_data.to_string(),
+        get_follow_button(target, subscribed),
+    );
+
+    safe_edit_message_reply_markup(&bot, message.chat.id, message.id, keyboard).await
+}
+
+pub fn get_subscriptions_handler() -> crate::bots::BotHandler {
+    dptree::entry().branch(
+        Update::filter_callback_query()
+            .chain(filter_callback_query::<FollowCallbackData>())
+            .endpoint(follow_handler),
+    )
+}
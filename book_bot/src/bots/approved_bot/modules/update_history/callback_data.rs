@@ -6,15 +6,33 @@ use std::sync::LazyLock;
 
 use crate::bots::approved_bot::modules::utils::pagination::GetPaginationCallbackData;
 
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
 static RE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"^update_log_(?P<from>\d{4}-\d{2}-\d{2})_(?P<to>\d{4}-\d{2}-\d{2})_(?P<page>\d+)$")
-        .unwrap()
+    Regex::new(
+        r"^update_log_(?P<from>\d{4}-\d{2}-\d{2})_(?P<to>\d{4}-\d{2}-\d{2})_(?:g(?P<genre>\d+)_)?(?P<page>\d+)$",
+    )
+    .unwrap()
+});
+
+static MENU_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^ul_(?:range|gm_(?P<from>\d{4}-\d{2}-\d{2})_(?P<to>\d{4}-\d{2}-\d{2})(?:_(?P<index>\d+))?)$",
+    )
+    .unwrap()
 });
 
+fn parse_date(s: &str) -> Result<NaiveDate, strum::ParseError> {
+    NaiveDate::parse_from_str(s, DATE_FORMAT).map_err(|_| strum::ParseError::VariantNotFound)
+}
+
+/// A page of books uploaded between `from` and `to`, optionally of one
+/// genre.
 #[derive(Clone, Copy)]
 pub struct UpdateLogCallbackData {
     pub from: NaiveDate,
     pub to: NaiveDate,
+    pub genre: Option<u32>,
     pub page: u32,
 }
 
@@ -24,46 +42,115 @@ impl FromStr for UpdateLogCallbackData {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let caps = RE.captures(s).ok_or(strum::ParseError::VariantNotFound)?;
 
-        let from = NaiveDate::parse_from_str(&caps["from"], "%Y-%m-%d")
-            .map_err(|_| strum::ParseError::VariantNotFound)?;
-        let to = NaiveDate::parse_from_str(&caps["to"], "%Y-%m-%d")
+        let from = parse_date(&caps["from"])?;
+        let to = parse_date(&caps["to"])?;
+        let genre = caps
+            .name("genre")
+            .map(|genre| genre.as_str().parse())
+            .transpose()
             .map_err(|_| strum::ParseError::VariantNotFound)?;
         let page: u32 = caps["page"]
             .parse()
             .map_err(|_| strum::ParseError::VariantNotFound)?;
         let page: u32 = std::cmp::max(1, page);
 
-        Ok(UpdateLogCallbackData { from, to, page })
+        Ok(UpdateLogCallbackData {
+            from,
+            to,
+            genre,
+            page,
+        })
     }
 }
 
 impl Display for UpdateLogCallbackData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let date_format = "%Y-%m-%d";
-
-        let from = self.from.format(date_format);
-        let to = self.to.format(date_format);
+        let from = self.from.format(DATE_FORMAT);
+        let to = self.to.format(DATE_FORMAT);
         let page = self.page;
 
-        write!(f, "update_log_{from}_{to}_{page}")
+        match self.genre {
+            Some(genre) => write!(f, "update_log_{from}_{to}_g{genre}_{page}"),
+            None => write!(f, "update_log_{from}_{to}_{page}"),
+        }
     }
 }
 
 impl GetPaginationCallbackData for UpdateLogCallbackData {
     fn get_pagination_callback_data(&self, target_page: u32) -> String {
-        let UpdateLogCallbackData { from, to, .. } = self;
         UpdateLogCallbackData {
-            from: *from,
-            to: *to,
             page: target_page,
+            ..*self
         }
         .to_string()
     }
 }
 
+/// Everything around the update log listing except its pages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateLogMenuCallbackData {
+    /// Ask for a period typed as text.
+    CustomRange,
+    /// Genre metas to filter the period by.
+    GenreMetas { from: NaiveDate, to: NaiveDate },
+    /// Genres of the meta at `index` in `get_genre_metas`.
+    GenreMeta {
+        from: NaiveDate,
+        to: NaiveDate,
+        index: u32,
+    },
+}
+
+impl FromStr for UpdateLogMenuCallbackData {
+    type Err = strum::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let caps = MENU_RE
+            .captures(s)
+            .ok_or(strum::ParseError::VariantNotFound)?;
+
+        let (Some(from), Some(to)) = (caps.name("from"), caps.name("to")) else {
+            return Ok(UpdateLogMenuCallbackData::CustomRange);
+        };
+        let (from, to) = (parse_date(from.as_str())?, parse_date(to.as_str())?);
+
+        match caps.name("index") {
+            Some(index) => Ok(UpdateLogMenuCallbackData::GenreMeta {
+                from,
+                to,
+                index: index
+                    .as_str()
+                    .parse()
+                    .map_err(|_| strum::ParseError::VariantNotFound)?,
+            }),
+            None => Ok(UpdateLogMenuCallbackData::GenreMetas { from, to }),
+        }
+    }
+}
+
+impl Display for UpdateLogMenuCallbackData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpdateLogMenuCallbackData::CustomRange => write!(f, "ul_range"),
+            UpdateLogMenuCallbackData::GenreMetas { from, to } => write!(
+                f,
+                "ul_gm_{}_{}",
+                from.format(DATE_FORMAT),
+                to.format(DATE_FORMAT)
+            ),
+            UpdateLogMenuCallbackData::GenreMeta { from, to, index } => write!(
+                f,
+                "ul_gm_{}_{}_{index}",
+                from.format(DATE_FORMAT),
+                to.format(DATE_FORMAT)
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::UpdateLogCallbackData;
+    use super::{UpdateLogCallbackData, UpdateLogMenuCallbackData};
     use chrono::NaiveDate;
     use std::str::FromStr;
 
@@ -72,14 +159,47 @@ mod tests {
         let cd = UpdateLogCallbackData {
             from: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
             to: NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(),
+            genre: None,
             page: 3,
         };
         let parsed = UpdateLogCallbackData::from_str(&cd.to_string()).unwrap();
         assert_eq!(parsed.from, cd.from);
         assert_eq!(parsed.to, cd.to);
+        assert_eq!(parsed.genre, None);
         assert_eq!(parsed.page, cd.page);
     }
 
+    #[test]
+    fn round_trip_with_genre() {
+        let cd = UpdateLogCallbackData {
+            from: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            to: NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(),
+            genre: Some(42),
+            page: 2,
+        };
+        assert_eq!(cd.to_string(), "update_log_2024-01-01_2024-01-31_g42_2");
+
+        let parsed = UpdateLogCallbackData::from_str(&cd.to_string()).unwrap();
+        assert_eq!(parsed.genre, Some(42));
+        assert_eq!(parsed.page, 2);
+    }
+
+    #[test]
+    fn round_trip_menu() {
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+
+        for cd in [
+            UpdateLogMenuCallbackData::CustomRange,
+            UpdateLogMenuCallbackData::GenreMetas { from, to },
+            UpdateLogMenuCallbackData::GenreMeta { from, to, index: 3 },
+        ] {
+            assert_eq!(UpdateLogMenuCallbackData::from_str(&cd.to_string()), Ok(cd));
+        }
+
+        assert!(UpdateLogMenuCallbackData::from_str("ul_gm_2024-01-01").is_err());
+    }
+
     #[test]
     fn page_zero_normalized_to_one() {
         let parsed = UpdateLogCallbackData::from_str("update_log_2024-01-01_2024-01-31_0").unwrap();
@@ -1,13 +1,34 @@
 pub mod callback_data;
 pub mod commands;
+pub mod range;
 
 use book_bot_macros::log_handler;
 use chrono::{prelude::*, Duration};
+use smallvec::SmallVec;
+use smartstring::alias::String as SmartString;
 
 use crate::bots::{
     approved_bot::{
-        modules::utils::{constants::ERROR_TRY_AGAIN, telegram_utils::safe_send_message},
-        services::book_library::get_uploaded_books,
+        modules::{
+            genres::keyboards::{get_genre_metas_keyboard, get_genres_keyboard},
+            utils::{
+                constants::{
+                    ERROR_TRY_AGAIN, ERROR_TRY_LATER, NOT_FOUND, TELEGRAM_MESSAGE_MAX_LENGTH,
+                },
+                pagination::generic_get_pagination_keyboard,
+                telegram_utils::{
+                    safe_answer_callback_query, safe_edit_message_text, safe_send_force_reply,
+                    safe_send_message, safe_send_message_with_reply,
+                },
+            },
+        },
+        services::{
+            book_library::{
+                self, get_uploaded_books,
+                types::{Empty, Page, SearchBook},
+            },
+            user_settings::{get_user_or_default_lang_codes, get_user_page_size, get_user_today},
+        },
         tools::filter_callback_query,
     },
     BotHandlerInternal,
@@ -16,63 +37,121 @@ use crate::bots::{
 use teloxide::{
     adaptors::{CacheMe, Throttle},
     prelude::*,
-    types::{InlineKeyboardButton, InlineKeyboardMarkup},
+    types::{
+        InlineKeyboardButton, InlineKeyboardButtonKind, InlineKeyboardMarkup, Me, ReplyParameters,
+    },
 };
 
-use self::{callback_data::UpdateLogCallbackData, commands::UpdateLogCommand};
+use self::{
+    callback_data::{UpdateLogCallbackData, UpdateLogMenuCallbackData},
+    commands::UpdateLogCommand,
+    range::parse_date_range,
+};
 
 use super::utils::pagination::{paginate, PaginationTexts};
 
-#[log_handler("update_history")]
-async fn update_log_command(message: Message, bot: CacheMe<Throttle<Bot>>) -> BotHandlerInternal {
-    let now = Utc::now().date_naive();
-    let d3 = now - Duration::days(3);
-    let d7 = now - Duration::days(7);
-    let d30 = now - Duration::days(30);
+/// First line of the custom period prompt; the reply to the prompt is
+/// matched by it, see `parse_range_reply`.
+const RANGE_PROMPT_PREFIX: &str = "📅 Обновление каталога за период";
+const RANGE_PROMPT: &str =
+    "Напишите в ответ на это сообщение даты через дефис, например 01.05.2024-15.05.2024, или один день.";
+const NO_NEW_BOOKS: &str = "Нет новых книг за этот период.";
+
+fn callback_button(text: &str, data: String) -> InlineKeyboardButton {
+    InlineKeyboardButton {
+        text: text.to_string(),
+        kind: InlineKeyboardButtonKind::CallbackData(data),
+    }
+}
+
+fn get_periods_keyboard(today: NaiveDate) -> InlineKeyboardMarkup {
+    let period = |text: &str, days: i64| {
+        vec![callback_button(
+            text,
+            UpdateLogCallbackData {
+                from: today - Duration::days(days),
+                to: today,
+                genre: None,
+                page: 1,
+            }
+            .to_string(),
+        )]
+    };
 
-    let keyboard = InlineKeyboardMarkup {
+    InlineKeyboardMarkup {
         inline_keyboard: vec![
-            vec![InlineKeyboardButton {
-                text: "За 3 дня".to_string(),
-                kind: teloxide::types::InlineKeyboardButtonKind::CallbackData(
-                    UpdateLogCallbackData {
-                        from: d3,
-                        to: now,
-                        page: 1,
-                    }
-                    .to_string(),
-                ),
-            }],
-            vec![InlineKeyboardButton {
-                text: "За 7 дней".to_string(),
-                kind: teloxide::types::InlineKeyboardButtonKind::CallbackData(
-                    UpdateLogCallbackData {
-                        from: d7,
-                        to: now,
-                        page: 1,
-                    }
-                    .to_string(),
-                ),
-            }],
-            vec![InlineKeyboardButton {
-                text: "За 30 дней".to_string(),
-                kind: teloxide::types::InlineKeyboardButtonKind::CallbackData(
-                    UpdateLogCallbackData {
-                        from: d30,
-                        to: now,
-                        page: 1,
-                    }
-                    .to_string(),
-                ),
-            }],
+            period("За 3 дня", 3),
+            period("За 7 дней", 7),
+            period("За 30 дней", 30),
+            vec![callback_button(
+                "📅 Свой период",
+                UpdateLogMenuCallbackData::CustomRange.to_string(),
+            )],
         ],
+    }
+}
+
+fn get_header(callback_data: &UpdateLogCallbackData) -> String {
+    let from = callback_data.from.format("%d.%m.%Y");
+    let to = callback_data.to.format("%d.%m.%Y");
+
+    format!("Обновление каталога ({from} - {to}):\n\n")
+}
+
+/// Genre choice under the listing; "✓" marks a filtered one.
+fn get_update_log_rows(callback_data: &UpdateLogCallbackData) -> Vec<Vec<InlineKeyboardButton>> {
+    let UpdateLogCallbackData {
+        from, to, genre, ..
+    } = *callback_data;
+
+    let genres = UpdateLogMenuCallbackData::GenreMetas { from, to }.to_string();
+
+    match genre {
+        Some(_) => vec![vec![
+            callback_button("🗂 Жанр ✓", genres),
+            callback_button(
+                "✖️ Все жанры",
+                UpdateLogCallbackData {
+                    genre: None,
+                    page: 1,
+                    ..*callback_data
+                }
+                .to_string(),
+            ),
+        ]],
+        None => vec![vec![callback_button("🗂 Жанр", genres)]],
+    }
+}
+
+async fn get_update_log_books(
+    callback_data: UpdateLogCallbackData,
+    page: u32,
+    page_size: u32,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+) -> anyhow::Result<Option<Page<SearchBook, Empty>>> {
+    get_uploaded_books(
+        page,
+        page_size,
+        allowed_langs,
+        callback_data.genre,
+        callback_data.from.format("%Y-%m-%d").to_string().into(),
+        callback_data.to.format("%Y-%m-%d").to_string().into(),
+    )
+    .await
+}
+
+#[log_handler("update_history")]
+async fn update_log_command(message: Message, bot: CacheMe<Throttle<Bot>>) -> BotHandlerInternal {
+    let today = match message.from.as_ref() {
+        Some(user) => get_user_today(user.id).await,
+        None => Utc::now().date_naive(),
     };
 
     safe_send_message(
         &bot,
         message.chat.id,
         "Обновление каталога:",
-        Some(keyboard),
+        Some(get_periods_keyboard(today)),
     )
     .await
 }
@@ -91,15 +170,9 @@ async fn update_log_pagination_handler(
         }
     };
 
-    let from = update_callback_data.from.format("%d.%m.%Y");
-    let to = update_callback_data.to.format("%d.%m.%Y");
-
-    let header = format!("Обновление каталога ({from} - {to}):\n\n");
-
-    const NO_NEW_BOOKS: &str = "Нет новых книг за этот период.";
-
-    let from = update_callback_data.from;
-    let to = update_callback_data.to;
+    let header = get_header(&update_callback_data);
+    let allowed_langs = get_user_or_default_lang_codes(cq.from.id).await;
+    let page_size = get_user_page_size(cq.from.id).await;
 
     paginate(
         &bot,
@@ -108,14 +181,9 @@ async fn update_log_pagination_handler(
         cq.message,
         update_callback_data.page,
         &header,
-        move |p| {
-            get_uploaded_books(
-                p,
-                from.format("%Y-%m-%d").to_string().into(),
-                to.format("%Y-%m-%d").to_string().into(),
-            )
-        },
+        |p| get_update_log_books(update_callback_data, p, page_size, allowed_langs.clone()),
         update_callback_data,
+        |_| get_update_log_rows(&update_callback_data),
         PaginationTexts {
             not_found: NO_NEW_BOOKS,
             no_items: NO_NEW_BOOKS,
@@ -125,6 +193,254 @@ async fn update_log_pagination_handler(
     .await
 }
 
+/// First page of the period as a reply to `message`.
+async fn send_update_log(
+    bot: &CacheMe<Throttle<Bot>>,
+    message: &Message,
+    user_id: UserId,
+    callback_data: UpdateLogCallbackData,
+) -> BotHandlerInternal {
+    let chat_id = message.chat.id;
+    let allowed_langs = get_user_or_default_lang_codes(user_id).await;
+    let page_size = get_user_page_size(user_id).await;
+
+    let items_page = match get_update_log_books(callback_data, 1, page_size, allowed_langs).await {
+        Ok(Some(v)) if v.pages > 0 => v,
+        Ok(_) => {
+            return safe_send_message_with_reply(
+                bot,
+                chat_id,
+                NO_NEW_BOOKS,
+                ReplyParameters::new(message.id),
+                None,
+            )
+            .await;
+        }
+        Err(err) => {
+            safe_send_message(bot, chat_id, ERROR_TRY_LATER, None).await?;
+            return Err(err);
+        }
+    };
+
+    let header = get_header(&callback_data);
+    let formatted_page =
+        items_page.format(1, TELEGRAM_MESSAGE_MAX_LENGTH.saturating_sub(header.len()));
+
+    let mut keyboard = generic_get_pagination_keyboard(1, items_page.pages, callback_data);
+    keyboard
+        .inline_keyboard
+        .extend(get_update_log_rows(&callback_data));
+
+    safe_send_message_with_reply(
+        bot,
+        chat_id,
+        format!("{header}{formatted_page}"),
+        ReplyParameters::new(message.id),
+        Some(keyboard),
+    )
+    .await
+}
+
+async fn range_prompt_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+) -> BotHandlerInternal {
+    let Some(message) = cq.message.as_ref() else {
+        return Ok(());
+    };
+    let chat_id = message.chat().id;
+
+    safe_answer_callback_query(&bot, cq.id.clone()).await?;
+
+    safe_send_force_reply(
+        &bot,
+        chat_id,
+        format!("{RANGE_PROMPT_PREFIX}\n\n{RANGE_PROMPT}"),
+    )
+    .await
+}
+
+/// A reply to the bot's own custom period prompt.
+#[derive(Clone)]
+struct RangeReply {
+    text: String,
+}
+
+fn parse_range_reply(message: &Message, me: &Me) -> Option<RangeReply> {
+    let prompt = message.reply_to_message()?;
+
+    if prompt.from.as_ref().map(|user| user.id) != Some(me.user.id) {
+        return None;
+    }
+
+    if prompt.text()?.lines().next()? != RANGE_PROMPT_PREFIX {
+        return None;
+    }
+
+    Some(RangeReply {
+        text: message.text()?.to_string(),
+    })
+}
+
+#[log_handler("update_history")]
+async fn range_reply_handler(
+    message: Message,
+    bot: CacheMe<Throttle<Bot>>,
+    reply: RangeReply,
+) -> BotHandlerInternal {
+    let Some(user_id) = message.from.as_ref().map(|user| user.id) else {
+        return Ok(());
+    };
+
+    let (from, to) = match parse_date_range(&reply.text, get_user_today(user_id).await) {
+        Ok(v) => v,
+        Err(err) => {
+            safe_send_message_with_reply(
+                &bot,
+                message.chat.id,
+                err.message(),
+                ReplyParameters::new(message.id),
+                None,
+            )
+            .await?;
+
+            return safe_send_force_reply(
+                &bot,
+                message.chat.id,
+                format!("{RANGE_PROMPT_PREFIX}\n\n{RANGE_PROMPT}"),
+            )
+            .await;
+        }
+    };
+
+    let callback_data = UpdateLogCallbackData {
+        from,
+        to,
+        genre: None,
+        page: 1,
+    };
+
+    send_update_log(&bot, &message, user_id, callback_data).await
+}
+
+async fn genre_metas_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> BotHandlerInternal {
+    let Some(message) = cq.message else {
+        return Ok(());
+    };
+    let chat_id = message.chat().id;
+
+    let metas = match book_library::get_genre_metas().await {
+        Ok(Some(v)) => v,
+        Ok(None) => return safe_send_message(&bot, chat_id, NOT_FOUND, None).await,
+        Err(err) => {
+            safe_send_message(&bot, chat_id, ERROR_TRY_LATER, None).await?;
+            return Err(err);
+        }
+    };
+
+    let mut keyboard = get_genre_metas_keyboard(metas, |index| {
+        UpdateLogMenuCallbackData::GenreMeta { from, to, index }.to_string()
+    });
+    keyboard.inline_keyboard.push(vec![callback_button(
+        "< Все жанры >",
+        UpdateLogCallbackData {
+            from,
+            to,
+            genre: None,
+            page: 1,
+        }
+        .to_string(),
+    )]);
+
+    safe_edit_message_text(
+        &bot,
+        chat_id,
+        message.id(),
+        "🗂 Новые книги какого раздела показать?",
+        Some(keyboard),
+    )
+    .await
+}
+
+async fn genre_meta_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    from: NaiveDate,
+    to: NaiveDate,
+    index: u32,
+) -> BotHandlerInternal {
+    let Some(message) = cq.message else {
+        return Ok(());
+    };
+    let chat_id = message.chat().id;
+
+    let metas = match book_library::get_genre_metas().await {
+        Ok(v) => v.unwrap_or_default(),
+        Err(err) => {
+            safe_send_message(&bot, chat_id, ERROR_TRY_LATER, None).await?;
+            return Err(err);
+        }
+    };
+
+    let Some(meta) = metas.get(index as usize) else {
+        return safe_send_message(&bot, chat_id, NOT_FOUND, None).await;
+    };
+
+    let genres = match book_library::get_genres(meta.into()).await {
+        Ok(Some(v)) => v.items,
+        Ok(None) => return safe_send_message(&bot, chat_id, NOT_FOUND, None).await,
+        Err(err) => {
+            safe_send_message(&bot, chat_id, ERROR_TRY_LATER, None).await?;
+            return Err(err);
+        }
+    };
+
+    let keyboard = get_genres_keyboard(
+        genres,
+        |genre_id| {
+            UpdateLogCallbackData {
+                from,
+                to,
+                genre: Some(genre_id),
+                page: 1,
+            }
+            .to_string()
+        },
+        UpdateLogMenuCallbackData::GenreMetas { from, to }.to_string(),
+    );
+
+    safe_edit_message_text(
+        &bot,
+        chat_id,
+        message.id(),
+        format!("🗂 {meta}\n\nВыбери жанр:"),
+        Some(keyboard),
+    )
+    .await
+}
+
+#[log_handler("update_history")]
+async fn update_log_menu_handler(
+    cq: CallbackQuery,
+    bot: CacheMe<Throttle<Bot>>,
+    callback_data: UpdateLogMenuCallbackData,
+) -> BotHandlerInternal {
+    match callback_data {
+        UpdateLogMenuCallbackData::CustomRange => range_prompt_handler(cq, bot).await,
+        UpdateLogMenuCallbackData::GenreMetas { from, to } => {
+            genre_metas_handler(cq, bot, from, to).await
+        }
+        UpdateLogMenuCallbackData::GenreMeta { from, to, index } => {
+            genre_meta_handler(cq, bot, from, to, index).await
+        }
+    }
+}
+
 pub fn get_update_log_handler() -> crate::bots::BotHandler {
     dptree::entry()
         .branch(
@@ -134,6 +450,13 @@ pub fn get_update_log_handler() -> crate::bots::BotHandler {
                     .endpoint(update_log_command),
             ),
         )
+        .branch(
+            Update::filter_message()
+                .chain(dptree::filter_map(|message: Message, me: Me| {
+                    parse_range_reply(&message, &me)
+                }))
+                .endpoint(range_reply_handler),
+        )
         .branch(
             Update::filter_callback_query().branch(
                 dptree::entry()
@@ -141,4 +464,57 @@ pub fn get_update_log_handler() -> crate::bots::BotHandler {
                     .endpoint(update_log_pagination_handler),
             ),
         )
+        .branch(
+            Update::filter_callback_query()
+                .chain(filter_callback_query::<UpdateLogMenuCallbackData>())
+                .endpoint(update_log_menu_handler),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(genre: Option<u32>) -> UpdateLogCallbackData {
+        UpdateLogCallbackData {
+            from: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            to: NaiveDate::from_ymd_opt(2024, 5, 15).unwrap(),
+            genre,
+            page: 4,
+        }
+    }
+
+    fn texts(rows: &[Vec<InlineKeyboardButton>]) -> Vec<&str> {
+        rows.iter()
+            .flatten()
+            .map(|button| button.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn genre_row_offers_reset_only_when_filtered() {
+        assert_eq!(texts(&get_update_log_rows(&data(None))), vec!["🗂 Жанр"]);
+
+        let rows = get_update_log_rows(&data(Some(7)));
+        assert_eq!(texts(&rows), vec!["🗂 Жанр ✓", "✖️ Все жанры"]);
+        assert_eq!(
+            rows[0][1].kind,
+            InlineKeyboardButtonKind::CallbackData(
+                "update_log_2024-05-01_2024-05-15_1".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn periods_end_today() {
+        let keyboard = get_periods_keyboard(NaiveDate::from_ymd_opt(2024, 5, 31).unwrap());
+
+        assert_eq!(
+            keyboard.inline_keyboard[0][0].kind,
+            InlineKeyboardButtonKind::CallbackData(
+                "update_log_2024-05-28_2024-05-31_1".to_string()
+            )
+        );
+        assert_eq!(keyboard.inline_keyboard.len(), 4);
+    }
 }
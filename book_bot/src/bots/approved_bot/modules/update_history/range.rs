@@ -0,0 +1,121 @@
+use chrono::NaiveDate;
+
+const DATE_FORMAT: &str = "%d.%m.%Y";
+
+/// Longest period that can be asked for in one go.
+pub const MAX_RANGE_DAYS: i64 = 366;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RangeError {
+    Format,
+    Reversed,
+    InFuture,
+    TooLong,
+}
+
+impl RangeError {
+    pub fn message(&self) -> String {
+        match self {
+            RangeError::Format => {
+                "Не получилось разобрать даты. Пример: 01.05.2024-15.05.2024".to_string()
+            }
+            RangeError::Reversed => "Начало периода позже его конца.".to_string(),
+            RangeError::InFuture => "Период не может начинаться в будущем.".to_string(),
+            RangeError::TooLong => format!("Период не может быть длиннее {MAX_RANGE_DAYS} дней."),
+        }
+    }
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate, RangeError> {
+    NaiveDate::parse_from_str(s.trim(), DATE_FORMAT).map_err(|_| RangeError::Format)
+}
+
+/// Parses "01.05.2024-15.05.2024" or a single day. The end is cut to
+/// `today`: nothing has been uploaded after it yet.
+pub fn parse_date_range(
+    text: &str,
+    today: NaiveDate,
+) -> Result<(NaiveDate, NaiveDate), RangeError> {
+    let text = text.trim();
+
+    let (from, to) = match text.split_once(['-', '–', '—']) {
+        Some((from, to)) => (parse_date(from)?, parse_date(to)?),
+        None => {
+            let day = parse_date(text)?;
+            (day, day)
+        }
+    };
+
+    if from > to {
+        return Err(RangeError::Reversed);
+    }
+    if from > today {
+        return Err(RangeError::InFuture);
+    }
+
+    let to = std::cmp::min(to, today);
+
+    if (to - from).num_days() >= MAX_RANGE_DAYS {
+        return Err(RangeError::TooLong);
+    }
+
+    Ok((from, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn parses_range_and_single_day() {
+        let today = date(2024, 6, 1);
+
+        assert_eq!(
+            parse_date_range("01.05.2024-15.05.2024", today),
+            Ok((date(2024, 5, 1), date(2024, 5, 15)))
+        );
+        assert_eq!(
+            parse_date_range(" 01.05.2024 — 15.05.2024 ", today),
+            Ok((date(2024, 5, 1), date(2024, 5, 15)))
+        );
+        assert_eq!(
+            parse_date_range("07.05.2024", today),
+            Ok((date(2024, 5, 7), date(2024, 5, 7)))
+        );
+    }
+
+    #[test]
+    fn end_is_cut_to_today() {
+        assert_eq!(
+            parse_date_range("20.05.2024-20.06.2024", date(2024, 6, 1)),
+            Ok((date(2024, 5, 20), date(2024, 6, 1)))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_ranges() {
+        let today = date(2024, 6, 1);
+
+        assert_eq!(parse_date_range("вчера", today), Err(RangeError::Format));
+        assert_eq!(
+            parse_date_range("31.02.2024-01.03.2024", today),
+            Err(RangeError::Format)
+        );
+        assert_eq!(
+            parse_date_range("15.05.2024-01.05.2024", today),
+            Err(RangeError::Reversed)
+        );
+        assert_eq!(
+            parse_date_range("02.06.2024", today),
+            Err(RangeError::InFuture)
+        );
+        assert_eq!(
+            parse_date_range("01.01.2023-01.06.2024", today),
+            Err(RangeError::TooLong)
+        );
+    }
+}
@@ -7,6 +7,8 @@ pub const ERROR_TRY_AGAIN: &str = "Ошибка! Попробуйте занов
 pub const REPEAT_SEARCH: &str = "Повторите поиск сначала";
 pub const REPEAT_REQUEST: &str = "Повторите запрос сначала";
 pub const NOT_FOUND: &str = "Не найдено :(";
+/// Prompt above a download format keyboard; removed once a file is sent
+pub const CHOOSE_FORMAT: &str = "Выбери формат:";
 pub const RATE_LIMIT_ERROR: &str = "Слишком много запросов, попробуйте позже";
 
 /// Search not-found messages
@@ -14,3 +16,4 @@ pub const BOOKS_NOT_FOUND: &str = "Книги не найдены!";
 pub const AUTHORS_NOT_FOUND: &str = "Авторы не найдены!";
 pub const SEQUENCES_NOT_FOUND: &str = "Серии не найдены!";
 pub const TRANSLATORS_NOT_FOUND: &str = "Переводчики не найдены!";
+pub const NOTHING_FOUND: &str = "Ничего не найдено!";
@@ -0,0 +1,174 @@
+use std::{fmt::Display, str::FromStr};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use regex::Regex;
+use std::sync::LazyLock;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardButtonKind};
+
+use super::errors::CommandParseError;
+
+static RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?P<link_type>[bast])_(?P<id>\d+)$").unwrap());
+
+/// Telegram accepts at most 64 characters of `[A-Za-z0-9_-]` in a start
+/// parameter.
+const MAX_PAYLOAD_LENGTH: usize = 64;
+
+/// Search links carry the base64url-encoded query after this prefix, so
+/// referral and other foreign payloads are not taken for searches.
+const SEARCH_PREFIX: &str = "q_";
+
+/// `/start` payload of a `t.me/<bot>?start=<payload>` link.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeepLink {
+    Book { id: u32 },
+    Author { id: u32 },
+    Sequence { id: u32 },
+    Translator { id: u32 },
+    Search { query: String },
+}
+
+impl Display for DeepLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeepLink::Book { id } => write!(f, "b_{id}"),
+            DeepLink::Author { id } => write!(f, "a_{id}"),
+            DeepLink::Sequence { id } => write!(f, "s_{id}"),
+            DeepLink::Translator { id } => write!(f, "t_{id}"),
+            DeepLink::Search { query } => {
+                write!(f, "{SEARCH_PREFIX}{}", URL_SAFE_NO_PAD.encode(query))
+            }
+        }
+    }
+}
+
+impl FromStr for DeepLink {
+    type Err = CommandParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Some(caps) = RE.captures(s) {
+            let id: u32 = caps["id"].parse().map_err(|_| CommandParseError)?;
+
+            return match &caps["link_type"] {
+                "b" => Ok(DeepLink::Book { id }),
+                "a" => Ok(DeepLink::Author { id }),
+                "s" => Ok(DeepLink::Sequence { id }),
+                "t" => Ok(DeepLink::Translator { id }),
+                _ => Err(CommandParseError),
+            };
+        }
+
+        let encoded = s.strip_prefix(SEARCH_PREFIX).ok_or(CommandParseError)?;
+        let decoded = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|_| CommandParseError)?;
+        let query = String::from_utf8(decoded).map_err(|_| CommandParseError)?;
+        let query = query.trim();
+
+        if query.is_empty() {
+            return Err(CommandParseError);
+        }
+
+        Ok(DeepLink::Search {
+            query: query.to_string(),
+        })
+    }
+}
+
+impl DeepLink {
+    pub fn url(&self, bot_username: &str) -> Option<String> {
+        let payload = self.to_string();
+
+        if payload.len() > MAX_PAYLOAD_LENGTH {
+            return None;
+        }
+
+        Some(format!("https://t.me/{bot_username}?start={payload}"))
+    }
+}
+
+/// "Share" button that opens Telegram's share dialog with the deep link.
+pub fn get_share_button(link: &DeepLink, bot_username: &str) -> Option<InlineKeyboardButton> {
+    let deep_link = link.url(bot_username)?;
+    let share_url =
+        url::Url::parse_with_params("https://t.me/share/url", [("url", deep_link)]).ok()?;
+
+    Some(InlineKeyboardButton {
+        text: String::from("🔗 Поделиться"),
+        kind: InlineKeyboardButtonKind::Url(share_url),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_ids() {
+        for link in [
+            DeepLink::Book { id: 123 },
+            DeepLink::Author { id: 45 },
+            DeepLink::Sequence { id: 67 },
+            DeepLink::Translator { id: 89 },
+        ] {
+            assert_eq!(DeepLink::from_str(&link.to_string()).unwrap(), link);
+        }
+    }
+
+    #[test]
+    fn round_trip_search_query() {
+        let link = DeepLink::Search {
+            query: "война и мир".to_string(),
+        };
+        let payload = link.to_string();
+
+        assert!(payload.starts_with("q_"));
+        assert!(payload
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'));
+        assert_eq!(DeepLink::from_str(&payload).unwrap(), link);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(DeepLink::from_str("").is_err());
+        assert!(DeepLink::from_str("!!!").is_err());
+    }
+
+    #[test]
+    fn rejects_unprefixed_payloads() {
+        for payload in ["ref", "promo2024", "abcd", &URL_SAFE_NO_PAD.encode("мир")] {
+            assert!(DeepLink::from_str(payload).is_err(), "{payload}");
+        }
+    }
+
+    #[test]
+    fn url_uses_bot_username() {
+        assert_eq!(
+            DeepLink::Author { id: 45 }.url("my_bot").as_deref(),
+            Some("https://t.me/my_bot?start=a_45")
+        );
+    }
+
+    #[test]
+    fn url_is_none_for_too_long_payload() {
+        let link = DeepLink::Search {
+            query: "a".repeat(100),
+        };
+        assert!(link.url("my_bot").is_none());
+    }
+
+    #[test]
+    fn share_button_wraps_deep_link() {
+        let button = get_share_button(&DeepLink::Book { id: 1 }, "my_bot").unwrap();
+        match button.kind {
+            InlineKeyboardButtonKind::Url(url) => assert_eq!(
+                url.as_str(),
+                "https://t.me/share/url?url=https%3A%2F%2Ft.me%2Fmy_bot%3Fstart%3Db_1"
+            ),
+            _ => panic!("expected url button"),
+        }
+    }
+}
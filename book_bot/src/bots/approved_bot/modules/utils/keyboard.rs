@@ -0,0 +1,79 @@
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardButtonKind, InlineKeyboardMarkup};
+
+const SHORT_TITLE_LENGTH: usize = 32;
+
+/// Book title cut to fit a one-line button label.
+pub fn short_title(title: &str) -> String {
+    if title.chars().count() <= SHORT_TITLE_LENGTH {
+        return title.to_string();
+    }
+
+    let cut: String = title.chars().take(SHORT_TITLE_LENGTH - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Swaps the button carrying `data` for `button`, keeping the rest of the
+/// keyboard (pagination, share) intact. Used by toggle buttons that edit
+/// their own message.
+pub fn replace_callback_button(
+    keyboard: &InlineKeyboardMarkup,
+    data: &str,
+    button: InlineKeyboardButton,
+) -> InlineKeyboardMarkup {
+    let inline_keyboard = keyboard
+        .inline_keyboard
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|item| match &item.kind {
+                    InlineKeyboardButtonKind::CallbackData(item_data) if item_data == data => {
+                        button.clone()
+                    }
+                    _ => item.clone(),
+                })
+                .collect()
+        })
+        .collect();
+
+    InlineKeyboardMarkup { inline_keyboard }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn callback_button(text: &str, data: &str) -> InlineKeyboardButton {
+        InlineKeyboardButton {
+            text: text.to_string(),
+            kind: InlineKeyboardButtonKind::CallbackData(data.to_string()),
+        }
+    }
+
+    #[test]
+    fn replaces_only_the_matching_button() {
+        let keyboard = InlineKeyboardMarkup {
+            inline_keyboard: vec![
+                vec![callback_button(">", "ba_5_2")],
+                vec![callback_button("🔔 Подписаться", "follow_a_5")],
+            ],
+        };
+
+        let updated = replace_callback_button(
+            &keyboard,
+            "follow_a_5",
+            callback_button("🔕 Отписаться", "follow_a_5"),
+        );
+
+        assert_eq!(updated.inline_keyboard[0][0].text, ">");
+        assert_eq!(updated.inline_keyboard[1][0].text, "🔕 Отписаться");
+    }
+
+    #[test]
+    fn short_title_keeps_short_and_cuts_long() {
+        assert_eq!(short_title("Война и мир"), "Война и мир");
+
+        let long = short_title(&"я".repeat(100));
+        assert_eq!(long.chars().count(), SHORT_TITLE_LENGTH);
+        assert!(long.ends_with('…'));
+    }
+}
@@ -16,3 +16,18 @@ pub fn is_message_text_equals(message: Option<MaybeInaccessibleMessage>, text: &
         None => false,
     }
 }
+
+/// Whether editing `message` to `text` and `keyboard` would change nothing.
+pub fn is_message_unchanged(
+    message: Option<MaybeInaccessibleMessage>,
+    text: &str,
+    keyboard: &InlineKeyboardMarkup,
+) -> bool {
+    let current_keyboard = match &message {
+        Some(MaybeInaccessibleMessage::Regular(message)) => message.reply_markup().cloned(),
+        _ => None,
+    }
+    .unwrap_or_else(|| InlineKeyboardMarkup::new(Vec::<Vec<InlineKeyboardButton>>::new()));
+
+    is_message_text_equals(message, text) && &current_keyboard == keyboard
+}
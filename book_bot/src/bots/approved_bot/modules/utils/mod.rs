@@ -1,7 +1,8 @@
 pub mod constants;
+pub mod deep_link;
 pub mod errors;
 pub mod filter_command;
+pub mod keyboard;
 pub mod message_text;
 pub mod pagination;
-pub mod split_text;
 pub mod telegram_utils;
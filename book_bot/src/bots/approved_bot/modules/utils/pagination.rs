@@ -1,117 +1,147 @@
 use core::fmt::Debug;
 
-use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardButtonKind, InlineKeyboardMarkup};
 use teloxide::{
     adaptors::{CacheMe, Throttle},
     prelude::*,
     types::{ChatId, MaybeInaccessibleMessage, MessageId},
 };
 
-use crate::bots::approved_bot::services::book_library::{
-    formatters::{Format, FormatTitle},
-    types::Page,
+use crate::bots::approved_bot::{
+    modules::page_input::callback_data::PageInputCallbackData,
+    services::book_library::{
+        formatters::{Format, FormatTitle},
+        types::Page,
+    },
 };
 
 use super::{
-    message_text::is_message_text_equals,
+    message_text::is_message_unchanged,
     telegram_utils::{safe_edit_message_text, safe_send_message},
 };
 
-pub enum PaginationDelta {
-    OneMinus,
-    OnePlus,
-    FiveMinus,
-    FivePlus,
-}
-
 pub trait GetPaginationCallbackData {
     fn get_pagination_callback_data(&self, target_page: u32) -> String;
 }
 
-pub fn generic_get_pagination_button<T>(
-    target_page: u32,
-    delta: PaginationDelta,
-    callback_data: &T,
-) -> InlineKeyboardButton
+/// Numbered buttons shown around the current page.
+const PAGE_WINDOW: u32 = 5;
+/// Telegram's limit for the data of a callback button.
+const CALLBACK_DATA_MAX_LENGTH: usize = 64;
+
+fn get_page_button<T>(text: String, target_page: u32, callback_data: &T) -> InlineKeyboardButton
 where
     T: GetPaginationCallbackData,
 {
-    let text = match delta {
-        PaginationDelta::OneMinus => "<",
-        PaginationDelta::OnePlus => ">",
-        PaginationDelta::FiveMinus => "< 5 <",
-        PaginationDelta::FivePlus => "> 5 >",
-    };
+    InlineKeyboardButton {
+        text,
+        kind: InlineKeyboardButtonKind::CallbackData(
+            callback_data.get_pagination_callback_data(target_page),
+        ),
+    }
+}
 
-    let callback_data = callback_data.get_pagination_callback_data(target_page);
+/// The callback data of `callback_data`'s pages without the page number,
+/// if the page number is its last part.
+fn get_page_template<T>(callback_data: &T) -> Option<String>
+where
+    T: GetPaginationCallbackData,
+{
+    let template = callback_data
+        .get_pagination_callback_data(0)
+        .strip_suffix('0')?
+        .to_string();
 
-    InlineKeyboardButton {
-        text: text.to_string(),
-        kind: teloxide::types::InlineKeyboardButtonKind::CallbackData(callback_data),
+    let is_suffix = [1, 42].into_iter().all(|page| {
+        callback_data.get_pagination_callback_data(page) == format!("{template}{page}")
+    });
+
+    is_suffix.then_some(template)
+}
+
+/// "🔢" button opening the page number keypad, see `modules::page_input`.
+fn get_page_input_button<T>(
+    page: u32,
+    total_pages: u32,
+    callback_data: &T,
+) -> Option<InlineKeyboardButton>
+where
+    T: GetPaginationCallbackData,
+{
+    let data = PageInputCallbackData {
+        page,
+        pages: total_pages,
+        input: String::new(),
+        template: get_page_template(callback_data)?,
+    }
+    .to_string();
+
+    // Typed digits make the data longer.
+    if data.len() + total_pages.to_string().len() > CALLBACK_DATA_MAX_LENGTH {
+        return None;
     }
+
+    Some(InlineKeyboardButton {
+        text: "🔢 Перейти к странице".to_string(),
+        kind: InlineKeyboardButtonKind::CallbackData(data),
+    })
 }
 
+/// A row of up to `PAGE_WINDOW` page numbers around `page` with buttons for
+/// the first and the last page, and the page number keypad for listings
+/// longer than the row.
 pub fn generic_get_pagination_keyboard<T>(
     page: u32,
     total_pages: u32,
-    search_data: T,
-    with_five: bool,
+    callback_data: T,
 ) -> InlineKeyboardMarkup
 where
     T: GetPaginationCallbackData,
 {
-    let buttons: Vec<Vec<InlineKeyboardButton>> = {
-        let t_page: i64 = page.into();
+    if total_pages <= 1 {
+        return InlineKeyboardMarkup {
+            inline_keyboard: vec![],
+        };
+    }
 
-        let mut result: Vec<Vec<InlineKeyboardButton>> = vec![];
+    let page = page.clamp(1, total_pages);
+    let first = page
+        .saturating_sub(PAGE_WINDOW / 2)
+        .min(total_pages.saturating_sub(PAGE_WINDOW - 1))
+        .max(1);
+    let last = (first + PAGE_WINDOW - 1).min(total_pages);
 
-        let mut one_page_row: Vec<InlineKeyboardButton> = vec![];
+    let mut pages_row: Vec<InlineKeyboardButton> = vec![];
 
-        if t_page - 1 > 0 {
-            one_page_row.push(generic_get_pagination_button(
-                page - 1,
-                PaginationDelta::OneMinus,
-                &search_data,
-            ))
-        }
-        if t_page < total_pages.into() {
-            one_page_row.push(generic_get_pagination_button(
-                page + 1,
-                PaginationDelta::OnePlus,
-                &search_data,
-            ))
-        }
-        if !one_page_row.is_empty() {
-            result.push(one_page_row);
-        }
+    if first > 1 {
+        pages_row.push(get_page_button("« 1".to_string(), 1, &callback_data));
+    }
+    for target_page in first..=last {
+        let text = if target_page == page {
+            format!("· {target_page} ·")
+        } else {
+            target_page.to_string()
+        };
 
-        if with_five {
-            let mut five_page_row: Vec<InlineKeyboardButton> = vec![];
-            if t_page - 5 > 0 {
-                five_page_row.push(generic_get_pagination_button(
-                    page - 5,
-                    PaginationDelta::FiveMinus,
-                    &search_data,
-                ))
-            }
-            if t_page + 5 < total_pages.into() {
-                five_page_row.push(generic_get_pagination_button(
-                    page + 5,
-                    PaginationDelta::FivePlus,
-                    &search_data,
-                ))
-            }
-            if !five_page_row.is_empty() {
-                result.push(five_page_row);
-            }
-        }
+        pages_row.push(get_page_button(text, target_page, &callback_data));
+    }
+    if last < total_pages {
+        pages_row.push(get_page_button(
+            format!("{total_pages} »"),
+            total_pages,
+            &callback_data,
+        ));
+    }
 
-        result
-    };
+    let mut inline_keyboard = vec![pages_row];
 
-    InlineKeyboardMarkup {
-        inline_keyboard: buttons,
+    if total_pages > PAGE_WINDOW {
+        if let Some(button) = get_page_input_button(page, total_pages, &callback_data) {
+            inline_keyboard.push(vec![button]);
+        }
     }
+
+    InlineKeyboardMarkup { inline_keyboard }
 }
 
 pub struct PaginationTexts<'a> {
@@ -132,7 +162,8 @@ pub struct PaginationTexts<'a> {
 /// pagination handlers. Callers own everything data-source-specific
 /// (extracting the query/id from the callback data, building the
 /// `fetcher` closure, and resolving `chat_id`/`message_id` from the
-/// incoming `CallbackQuery`).
+/// incoming `CallbackQuery`). `extra_rows` builds rows appended below the
+/// page buttons from the items shown (e.g. "Share" or per-item buttons).
 #[allow(clippy::too_many_arguments)]
 pub async fn paginate<T, P, Fut>(
     bot: &CacheMe<Throttle<Bot>>,
@@ -143,6 +174,7 @@ pub async fn paginate<T, P, Fut>(
     header: &str,
     fetcher: impl Fn(u32) -> Fut,
     keyboard_data: impl GetPaginationCallbackData,
+    extra_rows: impl FnOnce(&[T]) -> Vec<Vec<InlineKeyboardButton>>,
     texts: PaginationTexts<'_>,
 ) -> crate::bots::BotHandlerInternal
 where
@@ -189,14 +221,102 @@ where
     let formatted_page = items_page.format(page, super::constants::TELEGRAM_MESSAGE_MAX_LENGTH);
     let message_text = format!("{header}{formatted_page}");
 
-    if is_message_text_equals(cq_message, &message_text) {
+    let mut keyboard = generic_get_pagination_keyboard(page, items_page.pages, keyboard_data);
+    keyboard
+        .inline_keyboard
+        .extend(extra_rows(&items_page.items));
+
+    // Also redraws the page row when the page number keypad is open.
+    if is_message_unchanged(cq_message, &message_text, &keyboard) {
         return Ok(());
     }
 
-    let keyboard = generic_get_pagination_keyboard(page, items_page.pages, keyboard_data, true);
     safe_edit_message_text(bot, chat_id, message_id, message_text, Some(keyboard)).await
 }
 
+#[cfg(test)]
+mod keyboard_tests {
+    use super::*;
+
+    struct FakeData;
+
+    impl GetPaginationCallbackData for FakeData {
+        fn get_pagination_callback_data(&self, target_page: u32) -> String {
+            format!("fake_{target_page}")
+        }
+    }
+
+    /// Page number in the middle of the data.
+    struct MiddlePageData;
+
+    impl GetPaginationCallbackData for MiddlePageData {
+        fn get_pagination_callback_data(&self, target_page: u32) -> String {
+            format!("fake_{target_page}_x")
+        }
+    }
+
+    fn texts(row: &[InlineKeyboardButton]) -> Vec<&str> {
+        row.iter().map(|button| button.text.as_str()).collect()
+    }
+
+    #[test]
+    fn single_page_has_no_buttons() {
+        assert!(generic_get_pagination_keyboard(1, 1, FakeData)
+            .inline_keyboard
+            .is_empty());
+    }
+
+    #[test]
+    fn short_listing_shows_every_page() {
+        let keyboard = generic_get_pagination_keyboard(2, 4, FakeData);
+
+        assert_eq!(keyboard.inline_keyboard.len(), 1);
+        assert_eq!(
+            texts(&keyboard.inline_keyboard[0]),
+            ["1", "· 2 ·", "3", "4"]
+        );
+    }
+
+    #[test]
+    fn long_listing_has_first_last_and_page_input() {
+        let keyboard = generic_get_pagination_keyboard(10, 30, FakeData);
+
+        assert_eq!(
+            texts(&keyboard.inline_keyboard[0]),
+            ["« 1", "8", "9", "· 10 ·", "11", "12", "30 »"]
+        );
+        assert_eq!(
+            keyboard.inline_keyboard[0][6].kind,
+            InlineKeyboardButtonKind::CallbackData("fake_30".to_string())
+        );
+        assert_eq!(
+            keyboard.inline_keyboard[1][0].kind,
+            InlineKeyboardButtonKind::CallbackData("pg_10_30__fake_".to_string())
+        );
+
+        let keyboard = generic_get_pagination_keyboard(1, 30, FakeData);
+        assert_eq!(
+            texts(&keyboard.inline_keyboard[0]),
+            ["· 1 ·", "2", "3", "4", "5", "30 »"]
+        );
+
+        let keyboard = generic_get_pagination_keyboard(30, 30, FakeData);
+        assert_eq!(
+            texts(&keyboard.inline_keyboard[0]),
+            ["« 1", "26", "27", "28", "29", "· 30 ·"]
+        );
+    }
+
+    #[test]
+    fn page_input_needs_page_number_at_the_end() {
+        assert_eq!(get_page_template(&FakeData).as_deref(), Some("fake_"));
+        assert_eq!(get_page_template(&MiddlePageData), None);
+
+        let keyboard = generic_get_pagination_keyboard(10, 30, MiddlePageData);
+        assert_eq!(keyboard.inline_keyboard.len(), 1);
+    }
+}
+
 #[cfg(test)]
 mod paginate_tests {
     use super::*;
@@ -231,6 +351,7 @@ mod paginate_tests {
     fn make_page(pages: u32) -> Page<FakeItem, FakeParent> {
         Page {
             items: vec![FakeItem("item".to_string())],
+            total: 0,
             pages,
             parent_item: None,
         }
@@ -1,85 +0,0 @@
-pub fn split_text_to_chunks(text: &str, width: usize) -> Vec<String> {
-    let mut result: Vec<String> = vec![];
-
-    let chunks = textwrap::wrap(text, width)
-        .into_iter()
-        .filter(|text| !text.replace('\r', "").is_empty())
-        .map(|text| text.to_string());
-
-    let mut index = 0;
-
-    for val in chunks {
-        if result.len() == index {
-            result.push(val);
-            continue;
-        }
-
-        if result[index].len() + val.len() + 1 > width {
-            result.push(val);
-            index += 1;
-            continue;
-        }
-
-        result[index].push('\n');
-        result[index].push_str(&val);
-    }
-
-    result
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::bots::approved_bot::modules::utils::split_text::split_text_to_chunks;
-
-    #[test]
-    fn test_fix_annotation_text() {
-        let input = "\n Библиотека современной фантастики. Том 21\n Содержание:\n РОМАН И ПОВЕСТИ:\n Разбивая стеклянные двери… Предисловие В. Ревича\n Джон Бойнтон Пристли. Дженни Вильерс. Роман о театре. Перевод с английского В. Ашкенази\n Уильям Сароян. Тигр Тома Трейси. Повесть. Перевод с английского Р. Рыбкина\n Роберт Янг. Срубить дерево. Повесть. Перевод с английского С. Васильевой\n РАССКАЗЫ:\n Жан Рей. Рука Геца фон Берлихингена. Перевод с французского А. Григорьева\n Клод Легран. По мерке. Перевод с французского А. Григорьева\n Саке Комацу. Смерть Бикуни. Перевод с японского З. Рахима\n Ана Мария Матуте. Король Зеннов. Перевод с испанского Е. Любимовой\n Антонио Минготе. Николас. Перевод с испанского Р. Рыбкина\n Юн Бинг. Риестофер Юсеф. Перевод с норвежского Л. Жданова\n Гораций Голд. Чего стоят крылья. Перевод с английского Ф. Мендельсона\n Питер С. Бигл. Милости просим, леди Смерть! Перевод с английского Я. Евдокимовой\n Андре Майе. Как я стала писательницей. Перевод с французского Р. Рыбкина\n Джеймс Поллард. Заколдованный поезд. Перевод с английского Р. Рыбкина\n Рэй Брэдбери. Апрельское колдовство. Перевод с английского Л. Жданова\n Айзек Азимов. Небывальщина. Перевод с английского К. Сенина и В. Тальми\n Р.А. Лэфферти. Семь дней ужаса. Перевод с английского И. Почиталина\n Генри Каттнер. Сим удостоверяется… Перевод с английского К. Сенина и В. Тальми\n ";
-        let expected_result: Vec<String> = vec![
-            " Библиотека современной фантастики. Том 21\n Содержание:\n РОМАН И ПОВЕСТИ:\n Разбивая стеклянные двери… Предисловие В. Ревича\n Джон Бойнтон Пристли. Дженни Вильерс. Роман о театре. Перевод с английского В. Ашкенази".to_string(),
-            " Уильям Сароян. Тигр Тома Трейси. Повесть. Перевод с английского Р. Рыбкина\n Роберт Янг. Срубить дерево. Повесть. Перевод с английского С. Васильевой\n РАССКАЗЫ:\n Жан Рей. Рука Геца фон Берлихингена. Перевод с французского А. Григорьева".to_string(),
-            " Клод Легран. По мерке. Перевод с французского А. Григорьева\n Саке Комацу. Смерть Бикуни. Перевод с японского З. Рахима\n Ана Мария Матуте. Король Зеннов. Перевод с испанского Е. Любимовой\n Антонио Минготе. Николас. Перевод с испанского Р. Рыбкина".to_string(),
-            " Юн Бинг. Риестофер Юсеф. Перевод с норвежского Л. Жданова\n Гораций Голд. Чего стоят крылья. Перевод с английского Ф. Мендельсона\n Питер С. Бигл. Милости просим, леди Смерть! Перевод с английского Я. Евдокимовой\n Андре Майе. Как я стала писательницей. Перевод с французского Р. Рыбкина".to_string(),
-            " Джеймс Поллард. Заколдованный поезд. Перевод с английского Р. Рыбкина\n Рэй Брэдбери. Апрельское колдовство. Перевод с английского Л. Жданова\n Айзек Азимов. Небывальщина. Перевод с английского К. Сенина и В. Тальми\n Р.А. Лэфферти. Семь дней ужаса. Перевод с английского И. Почиталина".to_string(),
-            " Генри Каттнер. Сим удостоверяется… Перевод с английского К. Сенина и В. Тальми".to_string()
-        ];
-
-        let result = split_text_to_chunks(input, 512);
-
-        assert_eq!(result, expected_result);
-    }
-
-    #[test]
-    fn wrap_width_is_honored_not_hardcoded() {
-        // 20 chars of plain text, wrapped at width 10, should never
-        // produce a line longer than 10 chars — this fails today because
-        // `textwrap::wrap` is hardcoded to 512 regardless of `width`.
-        let input = "aaaaaaaaaa bbbbbbbbbb";
-        let result = split_text_to_chunks(input, 10);
-        for chunk in &result {
-            for line in chunk.split('\n') {
-                assert!(
-                    line.len() <= 10,
-                    "line {line:?} (len {}) exceeds width 10",
-                    line.len()
-                );
-            }
-        }
-    }
-
-    #[test]
-    fn wrap_width_above_512_is_honored() {
-        let word = "a".repeat(20);
-        let input = std::iter::repeat_n(word, 50).collect::<Vec<_>>().join(" ");
-        let width = 1000;
-        let result = split_text_to_chunks(&input, width);
-        for chunk in &result {
-            for line in chunk.split('\n') {
-                assert!(
-                    line.len() <= width,
-                    "line {line:?} (len {}) exceeds width {width}",
-                    line.len()
-                );
-            }
-        }
-    }
-}
@@ -2,7 +2,8 @@ use teloxide::{
     adaptors::{CacheMe, Throttle},
     prelude::*,
     types::{
-        CallbackQueryId, InlineKeyboardMarkup, InputFile, MessageId, ParseMode, ReplyParameters,
+        CallbackQueryId, ForceReply, InlineKeyboardMarkup, InlineQueryId, InlineQueryResult,
+        InputFile, MessageId, ParseMode, ReplyParameters,
     },
     ApiError, RequestError,
 };
@@ -133,6 +134,34 @@ pub async fn safe_send_message(
     }
 }
 
+/// Safely send a message asking the user to reply to it, handling common
+/// Telegram API errors.
+///
+/// - `NotEnoughRights*` → Ok(()) (can't act, suppress)
+/// - Other errors → Err
+pub async fn safe_send_force_reply(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    text: impl Into<String>,
+) -> BotHandlerInternal {
+    match bot
+        .send_message(chat_id, text.into())
+        .reply_markup(ForceReply::new().selective())
+        .send()
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(RequestError::Api(
+            ApiError::NotEnoughRightsToPostMessages
+            | ApiError::NotEnoughRightsToRestrict
+            | ApiError::NotEnoughRightsToChangeChatPermissions
+            | ApiError::NotEnoughRightsToManagePins
+            | ApiError::NotEnoughRightsToPinMessage,
+        )) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
 /// Safely send a message with HTML parse mode, handling common Telegram API errors.
 ///
 /// - `NotEnoughRights*` → Ok(()) (can't act, suppress)
@@ -228,13 +257,15 @@ pub async fn safe_send_document(
     chat_id: ChatId,
     document: InputFile,
     caption: impl Into<String>,
+    thumbnail: Option<InputFile>,
 ) -> BotHandlerInternal {
-    match bot
-        .send_document(chat_id, document)
-        .caption(caption)
-        .send()
-        .await
-    {
+    let mut request = bot.send_document(chat_id, document).caption(caption);
+
+    if let Some(thumbnail) = thumbnail {
+        request = request.thumbnail(thumbnail);
+    }
+
+    match request.send().await {
         Ok(_) => Ok(()),
         Err(RequestError::Api(api_error)) => match api_error {
             ApiError::NotEnoughRightsToPostMessages
@@ -314,6 +345,31 @@ pub async fn safe_answer_callback_query_with_text(
     }
 }
 
+/// Safely answer an inline query, suppressing all errors.
+///
+/// Results are personal (they depend on the user's languages). A failed
+/// answer (e.g., the query is too old) only means the user keeps typing.
+pub async fn safe_answer_inline_query(
+    bot: &CacheMe<Throttle<Bot>>,
+    inline_query_id: InlineQueryId,
+    results: Vec<InlineQueryResult>,
+    next_offset: String,
+) -> BotHandlerInternal {
+    match bot
+        .answer_inline_query(inline_query_id, results)
+        .is_personal(true)
+        .next_offset(next_offset)
+        .send()
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            log::warn!("Failed to answer inline query: {:?}", e);
+            Ok(())
+        }
+    }
+}
+
 /// Safely copy a message, handling common Telegram API errors.
 ///
 /// - `MessageToCopyNotFound` → Ok(()) (original message deleted)
@@ -344,23 +400,24 @@ pub async fn safe_copy_message(
     }
 }
 
-/// Safely send a photo, handling common Telegram API errors.
+/// Safely send a photo, handling common Telegram API errors. Returns the
+/// sent message, whose photo sizes carry the `file_id`s to reuse.
 ///
-/// - `NotEnoughRights*` → Ok(()) (can't act, suppress)
+/// - `NotEnoughRights*` → Ok(None) (can't act, suppress)
 /// - Other errors → Err
 pub async fn safe_send_photo(
     bot: &CacheMe<Throttle<Bot>>,
     chat_id: ChatId,
     photo: InputFile,
-) -> BotHandlerInternal {
+) -> anyhow::Result<Option<Message>> {
     match bot.send_photo(chat_id, photo).send().await {
-        Ok(_) => Ok(()),
+        Ok(message) => Ok(Some(message)),
         Err(RequestError::Api(api_error)) => match api_error {
             ApiError::NotEnoughRightsToPostMessages
             | ApiError::NotEnoughRightsToRestrict
             | ApiError::NotEnoughRightsToChangeChatPermissions
             | ApiError::NotEnoughRightsToManagePins
-            | ApiError::NotEnoughRightsToPinMessage => Ok(()),
+            | ApiError::NotEnoughRightsToPinMessage => Ok(None),
             other => Err(RequestError::Api(other).into()),
         },
         Err(e) => Err(e.into()),
@@ -422,3 +479,41 @@ pub async fn safe_send_message_with_reply(
         Err(e) => Err(e.into()),
     }
 }
+
+/// Safely send an HTML message with reply parameters.
+///
+/// Same error handling as `safe_send_message_with_reply`, but sets HTML parse mode.
+pub async fn safe_send_message_html_with_reply(
+    bot: &CacheMe<Throttle<Bot>>,
+    chat_id: ChatId,
+    text: impl Into<String>,
+    reply_parameters: ReplyParameters,
+    keyboard: Option<InlineKeyboardMarkup>,
+) -> BotHandlerInternal {
+    let text = text.into();
+    let mut request = bot
+        .send_message(chat_id, &text)
+        .parse_mode(ParseMode::Html)
+        .reply_parameters(reply_parameters);
+
+    if let Some(ref keyboard) = keyboard {
+        request = request.reply_markup(keyboard.clone());
+    }
+
+    match request.send().await {
+        Ok(_) => Ok(()),
+        Err(RequestError::Api(ApiError::MessageToReplyNotFound)) => {
+            safe_send_message_html(bot, chat_id, text, keyboard).await
+        }
+        Err(RequestError::Api(api_error)) => match api_error {
+            ApiError::NotEnoughRightsToPostMessages
+            | ApiError::NotEnoughRightsToRestrict
+            | ApiError::NotEnoughRightsToChangeChatPermissions
+            | ApiError::NotEnoughRightsToManagePins
+            | ApiError::NotEnoughRightsToPinMessage
+            | ApiError::MessageTextIsEmpty => Ok(()),
+            other => Err(RequestError::Api(other).into()),
+        },
+        Err(e) => Err(e.into()),
+    }
+}
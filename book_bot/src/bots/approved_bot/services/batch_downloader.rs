@@ -1,21 +1,27 @@
 use smallvec::SmallVec;
 use smartstring::alias::String as SmartString;
 
-use serde::{Deserialize, Serialize};
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
 use crate::{
     bots::approved_bot::services::{build_url, check_response, HTTP_CLIENT},
     config,
 };
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
+/// What an archive is made of: everything of a sequence, author or
+/// translator, or an explicit list of books.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum TaskObjectType {
-    Sequence,
-    Author,
-    Translator,
+    Sequence { id: u32 },
+    Author { id: u32 },
+    Translator { id: u32 },
+    Books { ids: Vec<u32> },
 }
 
+/// The most books the bot puts into one `TaskObjectType::Books` archive;
+/// both selections and the shelf stop growing there.
+pub const MAX_TASK_BOOKS: usize = 100;
+
 #[derive(Deserialize, PartialEq, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum TaskStatus {
@@ -25,10 +31,8 @@ pub enum TaskStatus {
     Failed,
 }
 
-#[derive(Serialize)]
 pub struct CreateTaskData {
-    pub object_id: u32,
-    pub object_type: TaskObjectType,
+    pub object: TaskObjectType,
     pub file_format: String,
     pub allowed_langs: SmallVec<[SmartString; 3]>,
     /// When `true`, archive members have transliterated (GOST 7.79B) names.
@@ -37,6 +41,38 @@ pub struct CreateTaskData {
     pub normalized: bool,
 }
 
+/// The service takes `object_id` with `object_type` for sequences, authors
+/// and translators, and `book_ids` for `books`.
+impl Serialize for CreateTaskData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CreateTaskData", 5)?;
+
+        match &self.object {
+            TaskObjectType::Sequence { id } => {
+                state.serialize_field("object_type", "sequence")?;
+                state.serialize_field("object_id", id)?;
+            }
+            TaskObjectType::Author { id } => {
+                state.serialize_field("object_type", "author")?;
+                state.serialize_field("object_id", id)?;
+            }
+            TaskObjectType::Translator { id } => {
+                state.serialize_field("object_type", "translator")?;
+                state.serialize_field("object_id", id)?;
+            }
+            TaskObjectType::Books { ids } => {
+                state.serialize_field("object_type", "books")?;
+                state.serialize_field("book_ids", ids)?;
+            }
+        }
+
+        state.serialize_field("file_format", &self.file_format)?;
+        state.serialize_field("allowed_langs", &self.allowed_langs)?;
+        state.serialize_field("normalized", &self.normalized)?;
+        state.end()
+    }
+}
+
 #[derive(Deserialize, Clone)]
 pub struct Task {
     pub id: String,
@@ -82,3 +118,45 @@ pub async fn get_task(task_id: &str) -> anyhow::Result<Task> {
         .await?
         .ok_or_else(|| anyhow::anyhow!("batch-downloader service returned an empty response"))
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use smallvec::smallvec;
+
+    use super::*;
+
+    fn task_data(object: TaskObjectType) -> serde_json::Value {
+        serde_json::to_value(CreateTaskData {
+            object,
+            file_format: "fb2".to_string(),
+            allowed_langs: smallvec!["ru".into()],
+            normalized: true,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn objects_are_sent_by_id_and_books_by_ids() {
+        assert_eq!(
+            task_data(TaskObjectType::Author { id: 7 }),
+            json!({
+                "object_type": "author",
+                "object_id": 7,
+                "file_format": "fb2",
+                "allowed_langs": ["ru"],
+                "normalized": true,
+            })
+        );
+        assert_eq!(
+            task_data(TaskObjectType::Books { ids: vec![1, 2] }),
+            json!({
+                "object_type": "books",
+                "book_ids": [1, 2],
+                "file_format": "fb2",
+                "allowed_langs": ["ru"],
+                "normalized": true,
+            })
+        );
+    }
+}
@@ -13,42 +13,39 @@ use crate::{
     config,
 };
 
-use self::types::{CachedMessage, DownloadFile};
+use self::types::{CachedFile, CachedMessage, DownloadFile};
 
 pub mod types;
 
-pub async fn get_cached_message(
-    download_data: &DownloadQueryData,
-    bot_cache: BotCache,
-    user_id: Option<u64>,
-) -> anyhow::Result<Option<CachedMessage>> {
-    let DownloadQueryData::DownloadData {
-        book_id: id,
-        file_type: format,
-    } = download_data;
-
-    let is_need_copy = bot_cache == BotCache::Cache;
-    // The cache server now stores separate records per `normalized` mode.
-    // Mirror the user's setting here so we hit the same record that
-    // `download_file` would later request.
-    let requested_original = matches!(
+/// Whether the user gets books with original file names, which the cache
+/// server stores as separate, not normalized records.
+async fn is_original_requested(user_id: Option<u64>) -> bool {
+    matches!(
         get_user_file_name_lang_for(user_id).await,
         FileNameLang::Original
-    );
+    )
+}
 
-    let mut url = build_url(
-        &config::CONFIG.cache_server_url,
-        ["api", "v1", &id.to_string(), format, ""],
-    )?;
+/// Requests a cache server endpoint, in the `normalized` mode the user
+/// chose.
+async fn request_cache_server(
+    path: &[&str],
+    query: &[(&str, String)],
+    requested_original: bool,
+    user_id: Option<u64>,
+) -> anyhow::Result<reqwest::Response> {
+    let mut url = build_url(&config::CONFIG.cache_server_url, path.iter().copied())?;
     {
         let mut q = url.query_pairs_mut();
-        q.append_pair("copy", &is_need_copy.to_string());
+        for (key, value) in query {
+            q.append_pair(key, value);
+        }
         if requested_original {
             q.append_pair("normalized", "false");
         }
     }
 
-    let response = retry_on_429(user_id.is_some(), || {
+    retry_on_429(user_id.is_some(), || {
         let mut req = HTTP_CLIENT
             .get(url.clone())
             .header("Authorization", &config::CONFIG.cache_server_api_key);
@@ -59,6 +56,31 @@ pub async fn get_cached_message(
 
         req.send()
     })
+    .await
+}
+
+pub async fn get_cached_message(
+    download_data: &DownloadQueryData,
+    bot_cache: BotCache,
+    user_id: Option<u64>,
+) -> anyhow::Result<Option<CachedMessage>> {
+    let DownloadQueryData::DownloadData {
+        book_id: id,
+        file_type: format,
+    } = download_data;
+
+    let is_need_copy = bot_cache == BotCache::Cache;
+    // The cache server now stores separate records per `normalized` mode.
+    // Mirror the user's setting here so we hit the same record that
+    // `download_file` would later request.
+    let requested_original = is_original_requested(user_id).await;
+
+    let response = request_cache_server(
+        &["api", "v1", &id.to_string(), format, ""],
+        &[("copy", is_need_copy.to_string())],
+        requested_original,
+        user_id,
+    )
     .await?;
 
     let cached: Option<CachedMessage> = check_response(response, &[StatusCode::NO_CONTENT]).await?;
@@ -82,6 +104,28 @@ pub async fn get_cached_message(
     Ok(cached)
 }
 
+pub async fn get_cached_file_id(
+    download_data: &DownloadQueryData,
+    bot_id: u64,
+    user_id: Option<u64>,
+) -> anyhow::Result<Option<CachedFile>> {
+    let DownloadQueryData::DownloadData {
+        book_id: id,
+        file_type: format,
+    } = download_data;
+
+    // Same record selection as `get_cached_message`.
+    let response = request_cache_server(
+        &["api", "v1", "file_id", &id.to_string(), format, ""],
+        &[("bot_id", bot_id.to_string())],
+        is_original_requested(user_id).await,
+        user_id,
+    )
+    .await?;
+
+    check_response(response, &[StatusCode::NO_CONTENT, StatusCode::NOT_FOUND]).await
+}
+
 fn decode_b64_header(headers: &reqwest::header::HeaderMap, name: &str) -> anyhow::Result<String> {
     use anyhow::Context as _;
     use base64::{engine::general_purpose, Engine as _};
@@ -110,30 +154,12 @@ pub async fn download_file(
     // cache server not to transliterate. Default (Normalized / unknown)
     // matches the previous behavior — no query param is sent, the server
     // falls back to `normalized=true`.
-    let original = matches!(
-        get_user_file_name_lang_for(user_id).await,
-        FileNameLang::Original
-    );
-
-    let mut url = build_url(
-        &config::CONFIG.cache_server_url,
-        ["api", "v1", "download", &id.to_string(), format, ""],
-    )?;
-    if original {
-        url.query_pairs_mut().append_pair("normalized", "false");
-    }
-
-    let response = retry_on_429(user_id.is_some(), || {
-        let mut req = HTTP_CLIENT
-            .get(url.clone())
-            .header("Authorization", &config::CONFIG.cache_server_api_key);
-
-        if let Some(uid) = user_id {
-            req = req.header("X-User-Id", uid.to_string());
-        }
-
-        req.send()
-    })
+    let response = request_cache_server(
+        &["api", "v1", "download", &id.to_string(), format, ""],
+        &[],
+        is_original_requested(user_id).await,
+        user_id,
+    )
     .await?;
 
     let Some(response) = check_status(response, &[StatusCode::NO_CONTENT]).await? else {
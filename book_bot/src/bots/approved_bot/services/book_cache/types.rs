@@ -10,6 +10,13 @@ pub struct CachedMessage {
     pub is_normalized: Option<bool>,
 }
 
+/// A Telegram file_id that the requesting bot can send as-is (e.g. as an
+/// inline result), unlike `CachedMessage` which only supports `copy_message`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CachedFile {
+    pub file_id: String,
+}
+
 pub struct DownloadFile {
     pub response: reqwest::Response,
     pub filename: String,
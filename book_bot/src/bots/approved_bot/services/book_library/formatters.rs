@@ -1,7 +1,7 @@
 use std::cmp::min;
 
-use crate::bots::approved_bot::modules::download::commands::{
-    DownloadArchiveCommand, StartDownloadCommand,
+use crate::bots::approved_bot::modules::{
+    book_card::commands::BookCardCommand, download::commands::DownloadArchiveCommand,
 };
 
 use super::types::{
@@ -62,7 +62,7 @@ impl FormatTitle for Person {
 
 impl FormatTitle for Sequence {
     fn format_title(&self) -> String {
-        let Sequence { id, name } = self;
+        let Sequence { id, name, .. } = self;
 
         if *id == 0 {
             return "".to_string();
@@ -143,12 +143,21 @@ impl Format for Author {
 
 impl Format for Sequence {
     fn format(&self, _max_size: usize) -> FormatResult {
-        let Sequence { id, name, .. } = self;
+        let Sequence {
+            id,
+            name,
+            annotation_exists,
+            ..
+        } = self;
 
         let title = format!("📚 {name}");
         let link = format!("/s_{id}");
+        let annotation = match annotation_exists {
+            true => format!("\n📝 Аннотация: /s_an_{id}"),
+            false => "".to_string(),
+        };
 
-        let result = format!("{title} {link}");
+        let result = format!("{title} {link}{annotation}");
         let result_len = result.len();
 
         FormatResult {
@@ -172,7 +181,7 @@ impl Format for Translator {
         let title = format!("👤 {last_name} {first_name} {middle_name}\n");
         let link = format!("/t_{id}\n");
         let annotation = match self.annotation_exists {
-            true => format!("📝 Аннотация: /a_an_{id}"),
+            true => format!("📝 Аннотация: /t_an_{id}"),
             false => "".to_string(),
         };
 
@@ -322,6 +331,8 @@ struct FormatData<'a> {
     pub year: i32,
     pub pages: Option<u32>,
     pub position: Option<i32>,
+    /// Listings link to the book card; the card itself has a keyboard instead.
+    pub with_card_link: bool,
 }
 
 fn format_common(data: FormatData, max_size: usize) -> FormatResult {
@@ -337,6 +348,7 @@ fn format_common(data: FormatData, max_size: usize) -> FormatResult {
         year,
         pages,
         position,
+        with_card_link,
     } = data;
 
     let book_title = {
@@ -365,10 +377,12 @@ fn format_common(data: FormatData, max_size: usize) -> FormatResult {
         false => "".to_string(),
     };
 
-    let download_command = (StartDownloadCommand { id }).to_string();
-    let download_links = format!("Скачать:\n📥{download_command}");
+    let card_link = match with_card_link {
+        true => format!("Подробнее:\n📖{}", BookCardCommand { id }),
+        false => "".to_string(),
+    };
 
-    let required_data_len: usize = format!("{book_title}{annotations}{download_links}").len();
+    let required_data_len: usize = format!("{book_title}{annotations}{card_link}").len();
     let FormatVectorsResult {
         authors,
         translators,
@@ -383,9 +397,8 @@ fn format_common(data: FormatData, max_size: usize) -> FormatResult {
         max_size.saturating_sub(required_data_len),
     );
 
-    let result = format!(
-        "{book_title}{annotations}{authors}{translators}{sequences}{genres}{download_links}"
-    );
+    let result =
+        format!("{book_title}{annotations}{authors}{translators}{sequences}{genres}{card_link}");
     let result_len = result.len();
 
     FormatResult {
@@ -410,12 +423,44 @@ impl Format for Book {
                 year: self.year,
                 pages: self.pages,
                 position: self.position,
+                with_card_link: true,
             },
             max_size,
         )
     }
 }
 
+impl Book {
+    /// Full description for the `/b_{id}` card: `format_common` without the
+    /// card link, followed by an optional annotation excerpt.
+    pub fn format_card(&self, annotation_excerpt: Option<&str>) -> String {
+        let excerpt = match annotation_excerpt {
+            Some(v) => format!("\n\n{v}"),
+            None => "".to_string(),
+        };
+
+        let FormatResult { result, .. } = format_common(
+            FormatData {
+                id: self.id,
+                title: &self.title,
+                lang: &self.lang,
+                annotation_exists: self.annotation_exists,
+                authors: &self.authors,
+                translators: &self.translators,
+                sequences: &self.sequences,
+                genres: &self.genres,
+                year: self.year,
+                pages: self.pages,
+                position: None,
+                with_card_link: false,
+            },
+            NO_LIMIT.saturating_sub(excerpt.len()),
+        );
+
+        format!("{}{excerpt}", result.trim_end())
+    }
+}
+
 impl Format for SearchBook {
     fn format(&self, max_size: usize) -> FormatResult {
         format_common(
@@ -431,6 +476,7 @@ impl Format for SearchBook {
                 year: self.year,
                 pages: None,
                 position: None,
+                with_card_link: true,
             },
             max_size,
         )
@@ -452,6 +498,7 @@ impl Format for AuthorBook {
                 year: self.year,
                 pages: None,
                 position: None,
+                with_card_link: true,
             },
             max_size,
         )
@@ -473,6 +520,7 @@ impl Format for TranslatorBook {
                 year: self.year,
                 pages: None,
                 position: None,
+                with_card_link: true,
             },
             max_size,
         )
@@ -494,6 +542,7 @@ impl Format for SequenceBook {
                 year: self.year,
                 pages: None,
                 position: Some(self.position),
+                with_card_link: true,
             },
             max_size,
         )
@@ -502,8 +551,8 @@ impl Format for SequenceBook {
 
 #[cfg(test)]
 mod tests {
-    use super::super::types::{Person, PersonKind};
-    use super::{format_list, FormatInline, FormatTitle, FormatVectorsCounts};
+    use super::super::types::{Book, Person, PersonKind, Sequence, Translator};
+    use super::{format_list, Format, FormatInline, FormatTitle, FormatVectorsCounts};
 
     fn make_person(kind: PersonKind) -> Person {
         Person {
@@ -527,6 +576,38 @@ mod tests {
         assert_eq!(p.format_inline(), "👤 L F M /t_7");
     }
 
+    #[test]
+    fn translator_annotation_link_opens_translator_bio() {
+        let translator = Translator {
+            id: 7,
+            last_name: "L".to_string(),
+            first_name: "F".to_string(),
+            middle_name: "M".to_string(),
+            annotation_exists: true,
+        };
+
+        assert!(translator
+            .format(4096)
+            .result
+            .ends_with("📝 Аннотация: /t_an_7"));
+    }
+
+    #[test]
+    fn sequence_annotation_link_only_when_available() {
+        let mut sequence = Sequence {
+            id: 3,
+            name: "S".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(sequence.format(4096).result, "📚 S /s_3");
+
+        sequence.annotation_exists = true;
+        assert_eq!(
+            sequence.format(4096).result,
+            "📚 S /s_3\n📝 Аннотация: /s_an_3"
+        );
+    }
+
     #[test]
     fn format_title_is_empty_for_id_zero() {
         let mut p = make_person(PersonKind::Author);
@@ -681,4 +762,37 @@ mod tests {
             (0, 0, 0, 0)
         );
     }
+
+    fn make_book() -> Book {
+        serde_json::from_value(serde_json::json!({
+            "id": 42,
+            "title": "T",
+            "lang": "ru",
+            "available_types": ["fb2"],
+            "annotation_exists": true,
+            "authors": [],
+            "translators": [],
+            "sequences": [],
+            "genres": [],
+            "year": 2000,
+            "pages": 300,
+            "position": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn listing_links_to_book_card() {
+        let result = make_book().format(4096).result;
+        assert!(result.ends_with("Подробнее:\n📖/b_42"), "{result}");
+    }
+
+    #[test]
+    fn card_has_no_self_link_and_keeps_excerpt() {
+        let card = make_book().format_card(Some("Начало…"));
+        assert_eq!(
+            card,
+            "📖 T | ru | 2000г. | 300с.\n📝 Аннотация: /b_an_42\n\nНачало…"
+        );
+    }
 }
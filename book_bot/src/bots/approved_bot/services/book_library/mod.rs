@@ -93,30 +93,79 @@ pub async fn get_genres(
     _make_request(&["api", "v1", "genres"], params).await
 }
 
-const PAGE_SIZE: &str = "5";
+/// Listing page size for users who did not choose one in `/settings`.
+pub const DEFAULT_PAGE_SIZE: u32 = 5;
 
 pub async fn search_book(
     query: String,
     page: u32,
+    page_size: u32,
     allowed_langs: SmallVec<[SmartString; 3]>,
 ) -> anyhow::Result<Option<types::Page<types::SearchBook, Empty>>> {
     let mut params = get_allowed_langs_params(&allowed_langs);
 
     params.push(("page", page.to_string().into()));
-    params.push(("size", PAGE_SIZE.to_string().into()));
+    params.push(("size", page_size.to_string().into()));
 
     _make_request(&["api", "v1", "books", "search", &query], params).await
 }
 
+/// Field filters of a structured book search, see `search::query`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookFilter {
+    /// Free text matched the same way as in `search_book`.
+    pub text: Option<String>,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub genre: Option<String>,
+    pub year_gte: Option<i32>,
+    pub year_lte: Option<i32>,
+}
+
+pub async fn search_book_by_filter(
+    filter: &BookFilter,
+    page: u32,
+    page_size: u32,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+) -> anyhow::Result<Option<types::Page<types::SearchBook, Empty>>> {
+    let mut params = get_allowed_langs_params(&allowed_langs);
+
+    let text_params = [
+        ("q", &filter.text),
+        ("title", &filter.title),
+        ("author", &filter.author),
+        ("genre", &filter.genre),
+    ];
+    for (name, value) in text_params {
+        if let Some(value) = value {
+            params.push((name, value.as_str().into()));
+        }
+    }
+
+    let year_params = [("year_gte", filter.year_gte), ("year_lte", filter.year_lte)];
+    for (name, value) in year_params {
+        if let Some(value) = value {
+            params.push((name, value.to_string().into()));
+        }
+    }
+
+    params.push(("page", page.to_string().into()));
+    params.push(("size", page_size.to_string().into()));
+    params.push(("is_deleted", "false".into()));
+
+    _make_request(&["api", "v1", "books"], params).await
+}
+
 pub async fn search_author(
     query: String,
     page: u32,
+    page_size: u32,
     allowed_langs: SmallVec<[SmartString; 3]>,
 ) -> anyhow::Result<Option<types::Page<types::Author, Empty>>> {
     let mut params = get_allowed_langs_params(&allowed_langs);
 
     params.push(("page", page.to_string().into()));
-    params.push(("size", PAGE_SIZE.to_string().into()));
+    params.push(("size", page_size.to_string().into()));
 
     _make_request(&["api", "v1", "authors", "search", &query], params).await
 }
@@ -124,12 +173,13 @@ pub async fn search_author(
 pub async fn search_sequence(
     query: String,
     page: u32,
+    page_size: u32,
     allowed_langs: SmallVec<[SmartString; 3]>,
 ) -> anyhow::Result<Option<types::Page<types::Sequence, Empty>>> {
     let mut params = get_allowed_langs_params(&allowed_langs);
 
     params.push(("page", page.to_string().into()));
-    params.push(("size", PAGE_SIZE.to_string().into()));
+    params.push(("size", page_size.to_string().into()));
 
     _make_request(&["api", "v1", "sequences", "search", &query], params).await
 }
@@ -137,12 +187,13 @@ pub async fn search_sequence(
 pub async fn search_translator(
     query: String,
     page: u32,
+    page_size: u32,
     allowed_langs: SmallVec<[SmartString; 3]>,
 ) -> anyhow::Result<Option<types::Page<types::Translator, Empty>>> {
     let mut params = get_allowed_langs_params(&allowed_langs);
 
     params.push(("page", page.to_string().into()));
-    params.push(("size", PAGE_SIZE.to_string().into()));
+    params.push(("size", page_size.to_string().into()));
 
     _make_request(&["api", "v1", "translators", "search", &query], params).await
 }
@@ -163,28 +214,164 @@ pub async fn get_author_annotation(id: u32) -> anyhow::Result<Option<types::Auth
     .await
 }
 
+pub async fn get_translator_annotation(
+    id: u32,
+) -> anyhow::Result<Option<types::TranslatorAnnotation>> {
+    _make_request(
+        &["api", "v1", "translators", &id.to_string(), "annotation"],
+        vec![],
+    )
+    .await
+}
+
+pub async fn get_sequence_annotation(id: u32) -> anyhow::Result<Option<types::SequenceAnnotation>> {
+    _make_request(
+        &["api", "v1", "sequences", &id.to_string(), "annotation"],
+        vec![],
+    )
+    .await
+}
+
+/// Narrows an author's, translator's or series' book list.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookListFilter {
+    /// Part of the title.
+    pub title: Option<String>,
+    pub sort: BookSort,
+}
+
+fn get_book_list_filter_params(filter: &BookListFilter) -> Vec<(&'static str, SmartString)> {
+    let mut params = vec![];
+
+    if let Some(title) = &filter.title {
+        params.push(("title", title.as_str().into()));
+    }
+
+    if let Some(order) = filter.sort.as_param() {
+        params.push(("order", order.into()));
+    }
+
+    params
+}
+
 pub async fn get_author_books(
     id: u32,
     page: u32,
+    page_size: u32,
     allowed_langs: SmallVec<[SmartString; 3]>,
+    filter: BookListFilter,
 ) -> anyhow::Result<Option<types::Page<types::AuthorBook, types::Person>>> {
     let mut params = get_allowed_langs_params(&allowed_langs);
 
     params.push(("page", page.to_string().into()));
-    params.push(("size", PAGE_SIZE.to_string().into()));
+    params.push(("size", page_size.to_string().into()));
+    params.extend(get_book_list_filter_params(&filter));
 
     _make_request(&["api", "v1", "authors", &id.to_string(), "books"], params).await
 }
 
+/// Page size and page limit of the `get_all_*_books` listings; the page
+/// size also suits other background listings.
+pub const BULK_PAGE_SIZE: u32 = 100;
+const BULK_MAX_PAGES: u32 = 10;
+
+/// Every page of a book listing merged into one; long listings are cut at
+/// `BULK_MAX_PAGES * BULK_PAGE_SIZE` books.
+async fn get_all_books<T, P>(
+    segments: &[&str],
+    allowed_langs: SmallVec<[SmartString; 3]>,
+) -> anyhow::Result<Option<types::Page<T, P>>>
+where
+    T: DeserializeOwned,
+    P: DeserializeOwned,
+{
+    let mut result: Option<types::Page<T, P>> = None;
+    let mut page = 1;
+
+    loop {
+        let mut params = get_allowed_langs_params(&allowed_langs);
+
+        params.push(("page", page.to_string().into()));
+        params.push(("size", BULK_PAGE_SIZE.to_string().into()));
+
+        let Some(books) = _make_request::<types::Page<T, P>>(segments, params).await? else {
+            return Ok(result);
+        };
+
+        let pages = books.pages;
+
+        match result.as_mut() {
+            Some(result) => result.items.extend(books.items),
+            None => result = Some(books),
+        }
+
+        if page >= pages || page >= BULK_MAX_PAGES {
+            break;
+        }
+
+        page += 1;
+    }
+
+    if let Some(result) = result.as_mut() {
+        result.pages = std::cmp::min(result.pages, 1);
+    }
+
+    Ok(result)
+}
+
+pub async fn get_all_author_books(
+    id: u32,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+) -> anyhow::Result<Option<types::Page<types::AuthorBook, types::Person>>> {
+    get_all_books(
+        &["api", "v1", "authors", &id.to_string(), "books"],
+        allowed_langs,
+    )
+    .await
+}
+
+pub async fn get_all_sequence_books(
+    id: u32,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+) -> anyhow::Result<Option<types::Page<types::SequenceBook, types::Sequence>>> {
+    get_all_books(
+        &["api", "v1", "sequences", &id.to_string(), "books"],
+        allowed_langs,
+    )
+    .await
+}
+
+pub async fn get_all_translator_books(
+    id: u32,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+) -> anyhow::Result<Option<types::Page<types::TranslatorBook, types::Person>>> {
+    let mut result: Option<types::Page<types::TranslatorBook, types::Person>> = get_all_books(
+        &["api", "v1", "translators", &id.to_string(), "books"],
+        allowed_langs,
+    )
+    .await?;
+
+    if let Some(page) = result.as_mut() {
+        if let Some(parent) = page.parent_item.as_mut() {
+            parent.kind = types::PersonKind::Translator;
+        }
+    }
+
+    Ok(result)
+}
+
 pub async fn get_translator_books(
     id: u32,
     page: u32,
+    page_size: u32,
     allowed_langs: SmallVec<[SmartString; 3]>,
+    filter: BookListFilter,
 ) -> anyhow::Result<Option<types::Page<types::TranslatorBook, types::Person>>> {
     let mut params = get_allowed_langs_params(&allowed_langs);
 
     params.push(("page", page.to_string().into()));
-    params.push(("size", PAGE_SIZE.to_string().into()));
+    params.push(("size", page_size.to_string().into()));
+    params.extend(get_book_list_filter_params(&filter));
 
     let mut result: Option<types::Page<types::TranslatorBook, types::Person>> = _make_request(
         &["api", "v1", "translators", &id.to_string(), "books"],
@@ -204,12 +391,15 @@ pub async fn get_translator_books(
 pub async fn get_sequence_books(
     id: u32,
     page: u32,
+    page_size: u32,
     allowed_langs: SmallVec<[SmartString; 3]>,
+    filter: BookListFilter,
 ) -> anyhow::Result<Option<types::Page<types::SequenceBook, types::Sequence>>> {
     let mut params = get_allowed_langs_params(&allowed_langs);
 
     params.push(("page", page.to_string().into()));
-    params.push(("size", PAGE_SIZE.to_string().into()));
+    params.push(("size", page_size.to_string().into()));
+    params.extend(get_book_list_filter_params(&filter));
 
     _make_request(
         &["api", "v1", "sequences", &id.to_string(), "books"],
@@ -218,18 +408,89 @@ pub async fn get_sequence_books(
     .await
 }
 
+/// Order of a book listing. `Newest` is the book server's default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BookSort {
+    #[default]
+    Newest,
+    Title,
+    Year,
+    /// By series, then by the position in it.
+    Position,
+}
+
+impl BookSort {
+    /// One-letter code of the order in callback data.
+    pub fn code(self) -> &'static str {
+        match self {
+            BookSort::Newest => "n",
+            BookSort::Title => "t",
+            BookSort::Year => "y",
+            BookSort::Position => "p",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "n" => Some(BookSort::Newest),
+            "t" => Some(BookSort::Title),
+            "y" => Some(BookSort::Year),
+            "p" => Some(BookSort::Position),
+            _ => None,
+        }
+    }
+
+    fn as_param(self) -> Option<&'static str> {
+        match self {
+            BookSort::Newest => None,
+            BookSort::Title => Some("title"),
+            BookSort::Year => Some("-year"),
+            BookSort::Position => Some("position"),
+        }
+    }
+}
+
+pub async fn get_genre_books(
+    genre_id: u32,
+    page: u32,
+    page_size: u32,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+    sort: BookSort,
+) -> anyhow::Result<Option<types::Page<types::SearchBook, Empty>>> {
+    let mut params = get_allowed_langs_params(&allowed_langs);
+
+    params.push(("genre", genre_id.to_string().into()));
+    params.push(("page", page.to_string().into()));
+    params.push(("size", page_size.to_string().into()));
+    params.push(("is_deleted", "false".into()));
+
+    if let Some(order) = sort.as_param() {
+        params.push(("order", order.into()));
+    }
+
+    _make_request(&["api", "v1", "books"], params).await
+}
+
+/// Books uploaded between the dates; empty `allowed_langs` means any
+/// language.
 pub async fn get_uploaded_books(
     page: u32,
+    page_size: u32,
+    allowed_langs: SmallVec<[SmartString; 3]>,
+    genre_id: Option<u32>,
     uploaded_gte: SmartString,
     uploaded_lte: SmartString,
 ) -> anyhow::Result<Option<types::Page<types::SearchBook, Empty>>> {
-    let params = vec![
-        ("page", page.to_string().into()),
-        ("size", PAGE_SIZE.to_string().into()),
-        ("uploaded_gte", uploaded_gte),
-        ("uploaded_lte", uploaded_lte),
-        ("is_deleted", "false".into()),
-    ];
+    let mut params = get_allowed_langs_params(&allowed_langs);
+
+    if let Some(genre_id) = genre_id {
+        params.push(("genre", genre_id.to_string().into()));
+    }
+    params.push(("page", page.to_string().into()));
+    params.push(("size", page_size.to_string().into()));
+    params.push(("uploaded_gte", uploaded_gte));
+    params.push(("uploaded_lte", uploaded_lte));
+    params.push(("is_deleted", "false".into()));
 
     _make_request(&["api", "v1", "books"], params).await
 }
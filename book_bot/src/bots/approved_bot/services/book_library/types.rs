@@ -80,6 +80,12 @@ pub struct Translator {
 pub struct Sequence {
     pub id: u32,
     pub name: String,
+    /// Position of the book in the series, for the series of a book.
+    #[serde(default)]
+    pub position: Option<i32>,
+    /// Only sent for series themselves, not for the series of a book.
+    #[serde(default)]
+    pub annotation_exists: bool,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -102,7 +108,8 @@ fn none_parent_item<P>() -> Option<P> {
 #[derive(Deserialize, Debug, Clone)]
 pub struct Page<T, P> {
     pub items: Vec<T>,
-    // pub total: u32,
+    #[serde(default)]
+    pub total: u32,
 
     // pub page: u32,
 
@@ -208,7 +215,8 @@ where
 #[derive(Deserialize, Debug, Clone)]
 pub struct BookAnnotation {
     // pub id: u32,
-    // pub title: String,
+    #[serde(default)]
+    pub title: String,
     pub text: String,
     pub file: Option<String>,
 }
@@ -216,7 +224,26 @@ pub struct BookAnnotation {
 #[derive(Deserialize, Debug, Clone)]
 pub struct AuthorAnnotation {
     // pub id: u32,
-    // pub title: String,
+    #[serde(default)]
+    pub title: String,
+    pub text: String,
+    pub file: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TranslatorAnnotation {
+    // pub id: u32,
+    #[serde(default)]
+    pub title: String,
+    pub text: String,
+    pub file: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SequenceAnnotation {
+    // pub id: u32,
+    #[serde(default)]
+    pub title: String,
     pub text: String,
     pub file: Option<String>,
 }
@@ -324,6 +351,7 @@ mod tests {
     fn format_items_empty_does_not_panic() {
         let page: Page<FakeItem, FakeParent> = Page {
             items: vec![],
+            total: 0,
             pages: 1,
             parent_item: None,
         };
@@ -335,6 +363,7 @@ mod tests {
     fn format_items_small_max_size_does_not_panic() {
         let page: Page<FakeItem, FakeParent> = Page {
             items: vec![FakeItem, FakeItem],
+            total: 0,
             pages: 1,
             parent_item: None,
         };
@@ -0,0 +1,341 @@
+pub mod sender;
+
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use rusqlite::{params, OptionalExtension};
+
+use super::local_db::{LocalDb, LOCAL_DB};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigestFrequency {
+    Daily,
+    Weekly,
+}
+
+impl DigestFrequency {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DigestFrequency::Daily => "d",
+            DigestFrequency::Weekly => "w",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "d" => Some(DigestFrequency::Daily),
+            "w" => Some(DigestFrequency::Weekly),
+            _ => None,
+        }
+    }
+
+    /// Length of the period one digest covers.
+    pub fn days(&self) -> i64 {
+        match self {
+            DigestFrequency::Daily => 1,
+            DigestFrequency::Weekly => 7,
+        }
+    }
+}
+
+/// What a user asked to receive. `hour` is local to `utc_offset`, which
+/// mirrors the time zone from the user settings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Digest {
+    pub frequency: DigestFrequency,
+    pub hour: u32,
+    pub utc_offset: i32,
+    pub genre_id: Option<u32>,
+    pub genre_name: Option<String>,
+}
+
+/// One digest row. `bot_id` is the Telegram id of the bot the user opted
+/// in through, so the digest goes out via the same bot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DigestRecipient {
+    pub bot_id: u64,
+    pub user_id: u64,
+    pub digest: Digest,
+    pub last_sent_on: Option<NaiveDate>,
+}
+
+#[async_trait]
+pub trait DigestStorage: Send + Sync {
+    async fn get(&self, bot_id: u64, user_id: u64) -> anyhow::Result<Option<Digest>>;
+
+    /// Creates or updates the digest; when it was last sent is kept.
+    async fn save(&self, bot_id: u64, user_id: u64, digest: Digest) -> anyhow::Result<()>;
+
+    async fn disable(&self, bot_id: u64, user_id: u64) -> anyhow::Result<()>;
+
+    /// Follows a time zone change in the user settings; no-op without a
+    /// digest.
+    async fn set_utc_offset(
+        &self,
+        bot_id: u64,
+        user_id: u64,
+        utc_offset: i32,
+    ) -> anyhow::Result<()>;
+
+    async fn list(&self) -> anyhow::Result<Vec<DigestRecipient>>;
+
+    /// Records the local date the digest was sent on.
+    async fn mark_sent(&self, bot_id: u64, user_id: u64, date: NaiveDate) -> anyhow::Result<()>;
+}
+
+pub struct SqliteDigestStorage {
+    db: LocalDb,
+}
+
+impl SqliteDigestStorage {
+    pub fn new(db: LocalDb) -> anyhow::Result<Self> {
+        db.migrate(
+            "CREATE TABLE IF NOT EXISTS digests (
+                bot_id INTEGER NOT NULL,
+                user_id INTEGER NOT NULL,
+                frequency TEXT NOT NULL,
+                hour INTEGER NOT NULL,
+                utc_offset INTEGER NOT NULL,
+                genre_id INTEGER,
+                genre_name TEXT,
+                last_sent_on TEXT,
+                PRIMARY KEY (bot_id, user_id)
+            );",
+        )?;
+
+        Ok(Self { db })
+    }
+}
+
+fn digest_from_row(row: &rusqlite::Row, offset: usize) -> rusqlite::Result<Digest> {
+    let frequency: String = row.get(offset)?;
+
+    Ok(Digest {
+        frequency: DigestFrequency::from_code(&frequency).unwrap_or(DigestFrequency::Daily),
+        hour: row.get(offset + 1)?,
+        utc_offset: row.get(offset + 2)?,
+        genre_id: row.get(offset + 3)?,
+        genre_name: row.get(offset + 4)?,
+    })
+}
+
+// Telegram ids fit into SQLite's signed 64-bit integers.
+#[async_trait]
+impl DigestStorage for SqliteDigestStorage {
+    async fn get(&self, bot_id: u64, user_id: u64) -> anyhow::Result<Option<Digest>> {
+        self.db
+            .call(move |connection| {
+                connection
+                    .prepare_cached(
+                        "SELECT frequency, hour, utc_offset, genre_id, genre_name FROM digests
+                         WHERE bot_id = ?1 AND user_id = ?2",
+                    )?
+                    .query_row(params![bot_id as i64, user_id as i64], |row| {
+                        digest_from_row(row, 0)
+                    })
+                    .optional()
+            })
+            .await
+    }
+
+    async fn save(&self, bot_id: u64, user_id: u64, digest: Digest) -> anyhow::Result<()> {
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "INSERT INTO digests
+                        (bot_id, user_id, frequency, hour, utc_offset, genre_id, genre_name)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                     ON CONFLICT (bot_id, user_id) DO UPDATE SET
+                        frequency = excluded.frequency,
+                        hour = excluded.hour,
+                        utc_offset = excluded.utc_offset,
+                        genre_id = excluded.genre_id,
+                        genre_name = excluded.genre_name",
+                    params![
+                        bot_id as i64,
+                        user_id as i64,
+                        digest.frequency.code(),
+                        digest.hour,
+                        digest.utc_offset,
+                        digest.genre_id,
+                        digest.genre_name
+                    ],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn disable(&self, bot_id: u64, user_id: u64) -> anyhow::Result<()> {
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "DELETE FROM digests WHERE bot_id = ?1 AND user_id = ?2",
+                    params![bot_id as i64, user_id as i64],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_utc_offset(
+        &self,
+        bot_id: u64,
+        user_id: u64,
+        utc_offset: i32,
+    ) -> anyhow::Result<()> {
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "UPDATE digests SET utc_offset = ?3 WHERE bot_id = ?1 AND user_id = ?2",
+                    params![bot_id as i64, user_id as i64, utc_offset],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<DigestRecipient>> {
+        self.db
+            .call(move |connection| {
+                let mut statement = connection.prepare_cached(
+                    "SELECT bot_id, user_id, last_sent_on,
+                            frequency, hour, utc_offset, genre_id, genre_name
+                     FROM digests",
+                )?;
+
+                let rows = statement.query_map([], |row| {
+                    let last_sent_on: Option<String> = row.get(2)?;
+
+                    Ok(DigestRecipient {
+                        bot_id: row.get::<_, i64>(0)? as u64,
+                        user_id: row.get::<_, i64>(1)? as u64,
+                        digest: digest_from_row(row, 3)?,
+                        last_sent_on: last_sent_on
+                            .and_then(|date| NaiveDate::parse_from_str(&date, DATE_FORMAT).ok()),
+                    })
+                })?;
+
+                rows.collect()
+            })
+            .await
+    }
+
+    async fn mark_sent(&self, bot_id: u64, user_id: u64, date: NaiveDate) -> anyhow::Result<()> {
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "UPDATE digests SET last_sent_on = ?3 WHERE bot_id = ?1 AND user_id = ?2",
+                    params![
+                        bot_id as i64,
+                        user_id as i64,
+                        date.format(DATE_FORMAT).to_string()
+                    ],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+pub static DIGESTS: LazyLock<Box<dyn DigestStorage>> = LazyLock::new(|| {
+    Box::new(
+        SqliteDigestStorage::new(LOCAL_DB.clone())
+            .unwrap_or_else(|err| panic!("Cannot init digests storage: {err}")),
+    )
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage() -> SqliteDigestStorage {
+        SqliteDigestStorage::new(LocalDb::open_in_memory().unwrap()).unwrap()
+    }
+
+    fn digest() -> Digest {
+        Digest {
+            frequency: DigestFrequency::Daily,
+            hour: 9,
+            utc_offset: 180,
+            genre_id: None,
+            genre_name: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn save_update_and_disable() {
+        let storage = storage();
+
+        assert_eq!(storage.get(1, 10).await.unwrap(), None);
+
+        storage.save(1, 10, digest()).await.unwrap();
+        assert_eq!(storage.get(1, 10).await.unwrap(), Some(digest()));
+        assert_eq!(storage.get(2, 10).await.unwrap(), None);
+
+        let weekly = Digest {
+            frequency: DigestFrequency::Weekly,
+            genre_id: Some(5),
+            genre_name: Some("Фантастика".to_string()),
+            ..digest()
+        };
+        storage.save(1, 10, weekly.clone()).await.unwrap();
+        assert_eq!(storage.get(1, 10).await.unwrap(), Some(weekly));
+
+        storage.disable(1, 10).await.unwrap();
+        assert_eq!(storage.get(1, 10).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn sent_date_survives_settings_changes() {
+        let storage = storage();
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+
+        storage.save(1, 10, digest()).await.unwrap();
+        storage.mark_sent(1, 10, date).await.unwrap();
+        storage
+            .save(
+                1,
+                10,
+                Digest {
+                    hour: 20,
+                    ..digest()
+                },
+            )
+            .await
+            .unwrap();
+        storage.set_utc_offset(1, 10, -300).await.unwrap();
+
+        assert_eq!(
+            storage.list().await.unwrap(),
+            vec![DigestRecipient {
+                bot_id: 1,
+                user_id: 10,
+                digest: Digest {
+                    hour: 20,
+                    utc_offset: -300,
+                    ..digest()
+                },
+                last_sent_on: Some(date),
+            }]
+        );
+    }
+
+    #[test]
+    fn frequency_codes_round_trip() {
+        for frequency in [DigestFrequency::Daily, DigestFrequency::Weekly] {
+            assert_eq!(
+                DigestFrequency::from_code(frequency.code()),
+                Some(frequency)
+            );
+        }
+        assert_eq!(DigestFrequency::from_code("x"), None);
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Timelike, Utc};
+use teloxide::{
+    adaptors::{throttle::Limits, Throttle},
+    prelude::*,
+    types::InlineKeyboardMarkup,
+    ApiError, RequestError,
+};
+use tracing::log;
+
+use crate::{
+    bots::approved_bot::{
+        modules::{
+            update_history::callback_data::UpdateLogCallbackData,
+            utils::{
+                constants::TELEGRAM_MESSAGE_MAX_LENGTH, pagination::generic_get_pagination_keyboard,
+            },
+        },
+        services::{
+            book_library::get_uploaded_books,
+            user_settings::{get_user_or_default_lang_codes, get_user_page_size},
+        },
+    },
+    config,
+};
+
+use super::{Digest, DigestFrequency, DigestRecipient, DIGESTS};
+
+/// Local date and the complete days the digest covers, when it is due at
+/// `now`. A digest is due once its hour has come and a full period has
+/// passed since the last one.
+fn due_period(
+    digest: &Digest,
+    last_sent_on: Option<NaiveDate>,
+    now: DateTime<Utc>,
+) -> Option<(NaiveDate, NaiveDate, NaiveDate)> {
+    let timezone = FixedOffset::east_opt(digest.utc_offset * 60)?;
+    let local_now = now.with_timezone(&timezone);
+    let today = local_now.date_naive();
+
+    if local_now.hour() < digest.hour {
+        return None;
+    }
+
+    let days = digest.frequency.days();
+
+    if let Some(last_sent_on) = last_sent_on {
+        if (today - last_sent_on).num_days() < days {
+            return None;
+        }
+    }
+
+    Some((
+        today,
+        today - Duration::days(days),
+        today - Duration::days(1),
+    ))
+}
+
+fn format_header(digest: &Digest, from: NaiveDate, to: NaiveDate) -> String {
+    let period = match digest.frequency {
+        DigestFrequency::Daily => format!("за {}", from.format("%d.%m.%Y")),
+        DigestFrequency::Weekly => format!(
+            "за неделю ({} - {})",
+            from.format("%d.%m.%Y"),
+            to.format("%d.%m.%Y")
+        ),
+    };
+
+    match &digest.genre_name {
+        Some(genre) => format!("📬 Новые книги жанра «{genre}» {period}:\n\n"),
+        None => format!("📬 Новые книги {period}:\n\n"),
+    }
+}
+
+/// First page of the digest; the rest is paged through the update log.
+/// `None` when nothing was uploaded.
+async fn build_digest_message(
+    user_id: u64,
+    digest: &Digest,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> anyhow::Result<Option<(String, InlineKeyboardMarkup)>> {
+    let user_id = UserId(user_id);
+    let allowed_langs = get_user_or_default_lang_codes(user_id).await;
+    let page_size = get_user_page_size(user_id).await;
+
+    let items_page = match get_uploaded_books(
+        1,
+        page_size,
+        allowed_langs,
+        digest.genre_id,
+        from.format("%Y-%m-%d").to_string().into(),
+        to.format("%Y-%m-%d").to_string().into(),
+    )
+    .await?
+    {
+        Some(v) if v.pages > 0 => v,
+        _ => return Ok(None),
+    };
+
+    let header = format_header(digest, from, to);
+    let formatted_page =
+        items_page.format(1, TELEGRAM_MESSAGE_MAX_LENGTH.saturating_sub(header.len()));
+
+    let keyboard = generic_get_pagination_keyboard(
+        1,
+        items_page.pages,
+        UpdateLogCallbackData {
+            from,
+            to,
+            genre: digest.genre_id,
+            page: 1,
+        },
+    );
+
+    Ok(Some((format!("{header}{formatted_page}"), keyboard)))
+}
+
+/// A failure here only means the digest may be sent again, so it is logged
+/// rather than stopping the run.
+async fn mark_sent(bot_id: u64, user_id: u64, today: NaiveDate) {
+    if let Err(err) = DIGESTS.mark_sent(bot_id, user_id, today).await {
+        log::warn!("Failed to mark digest as sent: {err:?}");
+    }
+}
+
+/// Sends every due digest through the bot the user opted in with. `bots`
+/// maps Telegram bot ids onto tokens; digests of bots missing from it are
+/// left for a later run.
+///
+/// A digest is marked as sent only once it went out (or there was nothing
+/// new for it), so a failed build or send is retried on the next run.
+pub async fn send_digests(bots: HashMap<u64, String>) -> anyhow::Result<()> {
+    let now = Utc::now();
+    let mut telegram_bots: HashMap<u64, Throttle<Bot>> = HashMap::new();
+
+    for DigestRecipient {
+        bot_id,
+        user_id,
+        digest,
+        last_sent_on,
+    } in DIGESTS.list().await?
+    {
+        let Some(token) = bots.get(&bot_id) else {
+            continue;
+        };
+        let Some((today, from, to)) = due_period(&digest, last_sent_on, now) else {
+            continue;
+        };
+
+        let (text, keyboard) = match build_digest_message(user_id, &digest, from, to).await {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                mark_sent(bot_id, user_id, today).await;
+                continue;
+            }
+            Err(err) => {
+                log::warn!("Failed to build digest: {err:?}");
+                continue;
+            }
+        };
+
+        let bot = telegram_bots.entry(bot_id).or_insert_with(|| {
+            Bot::new(token.clone())
+                .set_api_url(config::CONFIG.telegram_bot_api.clone())
+                .throttle(Limits::default())
+        });
+
+        match bot
+            .send_message(ChatId(user_id as i64), text)
+            .reply_markup(keyboard)
+            .await
+        {
+            Ok(_) => mark_sent(bot_id, user_id, today).await,
+            Err(RequestError::Api(
+                ApiError::BotBlocked | ApiError::UserDeactivated | ApiError::ChatNotFound,
+            )) => {
+                if let Err(err) = DIGESTS.disable(bot_id, user_id).await {
+                    log::warn!("Failed to disable digest: {err:?}");
+                }
+            }
+            Err(err) => {
+                log::warn!("Failed to send digest: {err:?}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn digest(frequency: DigestFrequency) -> Digest {
+        Digest {
+            frequency,
+            hour: 9,
+            utc_offset: 180,
+            genre_id: None,
+            genre_name: None,
+        }
+    }
+
+    #[test]
+    fn daily_digest_waits_for_the_local_hour() {
+        let digest = digest(DigestFrequency::Daily);
+
+        // 08:30 and 09:30 in UTC+3.
+        let before = Utc.with_ymd_and_hms(2024, 5, 10, 5, 30, 0).unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 5, 10, 6, 30, 0).unwrap();
+
+        assert_eq!(due_period(&digest, None, before), None);
+        assert_eq!(
+            due_period(&digest, None, after),
+            Some((date(5, 10), date(5, 9), date(5, 9)))
+        );
+        assert_eq!(due_period(&digest, Some(date(5, 10)), after), None);
+        assert!(due_period(&digest, Some(date(5, 9)), after).is_some());
+    }
+
+    #[test]
+    fn local_midnight_starts_a_new_day() {
+        let digest = Digest {
+            hour: 0,
+            ..digest(DigestFrequency::Daily)
+        };
+
+        // 00:10 of May 11 in UTC+3.
+        let now = Utc.with_ymd_and_hms(2024, 5, 10, 21, 10, 0).unwrap();
+
+        assert_eq!(
+            due_period(&digest, Some(date(5, 10)), now),
+            Some((date(5, 11), date(5, 10), date(5, 10)))
+        );
+    }
+
+    #[test]
+    fn weekly_digest_covers_the_past_week() {
+        let digest = digest(DigestFrequency::Weekly);
+        let now = Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+
+        assert_eq!(due_period(&digest, Some(date(5, 4)), now), None);
+        assert_eq!(
+            due_period(&digest, Some(date(5, 3)), now),
+            Some((date(5, 10), date(5, 3), date(5, 9)))
+        );
+    }
+
+    #[test]
+    fn header_names_period_and_genre() {
+        let weekly = Digest {
+            genre_name: Some("Фантастика".to_string()),
+            ..digest(DigestFrequency::Weekly)
+        };
+
+        assert_eq!(
+            format_header(&digest(DigestFrequency::Daily), date(5, 9), date(5, 9)),
+            "📬 Новые книги за 09.05.2024:\n\n"
+        );
+        assert_eq!(
+            format_header(&weekly, date(5, 3), date(5, 9)),
+            "📬 Новые книги жанра «Фантастика» за неделю (03.05.2024 - 09.05.2024):\n\n"
+        );
+    }
+}
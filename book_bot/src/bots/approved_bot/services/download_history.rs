@@ -0,0 +1,171 @@
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+
+use super::local_db::{LocalDb, LOCAL_DB};
+
+/// Older downloads are dropped once a user goes past this many.
+pub const MAX_HISTORY_SIZE: u32 = 1000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DownloadRecord {
+    pub book_id: u32,
+    pub file_type: String,
+    pub downloaded_at: DateTime<Utc>,
+}
+
+/// Books a user has received from the bot.
+#[async_trait]
+pub trait DownloadHistoryStorage: Send + Sync {
+    async fn add(&self, user_id: u64, book_id: u32, file_type: String) -> anyhow::Result<()>;
+
+    /// Records, most recent first.
+    async fn list(
+        &self,
+        user_id: u64,
+        offset: u32,
+        limit: u32,
+    ) -> anyhow::Result<Vec<DownloadRecord>>;
+
+    async fn count(&self, user_id: u64) -> anyhow::Result<u32>;
+}
+
+pub struct SqliteDownloadHistoryStorage {
+    db: LocalDb,
+}
+
+impl SqliteDownloadHistoryStorage {
+    pub fn new(db: LocalDb) -> anyhow::Result<Self> {
+        db.migrate(
+            "CREATE TABLE IF NOT EXISTS download_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                book_id INTEGER NOT NULL,
+                file_type TEXT NOT NULL,
+                downloaded_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS download_history_user
+                ON download_history (user_id, id);",
+        )?;
+
+        Ok(Self { db })
+    }
+}
+
+#[async_trait]
+impl DownloadHistoryStorage for SqliteDownloadHistoryStorage {
+    async fn add(&self, user_id: u64, book_id: u32, file_type: String) -> anyhow::Result<()> {
+        let downloaded_at = Utc::now().timestamp();
+
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "INSERT INTO download_history (user_id, book_id, file_type, downloaded_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![user_id as i64, book_id, file_type, downloaded_at],
+                )?;
+
+                connection.execute(
+                    "DELETE FROM download_history
+                     WHERE user_id = ?1 AND id <= (
+                        SELECT id FROM download_history WHERE user_id = ?1
+                        ORDER BY id DESC LIMIT 1 OFFSET ?2
+                     )",
+                    params![user_id as i64, MAX_HISTORY_SIZE],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        user_id: u64,
+        offset: u32,
+        limit: u32,
+    ) -> anyhow::Result<Vec<DownloadRecord>> {
+        self.db
+            .call(move |connection| {
+                let mut statement = connection.prepare_cached(
+                    "SELECT book_id, file_type, downloaded_at FROM download_history
+                     WHERE user_id = ?1 ORDER BY id DESC LIMIT ?2 OFFSET ?3",
+                )?;
+
+                let rows = statement.query_map(params![user_id as i64, limit, offset], |row| {
+                    Ok(DownloadRecord {
+                        book_id: row.get(0)?,
+                        file_type: row.get(1)?,
+                        downloaded_at: DateTime::from_timestamp(row.get(2)?, 0).unwrap_or_default(),
+                    })
+                })?;
+
+                rows.collect()
+            })
+            .await
+    }
+
+    async fn count(&self, user_id: u64) -> anyhow::Result<u32> {
+        self.db
+            .call(move |connection| {
+                connection.query_row(
+                    "SELECT COUNT(*) FROM download_history WHERE user_id = ?1",
+                    params![user_id as i64],
+                    |row| row.get(0),
+                )
+            })
+            .await
+    }
+}
+
+pub static DOWNLOAD_HISTORY: LazyLock<Box<dyn DownloadHistoryStorage>> = LazyLock::new(|| {
+    Box::new(
+        SqliteDownloadHistoryStorage::new(LOCAL_DB.clone())
+            .unwrap_or_else(|err| panic!("Cannot init download history storage: {err}")),
+    )
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage() -> SqliteDownloadHistoryStorage {
+        SqliteDownloadHistoryStorage::new(LocalDb::open_in_memory().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn lists_most_recent_first() {
+        let storage = storage();
+
+        storage.add(1, 10, "fb2".to_string()).await.unwrap();
+        storage.add(1, 20, "epub".to_string()).await.unwrap();
+        storage.add(2, 30, "fb2".to_string()).await.unwrap();
+
+        let records = storage.list(1, 0, 10).await.unwrap();
+        let books: Vec<(u32, &str)> = records
+            .iter()
+            .map(|r| (r.book_id, r.file_type.as_str()))
+            .collect();
+
+        assert_eq!(books, vec![(20, "epub"), (10, "fb2")]);
+        assert_eq!(storage.count(1).await.unwrap(), 2);
+        assert_eq!(storage.list(1, 1, 10).await.unwrap()[0].book_id, 10);
+    }
+
+    #[tokio::test]
+    async fn keeps_at_most_max_history_size_records() {
+        let storage = storage();
+
+        for book_id in 0..MAX_HISTORY_SIZE + 5 {
+            storage.add(1, book_id, "fb2".to_string()).await.unwrap();
+        }
+
+        assert_eq!(storage.count(1).await.unwrap(), MAX_HISTORY_SIZE);
+        assert_eq!(
+            storage.list(1, 0, 1).await.unwrap()[0].book_id,
+            MAX_HISTORY_SIZE + 4
+        );
+    }
+}
@@ -0,0 +1,457 @@
+//! Covers embedded into downloaded books: FB2 keeps them base64-encoded in
+//! a `<binary>`, EPUB as an image declared in the OPF package. Files are
+//! read as streams, so large books are never loaded whole.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read, Seek},
+    path::Path,
+    sync::LazyLock,
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use moka::future::Cache;
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use tracing::log;
+use zip::ZipArchive;
+
+use super::{decode_image, photo_jpeg, thumbnail_jpeg, MAX_IMAGE_BYTES};
+
+/// Package documents are small; anything bigger is not worth parsing.
+const MAX_XML_BYTES: u64 = 1024 * 1024;
+
+/// Cover of a downloaded book, ready to send.
+pub struct Cover {
+    pub thumbnail: Vec<u8>,
+    pub photo: Vec<u8>,
+}
+
+/// Covers of recently downloaded books, shown on their book cards when the
+/// library has no cover of its own. Only kept in memory until the card has
+/// been shown once; from then on `PHOTO_FILE_IDS` keeps the uploaded photo
+/// under `book_cover_key`.
+pub static BOOK_COVERS: LazyLock<Cache<u32, Vec<u8>>> = LazyLock::new(|| {
+    Cache::builder()
+        .weigher(|_, photo: &Vec<u8>| photo.len().try_into().unwrap_or(u32::MAX))
+        .max_capacity(64 * 1024 * 1024)
+        .time_to_idle(Duration::from_secs(24 * 60 * 60))
+        .build()
+});
+
+/// Key of a book's cover in `PHOTO_FILE_IDS`, which otherwise holds URLs.
+pub fn book_cover_key(book_id: u32) -> String {
+    format!("book-cover:{book_id}")
+}
+
+/// Whether `filename` is a format covers are looked for in.
+pub fn may_have_cover(filename: &str) -> bool {
+    let filename = filename.to_lowercase();
+
+    filename.ends_with(".fb2") || filename.ends_with(".fb2.zip") || filename.ends_with(".epub")
+}
+
+/// Cover of the book at `path`, `None` if there is none or the file is
+/// broken. Blocking.
+pub fn extract_cover(path: &Path, filename: &str) -> Option<Cover> {
+    let result = File::open(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| read_cover(file, &filename.to_lowercase()))
+        .and_then(|image| {
+            image
+                .map(|image| {
+                    let image = decode_image(&image)?;
+
+                    Ok(Cover {
+                        thumbnail: thumbnail_jpeg(&image)?,
+                        photo: photo_jpeg(&image)?,
+                    })
+                })
+                .transpose()
+        });
+
+    result.unwrap_or_else(|err| {
+        log::debug!("No cover in {filename}: {err:?}");
+        None
+    })
+}
+
+fn read_cover(file: File, filename: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    if filename.ends_with(".epub") {
+        return epub_cover(file);
+    }
+
+    if filename.ends_with(".zip") {
+        let mut archive = ZipArchive::new(file)?;
+        let Some(name) = archive
+            .file_names()
+            .find(|name| name.to_lowercase().ends_with(".fb2"))
+            .map(str::to_string)
+        else {
+            return Ok(None);
+        };
+
+        return fb2_cover(BufReader::new(archive.by_name(&name)?));
+    }
+
+    fb2_cover(BufReader::new(file))
+}
+
+fn attribute(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|attribute| attribute.key.local_name().as_ref() == name)
+        .map(|attribute| String::from_utf8_lossy(&attribute.value).into_owned())
+}
+
+/// FB2 names its cover in `<coverpage>` of the description, which comes
+/// before the binaries.
+pub fn fb2_cover<R: BufRead>(reader: R) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut reader = Reader::from_reader(reader);
+    reader.config_mut().check_end_names = false;
+
+    let mut buf = Vec::new();
+    let mut in_coverpage = false;
+    let mut cover_id: Option<String> = None;
+    let mut data: Option<Vec<u8>> = None;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(element) | Event::Empty(element) => match element.local_name().as_ref() {
+                b"coverpage" => in_coverpage = true,
+                b"image" if in_coverpage && cover_id.is_none() => {
+                    cover_id = attribute(&element, b"href")
+                        .map(|href| href.trim_start_matches('#').to_string());
+                }
+                b"binary" => {
+                    let Some(cover_id) = cover_id.as_deref() else {
+                        return Ok(None);
+                    };
+                    if attribute(&element, b"id").as_deref() == Some(cover_id) {
+                        data = Some(Vec::new());
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(text) => {
+                if let Some(data) = data.as_mut() {
+                    data.extend(text.iter().filter(|c| !c.is_ascii_whitespace()));
+
+                    // Base64 takes 4 bytes for every 3.
+                    if data.len() / 4 * 3 > MAX_IMAGE_BYTES {
+                        return Ok(None);
+                    }
+                }
+            }
+            Event::End(element) => match element.local_name().as_ref() {
+                b"coverpage" => in_coverpage = false,
+                b"binary" => {
+                    if let Some(data) = data {
+                        return Ok(Some(STANDARD.decode(data)?));
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+
+        buf.clear();
+    }
+}
+
+fn read_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+    limit: u64,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let entry = match archive.by_name(name) {
+        Ok(v) => v,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    if entry.size() > limit {
+        return Ok(None);
+    }
+
+    let mut data = Vec::new();
+    entry.take(limit).read_to_end(&mut data)?;
+
+    Ok(Some(data))
+}
+
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let decoded = (bytes[index] == b'%')
+            .then(|| path.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match decoded {
+            Some(byte) => {
+                result.push(byte);
+                index += 3;
+            }
+            None => {
+                result.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+/// Archive path of `href` given relative to the package document.
+fn resolve_href(package_path: &str, href: &str) -> String {
+    let mut segments: Vec<&str> = package_path.split('/').collect();
+    segments.pop();
+
+    let href = percent_decode(href.split('#').next().unwrap_or_default());
+
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    segments.join("/")
+}
+
+struct ManifestItem {
+    id: String,
+    href: String,
+    media_type: String,
+    properties: String,
+}
+
+/// EPUB 3 marks the cover item with `cover-image`, EPUB 2 points at it
+/// with `<meta name="cover">`; failing both, an image called "cover" is
+/// the best guess.
+fn find_cover_href(package: &[u8]) -> anyhow::Result<Option<String>> {
+    let mut reader = Reader::from_reader(package);
+    reader.config_mut().check_end_names = false;
+
+    let mut buf = Vec::new();
+    let mut items = vec![];
+    let mut meta_cover_id = None;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(element) | Event::Empty(element) => match element.local_name().as_ref() {
+                b"item" => items.push(ManifestItem {
+                    id: attribute(&element, b"id").unwrap_or_default(),
+                    href: attribute(&element, b"href").unwrap_or_default(),
+                    media_type: attribute(&element, b"media-type").unwrap_or_default(),
+                    properties: attribute(&element, b"properties").unwrap_or_default(),
+                }),
+                b"meta" if attribute(&element, b"name").as_deref() == Some("cover") => {
+                    meta_cover_id = attribute(&element, b"content");
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+
+        buf.clear();
+    }
+
+    let is_image = |item: &&ManifestItem| item.media_type.starts_with("image/");
+
+    let cover = items
+        .iter()
+        .filter(is_image)
+        .find(|item| {
+            item.properties
+                .split_whitespace()
+                .any(|p| p == "cover-image")
+        })
+        .or_else(|| {
+            items
+                .iter()
+                .filter(is_image)
+                .find(|item| Some(&item.id) == meta_cover_id.as_ref())
+        })
+        .or_else(|| {
+            items.iter().filter(is_image).find(|item| {
+                item.id.to_lowercase().contains("cover")
+                    || item.href.to_lowercase().contains("cover")
+            })
+        });
+
+    Ok(cover.map(|item| item.href.clone()))
+}
+
+pub fn epub_cover<R: Read + Seek>(reader: R) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut archive = ZipArchive::new(reader)?;
+
+    let Some(container) = read_entry(&mut archive, "META-INF/container.xml", MAX_XML_BYTES)? else {
+        return Ok(None);
+    };
+
+    let mut reader = Reader::from_reader(container.as_slice());
+    let mut buf = Vec::new();
+    let mut package_path = None;
+
+    while package_path.is_none() {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(element) | Event::Empty(element)
+                if element.local_name().as_ref() == b"rootfile" =>
+            {
+                package_path = attribute(&element, b"full-path");
+            }
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+
+        buf.clear();
+    }
+    let package_path = package_path.unwrap_or_default();
+
+    let Some(package) = read_entry(&mut archive, &package_path, MAX_XML_BYTES)? else {
+        return Ok(None);
+    };
+    let Some(href) = find_cover_href(&package)? else {
+        return Ok(None);
+    };
+
+    read_entry(
+        &mut archive,
+        &resolve_href(&package_path, &href),
+        MAX_IMAGE_BYTES as u64,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::*;
+
+    const IMAGE: &[u8] = b"\x89PNG fake image";
+
+    fn fb2(cover: &str) -> String {
+        format!(
+            r##"<?xml version="1.0" encoding="windows-1251"?>
+<FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0" xmlns:l="http://www.w3.org/1999/xlink">
+<description><title-info><coverpage><image l:href="#{cover}"/></coverpage></title-info></description>
+<body><p>Text</p></body>
+<binary id="other.jpg" content-type="image/jpeg">AAAA</binary>
+<binary id="cover.png" content-type="image/png">
+{}
+</binary>
+</FictionBook>"##,
+            STANDARD.encode(IMAGE)
+        )
+    }
+
+    fn zip(entries: &[(&str, &[u8])]) -> Cursor<Vec<u8>> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+
+        for (name, data) in entries {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+
+        let mut result = writer.finish().unwrap();
+        result.set_position(0);
+        result
+    }
+
+    const CONTAINER: &[u8] = br#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#;
+
+    #[test]
+    fn fb2_cover_is_decoded() {
+        assert_eq!(
+            fb2_cover(fb2("cover.png").as_bytes()).unwrap().as_deref(),
+            Some(IMAGE)
+        );
+    }
+
+    #[test]
+    fn fb2_without_cover_has_none() {
+        assert_eq!(fb2_cover(fb2("missing.png").as_bytes()).unwrap(), None);
+        assert_eq!(
+            fb2_cover(&b"<FictionBook><binary id=\"a\">AAAA</binary></FictionBook>"[..]).unwrap(),
+            None
+        );
+        assert!(fb2_cover(&b"<FictionBook><coverpage><image l:href=\"#a\"/></coverpage><binary id=\"a\">!!!</binary>"[..]).is_err());
+    }
+
+    #[test]
+    fn epub2_cover_from_meta() {
+        let package = br#"<package xmlns="http://www.idpf.org/2007/opf" version="2.0">
+  <metadata><meta name="cover" content="img1"/></metadata>
+  <manifest>
+    <item id="ch1" href="text/ch1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="img1" href="../images/my%20cover.png" media-type="image/png"/>
+  </manifest>
+</package>"#;
+        let epub = zip(&[
+            ("META-INF/container.xml", CONTAINER),
+            ("OEBPS/content.opf", package),
+            ("images/my cover.png", IMAGE),
+        ]);
+
+        assert_eq!(epub_cover(epub).unwrap().as_deref(), Some(IMAGE));
+    }
+
+    #[test]
+    fn epub3_cover_from_properties() {
+        let package = br#"<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <manifest>
+    <item id="a" href="a.png" media-type="image/png"/>
+    <item id="b" href="img/b.png" media-type="image/png" properties="cover-image"/>
+  </manifest>
+</package>"#;
+        let epub = zip(&[
+            ("META-INF/container.xml", CONTAINER),
+            ("OEBPS/content.opf", package),
+            ("OEBPS/img/b.png", IMAGE),
+        ]);
+
+        assert_eq!(epub_cover(epub).unwrap().as_deref(), Some(IMAGE));
+    }
+
+    #[test]
+    fn broken_files_fall_back_silently() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("book.epub");
+        std::fs::write(&path, b"not a zip").unwrap();
+
+        assert!(extract_cover(&path, "book.epub").is_none());
+        assert!(extract_cover(&path, "book.fb2").is_none());
+        assert!(epub_cover(zip(&[("mimetype", b"application/epub+zip")]))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn formats_with_covers() {
+        assert!(may_have_cover("Tolstoy_Voyna.fb2.zip"));
+        assert!(may_have_cover("book.EPUB"));
+        assert!(!may_have_cover("book.pdf"));
+        assert!(!may_have_cover("book.mobi"));
+    }
+}
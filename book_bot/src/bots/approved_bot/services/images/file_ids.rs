@@ -0,0 +1,115 @@
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use rusqlite::{params, OptionalExtension};
+
+use crate::bots::approved_bot::services::local_db::{LocalDb, LOCAL_DB};
+
+/// Telegram `file_id`s of photos already uploaded from library URLs, or
+/// from book covers under `covers::book_cover_key`. The ids are only valid
+/// for the bot that uploaded the photo, hence `bot_id`.
+#[async_trait]
+pub trait PhotoFileIdStorage: Send + Sync {
+    async fn get(&self, bot_id: u64, url: String) -> anyhow::Result<Option<String>>;
+
+    async fn save(&self, bot_id: u64, url: String, file_id: String) -> anyhow::Result<()>;
+
+    async fn remove(&self, bot_id: u64, url: String) -> anyhow::Result<()>;
+}
+
+pub struct SqlitePhotoFileIdStorage {
+    db: LocalDb,
+}
+
+impl SqlitePhotoFileIdStorage {
+    pub fn new(db: LocalDb) -> anyhow::Result<Self> {
+        db.migrate(
+            "CREATE TABLE IF NOT EXISTS photo_file_ids (
+                bot_id INTEGER NOT NULL,
+                url TEXT NOT NULL,
+                file_id TEXT NOT NULL,
+                PRIMARY KEY (bot_id, url)
+            );",
+        )?;
+
+        Ok(Self { db })
+    }
+}
+
+// Telegram ids fit into SQLite's signed 64-bit integers.
+#[async_trait]
+impl PhotoFileIdStorage for SqlitePhotoFileIdStorage {
+    async fn get(&self, bot_id: u64, url: String) -> anyhow::Result<Option<String>> {
+        self.db
+            .call(move |connection| {
+                connection
+                    .prepare_cached(
+                        "SELECT file_id FROM photo_file_ids WHERE bot_id = ?1 AND url = ?2",
+                    )?
+                    .query_row(params![bot_id as i64, url], |row| row.get(0))
+                    .optional()
+            })
+            .await
+    }
+
+    async fn save(&self, bot_id: u64, url: String, file_id: String) -> anyhow::Result<()> {
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "INSERT OR REPLACE INTO photo_file_ids (bot_id, url, file_id)
+                     VALUES (?1, ?2, ?3)",
+                    params![bot_id as i64, url, file_id],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn remove(&self, bot_id: u64, url: String) -> anyhow::Result<()> {
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "DELETE FROM photo_file_ids WHERE bot_id = ?1 AND url = ?2",
+                    params![bot_id as i64, url],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+pub static PHOTO_FILE_IDS: LazyLock<Box<dyn PhotoFileIdStorage>> = LazyLock::new(|| {
+    Box::new(
+        SqlitePhotoFileIdStorage::new(LOCAL_DB.clone())
+            .unwrap_or_else(|err| panic!("Cannot init photo file ids storage: {err}")),
+    )
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn file_ids_are_kept_per_bot() {
+        let storage = SqlitePhotoFileIdStorage::new(LocalDb::open_in_memory().unwrap()).unwrap();
+        let url = "https://library/cover.jpg".to_string();
+
+        storage.save(1, url.clone(), "a".to_string()).await.unwrap();
+        assert_eq!(
+            storage.get(1, url.clone()).await.unwrap().as_deref(),
+            Some("a")
+        );
+        assert_eq!(storage.get(2, url.clone()).await.unwrap(), None);
+
+        storage.save(1, url.clone(), "b".to_string()).await.unwrap();
+        assert_eq!(
+            storage.get(1, url.clone()).await.unwrap().as_deref(),
+            Some("b")
+        );
+
+        storage.remove(1, url.clone()).await.unwrap();
+        assert_eq!(storage.get(1, url).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,255 @@
+pub mod covers;
+pub mod file_ids;
+
+use std::io::Cursor;
+use std::time::Duration;
+
+use image::{
+    codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, GenericImageView, ImageReader,
+    Limits,
+};
+use reqwest::header::CONTENT_TYPE;
+use thiserror::Error;
+
+use super::{check_status, HTTP_CLIENT};
+
+/// Telegram rejects photos over 10 MB, and nothing bigger is worth
+/// downloading for a cover.
+pub const MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Telegram scales photos down to this side anyway.
+const MAX_PHOTO_SIDE: u32 = 2560;
+
+/// Telegram refuses photos stretched further than this.
+const MAX_ASPECT_RATIO: u32 = 20;
+
+/// A few megabytes of image data can claim a huge canvas; anything past
+/// these is refused before its pixels are allocated.
+const MAX_DECODED_SIDE: u32 = 10_000;
+const MAX_DECODED_BYTES: u64 = 256 * 1024 * 1024;
+
+const JPEG_QUALITY: u8 = 85;
+
+/// Telegram wants document thumbnails within 320x320 and 200 kB.
+const THUMBNAIL_SIDE: u32 = 320;
+const MAX_THUMBNAIL_BYTES: usize = 200 * 1024;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Error)]
+pub enum ImageError {
+    #[error("not an image: {0}")]
+    NotAnImage(String),
+    #[error("image is larger than {MAX_IMAGE_BYTES} bytes")]
+    TooLarge,
+    #[error("image of {0}x{1} cannot be sent as a photo")]
+    BadShape(u32, u32),
+    #[error("image is too large to decode: {0}")]
+    OverLimits(String),
+}
+
+/// Downloads an image through the shared client, refusing anything that
+/// is not an image or is too large. `None` if the library has no file.
+pub async fn fetch_image(url: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    let response = HTTP_CLIENT.get(url).timeout(FETCH_TIMEOUT).send().await?;
+
+    let Some(mut response) = check_status(response, &[reqwest::StatusCode::NOT_FOUND]).await?
+    else {
+        return Ok(None);
+    };
+
+    if let Some(content_type) = response.headers().get(CONTENT_TYPE) {
+        let content_type = content_type.to_str().unwrap_or_default();
+
+        if !content_type.starts_with("image/") {
+            return Err(ImageError::NotAnImage(content_type.to_string()).into());
+        }
+    }
+
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_IMAGE_BYTES as u64)
+    {
+        return Err(ImageError::TooLarge.into());
+    }
+
+    let mut data = Vec::new();
+
+    while let Some(chunk) = response.chunk().await? {
+        if data.len() + chunk.len() > MAX_IMAGE_BYTES {
+            return Err(ImageError::TooLarge.into());
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    Ok(Some(data))
+}
+
+/// Decodes an image of any supported format within `MAX_DECODED_SIDE` and
+/// `MAX_DECODED_BYTES`.
+pub fn decode_image(data: &[u8]) -> anyhow::Result<DynamicImage> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DECODED_SIDE);
+    limits.max_image_height = Some(MAX_DECODED_SIDE);
+    limits.max_alloc = Some(MAX_DECODED_BYTES);
+
+    let mut reader = ImageReader::new(Cursor::new(data)).with_guessed_format()?;
+    reader.limits(limits);
+
+    reader.decode().map_err(|err| match err {
+        image::ImageError::Limits(err) => ImageError::OverLimits(err.to_string()).into(),
+        err => ImageError::NotAnImage(err.to_string()).into(),
+    })
+}
+
+/// Re-encodes an image as a JPEG Telegram accepts as a photo, scaling it
+/// down to `MAX_PHOTO_SIDE` if needed.
+pub fn to_telegram_jpeg(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    photo_jpeg(&decode_image(data)?)
+}
+
+/// `to_telegram_jpeg` for an already decoded image.
+pub fn photo_jpeg(image: &DynamicImage) -> anyhow::Result<Vec<u8>> {
+    let (width, height) = image.dimensions();
+
+    if width == 0 || height == 0 || width.max(height) / width.min(height) >= MAX_ASPECT_RATIO {
+        return Err(ImageError::BadShape(width, height).into());
+    }
+
+    // JPEG has no alpha channel.
+    let image = if width.max(height) > MAX_PHOTO_SIDE {
+        DynamicImage::ImageRgb8(
+            image
+                .resize(MAX_PHOTO_SIDE, MAX_PHOTO_SIDE, FilterType::Triangle)
+                .to_rgb8(),
+        )
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    };
+
+    let mut result = Cursor::new(Vec::new());
+    image.write_with_encoder(JpegEncoder::new_with_quality(&mut result, JPEG_QUALITY))?;
+
+    Ok(result.into_inner())
+}
+
+/// Downscales a decoded image into a JPEG document thumbnail.
+pub fn thumbnail_jpeg(image: &DynamicImage) -> anyhow::Result<Vec<u8>> {
+    let image = DynamicImage::ImageRgb8(image.thumbnail(THUMBNAIL_SIDE, THUMBNAIL_SIDE).to_rgb8());
+
+    for quality in [JPEG_QUALITY, 60, 40] {
+        let mut result = Cursor::new(Vec::new());
+        image.write_with_encoder(JpegEncoder::new_with_quality(&mut result, quality))?;
+
+        if result.get_ref().len() <= MAX_THUMBNAIL_BYTES {
+            return Ok(result.into_inner());
+        }
+    }
+
+    Err(ImageError::TooLarge.into())
+}
+
+/// `fetch_image` followed by `to_telegram_jpeg`, off the async runtime.
+pub async fn fetch_telegram_photo(url: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    let Some(data) = fetch_image(url).await? else {
+        return Ok(None);
+    };
+
+    let photo = tokio::task::spawn_blocking(move || to_telegram_jpeg(&data)).await??;
+
+    Ok(Some(photo))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageFormat, RgbaImage};
+
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut result = Cursor::new(Vec::new());
+        RgbaImage::new(width, height)
+            .write_to(&mut result, ImageFormat::Png)
+            .unwrap();
+        result.into_inner()
+    }
+
+    #[test]
+    fn png_becomes_jpeg() {
+        let jpeg = to_telegram_jpeg(&png(30, 40)).unwrap();
+
+        assert_eq!(image::guess_format(&jpeg).unwrap(), ImageFormat::Jpeg);
+        assert_eq!(
+            image::load_from_memory(&jpeg).unwrap().dimensions(),
+            (30, 40)
+        );
+    }
+
+    #[test]
+    fn large_image_is_scaled_down() {
+        let jpeg =
+            to_telegram_jpeg(&png(MAX_PHOTO_SIDE * 5 / 4, MAX_PHOTO_SIDE / 4 * 5 / 4)).unwrap();
+
+        assert_eq!(
+            image::load_from_memory(&jpeg).unwrap().dimensions(),
+            (MAX_PHOTO_SIDE, MAX_PHOTO_SIDE / 4)
+        );
+    }
+
+    #[test]
+    fn thumbnail_fits_into_320px() {
+        let jpeg = thumbnail_jpeg(&decode_image(&png(600, 900)).unwrap()).unwrap();
+
+        assert_eq!(image::guess_format(&jpeg).unwrap(), ImageFormat::Jpeg);
+        assert_eq!(
+            image::load_from_memory(&jpeg).unwrap().dimensions(),
+            (213, 320)
+        );
+    }
+
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for byte in data {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xedb8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    /// A PNG header claiming a 60000x60000 canvas, with no pixels at all.
+    fn oversized_png() -> Vec<u8> {
+        let mut chunk = b"IHDR".to_vec();
+        chunk.extend_from_slice(&60000u32.to_be_bytes());
+        chunk.extend_from_slice(&60000u32.to_be_bytes());
+        // 8-bit RGBA, default compression, filtering and no interlacing.
+        chunk.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+        data.extend_from_slice(&13u32.to_be_bytes());
+        data.extend_from_slice(&chunk);
+        data.extend_from_slice(&crc32(&chunk).to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn refuses_oversized_headers_before_decoding() {
+        let err = to_telegram_jpeg(&oversized_png()).unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<ImageError>(),
+            Some(ImageError::OverLimits(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_garbage_and_narrow_strips() {
+        assert!(to_telegram_jpeg(b"<html>not found</html>").is_err());
+        assert!(to_telegram_jpeg(&png(400, 10)).is_err());
+    }
+}
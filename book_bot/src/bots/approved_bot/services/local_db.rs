@@ -0,0 +1,72 @@
+use std::sync::{Arc, LazyLock, Mutex};
+
+use rusqlite::Connection;
+
+use crate::config;
+
+/// Shared SQLite connection for bot-local state. Queries are short, so a
+/// single connection behind a mutex is enough; every call runs on the
+/// blocking pool to keep the async workers free.
+#[derive(Clone)]
+pub struct LocalDb {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl LocalDb {
+    pub fn open(path: &str) -> anyhow::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+
+        Ok(Self::from_connection(connection))
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> anyhow::Result<Self> {
+        Ok(Self::from_connection(Connection::open_in_memory()?))
+    }
+
+    fn from_connection(connection: Connection) -> Self {
+        Self {
+            connection: Arc::new(Mutex::new(connection)),
+        }
+    }
+
+    /// Runs schema statements synchronously; meant for store constructors,
+    /// which run once before the store is used.
+    pub fn migrate(&self, sql: &str) -> anyhow::Result<()> {
+        let connection = self
+            .connection
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Local db mutex is poisoned"))?;
+
+        connection.execute_batch(sql)?;
+
+        Ok(())
+    }
+
+    pub async fn call<T, F>(&self, f: F) -> anyhow::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let connection = self.connection.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut connection = connection
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Local db mutex is poisoned"))?;
+
+            f(&mut connection).map_err(anyhow::Error::from)
+        })
+        .await?
+    }
+}
+
+pub static LOCAL_DB: LazyLock<LocalDb> = LazyLock::new(|| {
+    LocalDb::open(&config::CONFIG.local_db_path).unwrap_or_else(|err| {
+        panic!(
+            "Cannot open local db at {}: {err}",
+            config::CONFIG.local_db_path
+        )
+    })
+});
@@ -1,8 +1,18 @@
 pub mod batch_downloader;
 pub mod book_cache;
 pub mod book_library;
+pub mod digests;
 pub mod donation_notifications;
+pub mod download_history;
+pub mod images;
+pub mod local_db;
 pub mod rate_limit;
+pub mod search_queries;
+pub mod selection;
+pub mod shelf;
+pub mod short_id;
+pub mod subscriptions;
+pub mod telegraph;
 pub mod user_settings;
 
 use std::sync::LazyLock;
@@ -0,0 +1,73 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use moka::future::Cache;
+
+use super::short_id::new_short_id;
+
+#[derive(Clone)]
+struct SearchQuery {
+    user_id: u64,
+    query: String,
+}
+
+/// Typed search queries, so paging a results message does not depend on
+/// the message it replies to. Kept in memory only: after a restart or once
+/// expired, pagination falls back to the replied-to message.
+static SEARCH_QUERIES: LazyLock<Cache<String, SearchQuery>> = LazyLock::new(|| {
+    Cache::builder()
+        .time_to_idle(Duration::from_secs(24 * 60 * 60))
+        .max_capacity(65536)
+        .build()
+});
+
+/// Stores the query typed by the user and returns its short id.
+pub async fn save_search_query(user_id: u64, query: &str) -> String {
+    let id = new_short_id();
+
+    SEARCH_QUERIES
+        .insert(
+            id.clone(),
+            SearchQuery {
+                user_id,
+                query: query.to_string(),
+            },
+        )
+        .await;
+
+    id
+}
+
+/// The stored query, or `None` if it expired or belongs to someone else.
+pub async fn get_search_query(id: &str, user_id: u64) -> Option<String> {
+    SEARCH_QUERIES
+        .get(id)
+        .await
+        .filter(|query| query.user_id == user_id)
+        .map(|query| query.query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn saved_queries_are_found_by_id() {
+        let id = save_search_query(1, "война и мир").await;
+        let other = save_search_query(1, "война и мир").await;
+
+        assert_ne!(id, other);
+        assert_eq!(
+            get_search_query(&id, 1).await.as_deref(),
+            Some("война и мир")
+        );
+        assert_eq!(get_search_query("missing", 1).await, None);
+    }
+
+    #[tokio::test]
+    async fn queries_are_private_to_their_user() {
+        let id = save_search_query(1, "война и мир").await;
+
+        assert_eq!(get_search_query(&id, 2).await, None);
+    }
+}
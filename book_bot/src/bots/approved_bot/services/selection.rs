@@ -0,0 +1,166 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use moka::{
+    future::Cache,
+    ops::compute::{CompResult, Op},
+};
+
+use super::{batch_downloader::MAX_TASK_BOOKS, short_id::new_short_id};
+
+/// The listing a selection was started from, enough to fetch its pages again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SelectionSource {
+    Search { query: String },
+    Author { id: u32 },
+    Translator { id: u32 },
+    Sequence { id: u32 },
+}
+
+/// Books picked by one user from one listing, across all of its pages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub user_id: u64,
+    pub source: SelectionSource,
+    pub book_ids: Vec<u32>,
+}
+
+impl Selection {
+    /// Selects or deselects the book. Returns `false` when a new book does
+    /// not fit anymore.
+    pub fn toggle(&mut self, book_id: u32) -> bool {
+        if let Some(index) = self.book_ids.iter().position(|id| *id == book_id) {
+            self.book_ids.remove(index);
+            return true;
+        }
+
+        if self.book_ids.len() >= MAX_TASK_BOOKS {
+            return false;
+        }
+
+        self.book_ids.push(book_id);
+        true
+    }
+}
+
+/// Selections are only kept in memory: they live as long as the user is
+/// clicking through a listing, and callback data carries just their id.
+static SELECTIONS: LazyLock<Cache<String, Selection>> = LazyLock::new(|| {
+    Cache::builder()
+        .time_to_idle(Duration::from_secs(60 * 60))
+        .max_capacity(16384)
+        .build()
+});
+
+/// Starts an empty selection and returns its short id.
+pub async fn create_selection(user_id: u64, source: SelectionSource) -> String {
+    let id = new_short_id();
+
+    SELECTIONS
+        .insert(
+            id.clone(),
+            Selection {
+                user_id,
+                source,
+                book_ids: vec![],
+            },
+        )
+        .await;
+
+    id
+}
+
+/// The user's selection, or `None` if it expired or belongs to someone else.
+pub async fn get_selection(id: &str, user_id: u64) -> Option<Selection> {
+    SELECTIONS
+        .get(id)
+        .await
+        .filter(|selection| selection.user_id == user_id)
+}
+
+pub enum ToggleOutcome {
+    Toggled(Selection),
+    /// The book is new and the selection is already full.
+    Full,
+    /// The selection expired or belongs to someone else.
+    Missing,
+}
+
+/// `Selection::toggle` on the stored selection as a single update, so quick
+/// clicks on several books cannot overwrite each other.
+pub async fn toggle_selected(id: &str, user_id: u64, book_id: u32) -> ToggleOutcome {
+    let result = SELECTIONS
+        .entry_by_ref(id)
+        .and_compute_with(|entry| {
+            let op = match entry.map(|entry| entry.into_value()) {
+                Some(mut selection) if selection.user_id == user_id => {
+                    if selection.toggle(book_id) {
+                        Op::Put(selection)
+                    } else {
+                        Op::Nop
+                    }
+                }
+                _ => Op::Nop,
+            };
+
+            std::future::ready(op)
+        })
+        .await;
+
+    match result {
+        CompResult::ReplacedWith(entry) => ToggleOutcome::Toggled(entry.into_value()),
+        CompResult::Unchanged(entry) if entry.value().user_id == user_id => ToggleOutcome::Full,
+        _ => ToggleOutcome::Missing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggle_adds_removes_and_respects_the_limit() {
+        let mut selection = Selection {
+            user_id: 1,
+            source: SelectionSource::Author { id: 1 },
+            book_ids: vec![],
+        };
+
+        assert!(selection.toggle(10));
+        assert!(selection.toggle(20));
+        assert!(selection.toggle(10));
+        assert_eq!(selection.book_ids, vec![20]);
+
+        selection.book_ids = (0..MAX_TASK_BOOKS as u32).collect();
+        assert!(!selection.toggle(1000));
+        assert_eq!(selection.book_ids.len(), MAX_TASK_BOOKS);
+    }
+
+    #[tokio::test]
+    async fn selections_are_private_to_their_user() {
+        let id = create_selection(1, SelectionSource::Sequence { id: 5 }).await;
+
+        assert!(get_selection(&id, 1).await.is_some());
+        assert!(get_selection(&id, 2).await.is_none());
+
+        let other = create_selection(1, SelectionSource::Sequence { id: 5 }).await;
+        assert_ne!(id, other);
+    }
+
+    #[tokio::test]
+    async fn concurrent_toggles_all_land() {
+        let id = create_selection(1, SelectionSource::Author { id: 1 }).await;
+
+        let toggles = (0..20).map(|book_id| toggle_selected(&id, 1, book_id));
+        futures::future::join_all(toggles).await;
+
+        let mut book_ids = get_selection(&id, 1).await.unwrap().book_ids;
+        book_ids.sort();
+        assert_eq!(book_ids, (0..20).collect::<Vec<_>>());
+
+        assert!(matches!(
+            toggle_selected(&id, 2, 100).await,
+            ToggleOutcome::Missing
+        ));
+    }
+}
@@ -0,0 +1,189 @@
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use rusqlite::params;
+
+use crate::{
+    bots::approved_bot::services::{build_url, check_response, check_status, HTTP_CLIENT},
+    config,
+};
+
+use super::local_db::{LocalDb, LOCAL_DB};
+
+/// A user's saved books.
+#[async_trait]
+pub trait ShelfStorage: Send + Sync {
+    async fn add(&self, user_id: u64, book_id: u32) -> anyhow::Result<()>;
+
+    async fn remove(&self, user_id: u64, book_id: u32) -> anyhow::Result<()>;
+
+    /// Book ids, most recently added first.
+    async fn list(&self, user_id: u64) -> anyhow::Result<Vec<u32>>;
+}
+
+pub struct SqliteShelfStorage {
+    db: LocalDb,
+}
+
+impl SqliteShelfStorage {
+    pub fn new(db: LocalDb) -> anyhow::Result<Self> {
+        db.migrate(
+            "CREATE TABLE IF NOT EXISTS shelf (
+                user_id INTEGER NOT NULL,
+                book_id INTEGER NOT NULL,
+                added_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (user_id, book_id)
+            );",
+        )?;
+
+        Ok(Self { db })
+    }
+}
+
+#[async_trait]
+impl ShelfStorage for SqliteShelfStorage {
+    async fn add(&self, user_id: u64, book_id: u32) -> anyhow::Result<()> {
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "INSERT OR IGNORE INTO shelf (user_id, book_id) VALUES (?1, ?2)",
+                    params![user_id as i64, book_id],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn remove(&self, user_id: u64, book_id: u32) -> anyhow::Result<()> {
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "DELETE FROM shelf WHERE user_id = ?1 AND book_id = ?2",
+                    params![user_id as i64, book_id],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list(&self, user_id: u64) -> anyhow::Result<Vec<u32>> {
+        self.db
+            .call(move |connection| {
+                let mut statement = connection.prepare_cached(
+                    "SELECT book_id FROM shelf WHERE user_id = ?1
+                     ORDER BY added_at DESC, rowid DESC",
+                )?;
+
+                let rows = statement.query_map(params![user_id as i64], |row| row.get(0))?;
+
+                rows.collect()
+            })
+            .await
+    }
+}
+
+/// Shelf kept by the user-settings service, so it is shared by every bot
+/// instance.
+pub struct UserSettingsShelfStorage;
+
+#[async_trait]
+impl ShelfStorage for UserSettingsShelfStorage {
+    async fn add(&self, user_id: u64, book_id: u32) -> anyhow::Result<()> {
+        let url = build_url(
+            &config::CONFIG.user_settings_url,
+            ["users", &user_id.to_string(), "shelf", &book_id.to_string()],
+        )?;
+
+        let response = HTTP_CLIENT
+            .put(url)
+            .header("Authorization", &config::CONFIG.user_settings_api_key)
+            .send()
+            .await?;
+
+        check_status(response, &[]).await?;
+
+        Ok(())
+    }
+
+    async fn remove(&self, user_id: u64, book_id: u32) -> anyhow::Result<()> {
+        let url = build_url(
+            &config::CONFIG.user_settings_url,
+            ["users", &user_id.to_string(), "shelf", &book_id.to_string()],
+        )?;
+
+        let response = HTTP_CLIENT
+            .delete(url)
+            .header("Authorization", &config::CONFIG.user_settings_api_key)
+            .send()
+            .await?;
+
+        check_status(response, &[StatusCode::NOT_FOUND]).await?;
+
+        Ok(())
+    }
+
+    async fn list(&self, user_id: u64) -> anyhow::Result<Vec<u32>> {
+        let url = build_url(
+            &config::CONFIG.user_settings_url,
+            ["users", &user_id.to_string(), "shelf", ""],
+        )?;
+
+        let response = HTTP_CLIENT
+            .get(url)
+            .header("Authorization", &config::CONFIG.user_settings_api_key)
+            .send()
+            .await?;
+
+        Ok(check_response(response, &[StatusCode::NOT_FOUND])
+            .await?
+            .unwrap_or_default())
+    }
+}
+
+pub static SHELF: LazyLock<Box<dyn ShelfStorage>> = LazyLock::new(|| {
+    if config::CONFIG.user_settings_shelf {
+        return Box::new(UserSettingsShelfStorage);
+    }
+
+    Box::new(
+        SqliteShelfStorage::new(LOCAL_DB.clone())
+            .unwrap_or_else(|err| panic!("Cannot init shelf storage: {err}")),
+    )
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage() -> SqliteShelfStorage {
+        SqliteShelfStorage::new(LocalDb::open_in_memory().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn lists_newest_first_without_duplicates() {
+        let storage = storage();
+
+        storage.add(1, 10).await.unwrap();
+        storage.add(1, 20).await.unwrap();
+        storage.add(1, 10).await.unwrap();
+        storage.add(2, 30).await.unwrap();
+
+        assert_eq!(storage.list(1).await.unwrap(), vec![20, 10]);
+        assert_eq!(storage.list(2).await.unwrap(), vec![30]);
+    }
+
+    #[tokio::test]
+    async fn remove_drops_only_that_book() {
+        let storage = storage();
+
+        storage.add(1, 10).await.unwrap();
+        storage.add(1, 20).await.unwrap();
+        storage.remove(1, 10).await.unwrap();
+        storage.remove(1, 99).await.unwrap();
+
+        assert_eq!(storage.list(1).await.unwrap(), vec![20]);
+    }
+}
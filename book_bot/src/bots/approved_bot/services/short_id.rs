@@ -0,0 +1,48 @@
+/// Ids fit into this many base36 digits, about 41 random bits.
+const SHORT_ID_LENGTH: u32 = 8;
+
+/// A short random base36 id for an in-memory entry referenced from callback
+/// data. Random rather than counted, so buttons left over from a previous
+/// run do not point at somebody's new entry; the stores still check who an
+/// entry belongs to.
+pub fn new_short_id() -> String {
+    to_base36(rand::random_range(0..36u64.pow(SHORT_ID_LENGTH)))
+}
+
+fn to_base36(mut value: u64) -> String {
+    const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    let mut result = vec![];
+    loop {
+        result.push(DIGITS[(value % 36) as usize]);
+        value /= 36;
+
+        if value == 0 {
+            break;
+        }
+    }
+    result.reverse();
+
+    String::from_utf8(result).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base36_ids_are_short() {
+        assert_eq!(to_base36(0), "0");
+        assert_eq!(to_base36(35), "z");
+        assert_eq!(to_base36(36), "10");
+        assert_eq!(to_base36(36u64.pow(SHORT_ID_LENGTH) - 1), "zzzzzzzz");
+    }
+
+    #[test]
+    fn ids_are_short_and_differ() {
+        let id = new_short_id();
+
+        assert!(id.len() <= SHORT_ID_LENGTH as usize);
+        assert_ne!(id, new_short_id());
+    }
+}
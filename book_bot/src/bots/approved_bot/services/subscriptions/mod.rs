@@ -0,0 +1,374 @@
+pub mod notifier;
+
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use rusqlite::params;
+
+use super::local_db::{LocalDb, LOCAL_DB};
+
+/// What a user can follow to get notified about new uploads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SubscriptionTarget {
+    Author { id: u32 },
+    Translator { id: u32 },
+    Sequence { id: u32 },
+}
+
+impl SubscriptionTarget {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SubscriptionTarget::Author { .. } => "a",
+            SubscriptionTarget::Translator { .. } => "t",
+            SubscriptionTarget::Sequence { .. } => "s",
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        match *self {
+            SubscriptionTarget::Author { id } => id,
+            SubscriptionTarget::Translator { id } => id,
+            SubscriptionTarget::Sequence { id } => id,
+        }
+    }
+
+    pub fn from_parts(kind: &str, id: u32) -> Option<Self> {
+        match kind {
+            "a" => Some(SubscriptionTarget::Author { id }),
+            "t" => Some(SubscriptionTarget::Translator { id }),
+            "s" => Some(SubscriptionTarget::Sequence { id }),
+            _ => None,
+        }
+    }
+}
+
+/// One subscription row. `bot_id` is the Telegram id of the bot the user
+/// subscribed through, so notifications go out via the same bot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subscriber {
+    pub bot_id: u64,
+    pub user_id: u64,
+    pub target: SubscriptionTarget,
+}
+
+#[async_trait]
+pub trait SubscriptionStorage: Send + Sync {
+    async fn subscribe(
+        &self,
+        bot_id: u64,
+        user_id: u64,
+        target: SubscriptionTarget,
+    ) -> anyhow::Result<()>;
+
+    async fn unsubscribe(
+        &self,
+        bot_id: u64,
+        user_id: u64,
+        target: SubscriptionTarget,
+    ) -> anyhow::Result<()>;
+
+    /// Drops every subscription of the user, e.g. after they blocked the bot.
+    async fn unsubscribe_all(&self, bot_id: u64, user_id: u64) -> anyhow::Result<()>;
+
+    async fn is_subscribed(
+        &self,
+        bot_id: u64,
+        user_id: u64,
+        target: SubscriptionTarget,
+    ) -> anyhow::Result<bool>;
+
+    async fn find_subscribers(
+        &self,
+        targets: Vec<SubscriptionTarget>,
+    ) -> anyhow::Result<Vec<Subscriber>>;
+
+    /// Records that the user was told about the book. Returns `false` when
+    /// it had already been recorded, so repeated checks never notify twice.
+    async fn mark_notified(&self, bot_id: u64, user_id: u64, book_id: u32) -> anyhow::Result<bool>;
+
+    /// Forgets notification marks older than `keep_days`.
+    async fn prune_notified(&self, keep_days: u32) -> anyhow::Result<()>;
+}
+
+pub struct SqliteSubscriptionStorage {
+    db: LocalDb,
+}
+
+impl SqliteSubscriptionStorage {
+    pub fn new(db: LocalDb) -> anyhow::Result<Self> {
+        db.migrate(
+            "CREATE TABLE IF NOT EXISTS subscriptions (
+                bot_id INTEGER NOT NULL,
+                user_id INTEGER NOT NULL,
+                target_kind TEXT NOT NULL,
+                target_id INTEGER NOT NULL,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (bot_id, user_id, target_kind, target_id)
+            );
+            CREATE INDEX IF NOT EXISTS subscriptions_target
+                ON subscriptions (target_kind, target_id);
+            CREATE TABLE IF NOT EXISTS subscription_notifications (
+                bot_id INTEGER NOT NULL,
+                user_id INTEGER NOT NULL,
+                book_id INTEGER NOT NULL,
+                notified_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (bot_id, user_id, book_id)
+            );",
+        )?;
+
+        Ok(Self { db })
+    }
+}
+
+// Telegram ids fit into SQLite's signed 64-bit integers.
+#[async_trait]
+impl SubscriptionStorage for SqliteSubscriptionStorage {
+    async fn subscribe(
+        &self,
+        bot_id: u64,
+        user_id: u64,
+        target: SubscriptionTarget,
+    ) -> anyhow::Result<()> {
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "INSERT OR IGNORE INTO subscriptions (bot_id, user_id, target_kind, target_id)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![bot_id as i64, user_id as i64, target.kind(), target.id()],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        bot_id: u64,
+        user_id: u64,
+        target: SubscriptionTarget,
+    ) -> anyhow::Result<()> {
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "DELETE FROM subscriptions
+                     WHERE bot_id = ?1 AND user_id = ?2 AND target_kind = ?3 AND target_id = ?4",
+                    params![bot_id as i64, user_id as i64, target.kind(), target.id()],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn unsubscribe_all(&self, bot_id: u64, user_id: u64) -> anyhow::Result<()> {
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "DELETE FROM subscriptions WHERE bot_id = ?1 AND user_id = ?2",
+                    params![bot_id as i64, user_id as i64],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn is_subscribed(
+        &self,
+        bot_id: u64,
+        user_id: u64,
+        target: SubscriptionTarget,
+    ) -> anyhow::Result<bool> {
+        self.db
+            .call(move |connection| {
+                connection
+                    .prepare_cached(
+                        "SELECT 1 FROM subscriptions
+                         WHERE bot_id = ?1 AND user_id = ?2 AND target_kind = ?3 AND target_id = ?4",
+                    )?
+                    .exists(params![
+                        bot_id as i64,
+                        user_id as i64,
+                        target.kind(),
+                        target.id()
+                    ])
+            })
+            .await
+    }
+
+    async fn find_subscribers(
+        &self,
+        targets: Vec<SubscriptionTarget>,
+    ) -> anyhow::Result<Vec<Subscriber>> {
+        self.db
+            .call(move |connection| {
+                let mut statement = connection.prepare_cached(
+                    "SELECT bot_id, user_id FROM subscriptions
+                     WHERE target_kind = ?1 AND target_id = ?2",
+                )?;
+
+                let mut result = vec![];
+
+                for target in targets {
+                    let rows = statement.query_map(params![target.kind(), target.id()], |row| {
+                        Ok(Subscriber {
+                            bot_id: row.get::<_, i64>(0)? as u64,
+                            user_id: row.get::<_, i64>(1)? as u64,
+                            target,
+                        })
+                    })?;
+
+                    for row in rows {
+                        result.push(row?);
+                    }
+                }
+
+                Ok(result)
+            })
+            .await
+    }
+
+    async fn mark_notified(&self, bot_id: u64, user_id: u64, book_id: u32) -> anyhow::Result<bool> {
+        let inserted = self
+            .db
+            .call(move |connection| {
+                connection.execute(
+                    "INSERT OR IGNORE INTO subscription_notifications (bot_id, user_id, book_id)
+                     VALUES (?1, ?2, ?3)",
+                    params![bot_id as i64, user_id as i64, book_id],
+                )
+            })
+            .await?;
+
+        Ok(inserted > 0)
+    }
+
+    async fn prune_notified(&self, keep_days: u32) -> anyhow::Result<()> {
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "DELETE FROM subscription_notifications
+                     WHERE notified_at < datetime('now', ?1)",
+                    params![format!("-{keep_days} days")],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+pub static SUBSCRIPTIONS: LazyLock<Box<dyn SubscriptionStorage>> = LazyLock::new(|| {
+    Box::new(
+        SqliteSubscriptionStorage::new(LOCAL_DB.clone())
+            .unwrap_or_else(|err| panic!("Cannot init subscriptions storage: {err}")),
+    )
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage() -> SqliteSubscriptionStorage {
+        SqliteSubscriptionStorage::new(LocalDb::open_in_memory().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn subscribe_and_unsubscribe() {
+        let storage = storage();
+        let target = SubscriptionTarget::Author { id: 7 };
+
+        assert!(!storage.is_subscribed(1, 10, target).await.unwrap());
+
+        storage.subscribe(1, 10, target).await.unwrap();
+        storage.subscribe(1, 10, target).await.unwrap();
+        assert!(storage.is_subscribed(1, 10, target).await.unwrap());
+        assert!(!storage.is_subscribed(2, 10, target).await.unwrap());
+
+        storage.unsubscribe(1, 10, target).await.unwrap();
+        assert!(!storage.is_subscribed(1, 10, target).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn finds_subscribers_of_any_target() {
+        let storage = storage();
+
+        storage
+            .subscribe(1, 10, SubscriptionTarget::Author { id: 7 })
+            .await
+            .unwrap();
+        storage
+            .subscribe(1, 11, SubscriptionTarget::Sequence { id: 7 })
+            .await
+            .unwrap();
+        storage
+            .subscribe(2, 12, SubscriptionTarget::Translator { id: 8 })
+            .await
+            .unwrap();
+
+        let subscribers = storage
+            .find_subscribers(vec![
+                SubscriptionTarget::Author { id: 7 },
+                SubscriptionTarget::Translator { id: 8 },
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            subscribers,
+            vec![
+                Subscriber {
+                    bot_id: 1,
+                    user_id: 10,
+                    target: SubscriptionTarget::Author { id: 7 },
+                },
+                Subscriber {
+                    bot_id: 2,
+                    user_id: 12,
+                    target: SubscriptionTarget::Translator { id: 8 },
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn unsubscribe_all_only_touches_one_user() {
+        let storage = storage();
+        let target = SubscriptionTarget::Sequence { id: 3 };
+
+        storage.subscribe(1, 10, target).await.unwrap();
+        storage.subscribe(1, 11, target).await.unwrap();
+        storage.unsubscribe_all(1, 10).await.unwrap();
+
+        assert!(!storage.is_subscribed(1, 10, target).await.unwrap());
+        assert!(storage.is_subscribed(1, 11, target).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn mark_notified_is_idempotent() {
+        let storage = storage();
+
+        assert!(storage.mark_notified(1, 10, 100).await.unwrap());
+        assert!(!storage.mark_notified(1, 10, 100).await.unwrap());
+        assert!(storage.mark_notified(1, 11, 100).await.unwrap());
+
+        storage.prune_notified(30).await.unwrap();
+        assert!(!storage.mark_notified(1, 10, 100).await.unwrap());
+    }
+
+    #[test]
+    fn target_parts_round_trip() {
+        for target in [
+            SubscriptionTarget::Author { id: 1 },
+            SubscriptionTarget::Translator { id: 2 },
+            SubscriptionTarget::Sequence { id: 3 },
+        ] {
+            assert_eq!(
+                SubscriptionTarget::from_parts(target.kind(), target.id()),
+                Some(target)
+            );
+        }
+        assert_eq!(SubscriptionTarget::from_parts("x", 1), None);
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+
+use chrono::{Duration, Utc};
+use smallvec::SmallVec;
+use smartstring::alias::String as SmartString;
+use teloxide::{
+    adaptors::{throttle::Limits, Throttle},
+    prelude::*,
+    ApiError, RequestError,
+};
+use tracing::log;
+
+use crate::{
+    bots::approved_bot::{
+        modules::utils::constants::TELEGRAM_MESSAGE_MAX_LENGTH,
+        services::book_library::{
+            formatters::Format, get_uploaded_books, types::SearchBook, BULK_PAGE_SIZE,
+        },
+        services::user_settings::get_user_or_default_lang_codes,
+    },
+    config,
+};
+
+use super::{SubscriptionTarget, SUBSCRIPTIONS};
+
+const NOTIFICATION_HEADER: &str = "🔔 Новые книги по вашим подпискам:\n\n";
+const NOTIFICATION_FOOTER: &str =
+    "\n\nОтписаться можно кнопкой 🔕 на странице автора, переводчика или серии.";
+
+/// A single book entry never takes more than this, so one prolific upload
+/// cannot push everything else out of the message.
+const BOOK_MAX_LENGTH: usize = 1024;
+
+/// Notification marks only need to outlive the upload window.
+const NOTIFIED_KEEP_DAYS: u32 = 7;
+
+fn book_targets(book: &SearchBook) -> Vec<SubscriptionTarget> {
+    let authors = book
+        .authors
+        .iter()
+        .map(|author| SubscriptionTarget::Author { id: author.id });
+    let translators = book
+        .translators
+        .iter()
+        .map(|translator| SubscriptionTarget::Translator { id: translator.id });
+    let sequences = book
+        .sequences
+        .iter()
+        .filter(|sequence| sequence.id != 0)
+        .map(|sequence| SubscriptionTarget::Sequence { id: sequence.id });
+
+    authors.chain(translators).chain(sequences).collect()
+}
+
+fn is_allowed_lang(book: &SearchBook, allowed_langs: &[SmartString]) -> bool {
+    allowed_langs.iter().any(|lang| lang.as_str() == book.lang)
+}
+
+/// Packs formatted books into as few messages as the Telegram limit allows.
+fn build_notification_messages(books: &[String]) -> Vec<String> {
+    let max_body_length =
+        TELEGRAM_MESSAGE_MAX_LENGTH - NOTIFICATION_HEADER.len() - NOTIFICATION_FOOTER.len();
+
+    let mut bodies: Vec<String> = vec![];
+
+    for book in books {
+        match bodies.last_mut() {
+            Some(body) if body.len() + 2 + book.len() <= max_body_length => {
+                body.push_str("\n\n");
+                body.push_str(book);
+            }
+            _ => bodies.push(book.clone()),
+        }
+    }
+
+    bodies
+        .into_iter()
+        .map(|body| format!("{NOTIFICATION_HEADER}{body}{NOTIFICATION_FOOTER}"))
+        .collect()
+}
+
+async fn get_recent_uploads() -> anyhow::Result<Vec<SearchBook>> {
+    let to = Utc::now().date_naive();
+    let from = to - Duration::days(1);
+
+    let mut books = vec![];
+    let mut page = 1;
+
+    loop {
+        let Some(items_page) = get_uploaded_books(
+            page,
+            BULK_PAGE_SIZE,
+            SmallVec::new(),
+            None,
+            from.format("%Y-%m-%d").to_string().into(),
+            to.format("%Y-%m-%d").to_string().into(),
+        )
+        .await?
+        else {
+            break;
+        };
+
+        books.extend(items_page.items);
+
+        if page >= items_page.pages {
+            break;
+        }
+        page += 1;
+    }
+
+    Ok(books)
+}
+
+/// Finds subscribers of every recently uploaded book and sends each of them
+/// one digest through the bot they subscribed with. `bots` maps Telegram bot
+/// ids onto tokens; subscribers of bots missing from it are left for a later
+/// run.
+///
+/// Books are filtered by the languages each subscriber allowed in
+/// `/settings`. A book is marked as notified before sending, so a failed send is not
+/// retried — missing a notification is better than sending it twice.
+pub async fn notify_subscribers(bots: HashMap<u64, String>) -> anyhow::Result<()> {
+    let books = get_recent_uploads().await?;
+
+    let mut recipients: HashMap<(u64, u64), Vec<String>> = HashMap::new();
+    let mut users_langs: HashMap<u64, SmallVec<[SmartString; 3]>> = HashMap::new();
+
+    for book in books.iter() {
+        let targets = book_targets(book);
+        if targets.is_empty() {
+            continue;
+        }
+
+        let mut seen = HashSet::new();
+
+        for subscriber in SUBSCRIPTIONS.find_subscribers(targets).await? {
+            let key = (subscriber.bot_id, subscriber.user_id);
+
+            if !bots.contains_key(&subscriber.bot_id) || !seen.insert(key) {
+                continue;
+            }
+
+            let allowed_langs = match users_langs.entry(subscriber.user_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    entry.insert(get_user_or_default_lang_codes(UserId(subscriber.user_id)).await)
+                }
+            };
+
+            if !is_allowed_lang(book, allowed_langs) {
+                continue;
+            }
+
+            if SUBSCRIPTIONS
+                .mark_notified(subscriber.bot_id, subscriber.user_id, book.id)
+                .await?
+            {
+                recipients
+                    .entry(key)
+                    .or_default()
+                    .push(book.format(BOOK_MAX_LENGTH).result);
+            }
+        }
+    }
+
+    let mut telegram_bots: HashMap<u64, Throttle<Bot>> = HashMap::new();
+
+    for ((bot_id, user_id), books) in recipients {
+        let Some(token) = bots.get(&bot_id) else {
+            continue;
+        };
+
+        let bot = telegram_bots.entry(bot_id).or_insert_with(|| {
+            Bot::new(token.clone())
+                .set_api_url(config::CONFIG.telegram_bot_api.clone())
+                .throttle(Limits::default())
+        });
+
+        for text in build_notification_messages(&books) {
+            match bot.send_message(ChatId(user_id as i64), text).await {
+                Ok(_) => {}
+                Err(RequestError::Api(
+                    ApiError::BotBlocked | ApiError::UserDeactivated | ApiError::ChatNotFound,
+                )) => {
+                    SUBSCRIPTIONS.unsubscribe_all(bot_id, user_id).await?;
+                    break;
+                }
+                Err(err) => {
+                    log::warn!("Failed to send subscription notification: {err:?}");
+                    break;
+                }
+            }
+        }
+    }
+
+    SUBSCRIPTIONS.prune_notified(NOTIFIED_KEEP_DAYS).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_book() -> SearchBook {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "title": "Книга",
+            "lang": "ru",
+            "annotation_exists": false,
+            "authors": [{"id": 10, "first_name": "", "last_name": "Автор", "middle_name": ""}],
+            "translators": [{"id": 20, "first_name": "", "last_name": "Переводчик", "middle_name": ""}],
+            "sequences": [{"id": 30, "name": "Серия"}, {"id": 0, "name": ""}],
+            "year": 2024
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn book_targets_cover_authors_translators_and_sequences() {
+        assert_eq!(
+            book_targets(&make_book()),
+            vec![
+                SubscriptionTarget::Author { id: 10 },
+                SubscriptionTarget::Translator { id: 20 },
+                SubscriptionTarget::Sequence { id: 30 },
+            ]
+        );
+    }
+
+    #[test]
+    fn books_in_disallowed_languages_are_skipped() {
+        let book = make_book();
+
+        assert!(is_allowed_lang(&book, &["uk".into(), "ru".into()]));
+        assert!(!is_allowed_lang(&book, &["en".into()]));
+    }
+
+    #[test]
+    fn short_digest_fits_one_message() {
+        let messages = build_notification_messages(&["a".to_string(), "b".to_string()]);
+
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].starts_with(NOTIFICATION_HEADER));
+        assert!(messages[0].contains("a\n\nb"));
+    }
+
+    #[test]
+    fn long_digest_is_split_under_the_limit() {
+        let books = vec!["x".repeat(BOOK_MAX_LENGTH); 10];
+        let messages = build_notification_messages(&books);
+
+        assert!(messages.len() > 1);
+        assert!(messages
+            .iter()
+            .all(|message| message.len() <= TELEGRAM_MESSAGE_MAX_LENGTH));
+    }
+}
@@ -0,0 +1,208 @@
+pub mod published_pages;
+
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+use super::{build_url, check_response, HTTP_CLIENT};
+
+/// Telegraph limits page titles to 256 characters.
+const MAX_TITLE_LENGTH: usize = 256;
+
+/// A piece of page content in Telegraph's DOM format: either text or an
+/// element with its children.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Node {
+    Text(String),
+    Element(Element),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Element {
+    pub tag: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attrs: Option<Attrs>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Node>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Attrs {
+    pub href: String,
+}
+
+impl Node {
+    pub fn element(tag: &'static str, children: Vec<Node>) -> Self {
+        Node::Element(Element {
+            tag,
+            attrs: None,
+            children,
+        })
+    }
+
+    pub fn link(href: String, children: Vec<Node>) -> Self {
+        Node::Element(Element {
+            tag: "a",
+            attrs: Some(Attrs { href }),
+            children,
+        })
+    }
+}
+
+/// Publishes read-only pages and returns their URLs.
+#[async_trait]
+pub trait PagePublisher: Send + Sync {
+    async fn publish(&self, title: &str, content: &[Node]) -> anyhow::Result<String>;
+}
+
+/// Talks to the Telegraph API, or to anything answering the same way at
+/// `api_url`.
+pub struct TelegraphPublisher {
+    api_url: Url,
+    access_token: String,
+}
+
+#[derive(Serialize)]
+struct CreatePage<'a> {
+    access_token: &'a str,
+    title: String,
+    content: &'a [Node],
+}
+
+#[derive(Deserialize)]
+struct TelegraphResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Page {
+    url: String,
+}
+
+impl TelegraphPublisher {
+    pub fn new(api_url: Url, access_token: String) -> Self {
+        Self {
+            api_url,
+            access_token,
+        }
+    }
+}
+
+#[async_trait]
+impl PagePublisher for TelegraphPublisher {
+    async fn publish(&self, title: &str, content: &[Node]) -> anyhow::Result<String> {
+        let response = HTTP_CLIENT
+            .post(build_url(&self.api_url, ["createPage"])?)
+            .json(&CreatePage {
+                access_token: &self.access_token,
+                title: title.chars().take(MAX_TITLE_LENGTH).collect(),
+                content,
+            })
+            .send()
+            .await?;
+
+        let response: TelegraphResponse<Page> = check_response(response, &[])
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Empty Telegraph response"))?;
+
+        match response {
+            TelegraphResponse {
+                ok: true,
+                result: Some(page),
+                ..
+            } => Ok(page.url),
+            TelegraphResponse { error, .. } => Err(anyhow::anyhow!(
+                "Telegraph refused the page: {}",
+                error.unwrap_or_default()
+            )),
+        }
+    }
+}
+
+/// `None` when no Telegraph token is configured; the "read full" option is
+/// not offered then.
+pub static PAGE_PUBLISHER: LazyLock<Option<Box<dyn PagePublisher>>> = LazyLock::new(|| {
+    let token = config::CONFIG.telegraph_access_token.clone()?;
+
+    Some(Box::new(TelegraphPublisher::new(
+        config::CONFIG.telegraph_api_url.clone(),
+        token,
+    )))
+});
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    async fn stand_in(reply: Value) -> Url {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let router = Router::new().route(
+            "/createPage",
+            post(move |Json(request): Json<Value>| async move {
+                assert_eq!(request["access_token"], "token");
+                assert_eq!(request["title"], "Война и мир");
+                assert_eq!(
+                    request["content"],
+                    json!([{"tag": "p", "children": ["Текст ", {"tag": "a", "attrs": {"href": "https://ex.com"}, "children": ["ссылка"]}]}])
+                );
+                Json(reply)
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        Url::parse(&format!("http://{address}")).unwrap()
+    }
+
+    fn content() -> Vec<Node> {
+        vec![Node::element(
+            "p",
+            vec![
+                Node::Text("Текст ".to_string()),
+                Node::link(
+                    "https://ex.com".to_string(),
+                    vec![Node::Text("ссылка".to_string())],
+                ),
+            ],
+        )]
+    }
+
+    #[tokio::test]
+    async fn publishes_through_the_api() {
+        let api_url = stand_in(json!({
+            "ok": true,
+            "result": {"path": "Vojna-i-mir-05-10", "url": "https://telegra.ph/Vojna-i-mir-05-10"}
+        }))
+        .await;
+
+        let url = TelegraphPublisher::new(api_url, "token".to_string())
+            .publish("Война и мир", &content())
+            .await
+            .unwrap();
+
+        assert_eq!(url, "https://telegra.ph/Vojna-i-mir-05-10");
+    }
+
+    #[tokio::test]
+    async fn api_errors_are_errors() {
+        let api_url = stand_in(json!({"ok": false, "error": "CONTENT_TOO_BIG"})).await;
+
+        let err = TelegraphPublisher::new(api_url, "token".to_string())
+            .publish("Война и мир", &content())
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("CONTENT_TOO_BIG"));
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use rusqlite::{params, OptionalExtension};
+
+use crate::bots::approved_bot::services::local_db::{LocalDb, LOCAL_DB};
+
+/// A published page and the hash of the content it was published from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublishedPage {
+    pub url: String,
+    pub content_hash: String,
+}
+
+/// Annotations already published as pages. `kind` tells apart books,
+/// authors and the like sharing an id.
+#[async_trait]
+pub trait PublishedPageStorage: Send + Sync {
+    async fn get(&self, kind: &'static str, id: u32) -> anyhow::Result<Option<PublishedPage>>;
+
+    async fn save(&self, kind: &'static str, id: u32, page: PublishedPage) -> anyhow::Result<()>;
+}
+
+/// FNV-1a of the parts, stable across runs and builds unlike `std::hash`.
+/// A changed annotation gets a different hash and is published again.
+pub fn content_hash(parts: &[&str]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+    for part in parts {
+        // The separator keeps ("ab", "c") and ("a", "bc") apart.
+        for byte in part.bytes().chain([0]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    format!("{hash:016x}")
+}
+
+pub struct SqlitePublishedPageStorage {
+    db: LocalDb,
+}
+
+impl SqlitePublishedPageStorage {
+    pub fn new(db: LocalDb) -> anyhow::Result<Self> {
+        db.migrate(
+            "CREATE TABLE IF NOT EXISTS published_pages (
+                kind TEXT NOT NULL,
+                id INTEGER NOT NULL,
+                url TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                PRIMARY KEY (kind, id)
+            );",
+        )?;
+
+        Ok(Self { db })
+    }
+}
+
+#[async_trait]
+impl PublishedPageStorage for SqlitePublishedPageStorage {
+    async fn get(&self, kind: &'static str, id: u32) -> anyhow::Result<Option<PublishedPage>> {
+        self.db
+            .call(move |connection| {
+                connection
+                    .prepare_cached(
+                        "SELECT url, content_hash FROM published_pages
+                         WHERE kind = ?1 AND id = ?2",
+                    )?
+                    .query_row(params![kind, id], |row| {
+                        Ok(PublishedPage {
+                            url: row.get(0)?,
+                            content_hash: row.get(1)?,
+                        })
+                    })
+                    .optional()
+            })
+            .await
+    }
+
+    async fn save(&self, kind: &'static str, id: u32, page: PublishedPage) -> anyhow::Result<()> {
+        self.db
+            .call(move |connection| {
+                connection.execute(
+                    "INSERT OR REPLACE INTO published_pages (kind, id, url, content_hash)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![kind, id, page.url, page.content_hash],
+                )
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+pub static PUBLISHED_PAGES: LazyLock<Box<dyn PublishedPageStorage>> = LazyLock::new(|| {
+    Box::new(
+        SqlitePublishedPageStorage::new(LOCAL_DB.clone())
+            .unwrap_or_else(|err| panic!("Cannot init published pages storage: {err}")),
+    )
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, content_hash: &str) -> PublishedPage {
+        PublishedPage {
+            url: url.to_string(),
+            content_hash: content_hash.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn pages_are_kept_per_kind_and_id() {
+        let storage = SqlitePublishedPageStorage::new(LocalDb::open_in_memory().unwrap()).unwrap();
+
+        storage
+            .save("b", 5, page("https://telegra.ph/a", "1"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            storage.get("b", 5).await.unwrap(),
+            Some(page("https://telegra.ph/a", "1"))
+        );
+        assert_eq!(storage.get("a", 5).await.unwrap(), None);
+        assert_eq!(storage.get("b", 6).await.unwrap(), None);
+
+        storage
+            .save("b", 5, page("https://telegra.ph/b", "2"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            storage.get("b", 5).await.unwrap(),
+            Some(page("https://telegra.ph/b", "2"))
+        );
+    }
+
+    #[test]
+    fn content_hash_tells_changed_content_apart() {
+        assert_eq!(
+            content_hash(&["Война и мир", "Текст"]),
+            content_hash(&["Война и мир", "Текст"])
+        );
+        assert_ne!(
+            content_hash(&["Война и мир", "Текст"]),
+            content_hash(&["Война и мир", "Текст."])
+        );
+        assert_ne!(content_hash(&["ab", "c"]), content_hash(&["a", "bc"]));
+    }
+}
@@ -1,3 +1,6 @@
+use std::ops::RangeInclusive;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
 use moka::future::Cache;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
@@ -10,11 +13,16 @@ use teloxide::types::{ChatId, UserId};
 use tracing::log;
 
 use crate::{
-    bots::approved_bot::services::{build_url, check_response, check_status, HTTP_CLIENT},
+    bots::approved_bot::{
+        modules::utils::constants::TELEGRAM_MESSAGE_MAX_LENGTH,
+        services::{
+            book_library::DEFAULT_PAGE_SIZE, build_url, check_response, check_status, HTTP_CLIENT,
+        },
+    },
     config,
 };
 
-/// API values: "book" | "author" | "series" | "translator"
+/// API values: "book" | "author" | "series" | "translator" | "all"
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum DefaultSearchType {
@@ -22,6 +30,8 @@ pub enum DefaultSearchType {
     Author,
     Series,
     Translator,
+    /// Books, authors, series and translators at once.
+    All,
 }
 
 impl DefaultSearchType {
@@ -31,6 +41,7 @@ impl DefaultSearchType {
             DefaultSearchType::Author => "author",
             DefaultSearchType::Series => "series",
             DefaultSearchType::Translator => "translator",
+            DefaultSearchType::All => "all",
         }
     }
 
@@ -40,6 +51,7 @@ impl DefaultSearchType {
             "author" => Some(DefaultSearchType::Author),
             "series" => Some(DefaultSearchType::Series),
             "translator" => Some(DefaultSearchType::Translator),
+            "all" => Some(DefaultSearchType::All),
             _ => None,
         }
     }
@@ -98,6 +110,76 @@ pub struct UserSettings {
     pub default_search: Option<DefaultSearchType>,
     #[serde(default)]
     pub file_name_lang: FileNameLang,
+    /// Download formats in order of preference, e.g. `["fb2", "epub"]`.
+    #[serde(default)]
+    pub preferred_formats: SmallVec<[SmartString; 3]>,
+    /// Offer the next book of the series after a download.
+    #[serde(default = "default_series_suggestions")]
+    pub series_suggestions: bool,
+    /// Items per page of book, author, series and translator listings.
+    #[serde(default = "default_page_size")]
+    pub page_size: u32,
+    /// Offset of the user's time zone from UTC, in minutes.
+    #[serde(default)]
+    pub utc_offset: i32,
+}
+
+fn default_series_suggestions() -> bool {
+    true
+}
+
+fn default_page_size() -> u32 {
+    DEFAULT_PAGE_SIZE
+}
+
+/// Page sizes offered in `/settings`.
+pub const PAGE_SIZES: [u32; 3] = [5, 10, 20];
+
+/// `Page::format` splits a message between the items of a page; with less
+/// than this many bytes per item, little more than the titles is left.
+const MIN_ITEM_SIZE: usize = 160;
+pub const MAX_PAGE_SIZE: u32 = (TELEGRAM_MESSAGE_MAX_LENGTH / MIN_ITEM_SIZE) as u32;
+
+/// Everything in `UserSettings` except the languages, so settings handlers
+/// can change one field and save the rest untouched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserPreferences {
+    pub default_search: Option<DefaultSearchType>,
+    pub file_name_lang: FileNameLang,
+    pub preferred_formats: SmallVec<[SmartString; 3]>,
+    pub series_suggestions: bool,
+    pub page_size: u32,
+    pub utc_offset: i32,
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        UserPreferences {
+            default_search: None,
+            file_name_lang: FileNameLang::default(),
+            preferred_formats: SmallVec::new(),
+            series_suggestions: default_series_suggestions(),
+            page_size: default_page_size(),
+            utc_offset: 0,
+        }
+    }
+}
+
+impl UserPreferences {
+    /// Preferences stored in `settings`, or the defaults for a new user.
+    pub fn from_settings(settings: Option<&UserSettings>) -> Self {
+        match settings {
+            Some(settings) => UserPreferences {
+                default_search: settings.default_search,
+                file_name_lang: settings.file_name_lang,
+                preferred_formats: settings.preferred_formats.clone(),
+                series_suggestions: settings.series_suggestions,
+                page_size: settings.page_size,
+                utc_offset: settings.utc_offset,
+            },
+            None => UserPreferences::default(),
+        }
+    }
 }
 
 pub static USER_SETTINGS_CACHE: LazyLock<Cache<UserId, Option<UserSettings>>> =
@@ -165,7 +247,6 @@ pub async fn get_user_or_default_lang_codes(user_id: UserId) -> SmallVec<[SmartS
     }
 }
 
-#[allow(clippy::too_many_arguments)]
 pub async fn create_or_update_user_settings(
     user_id: UserId,
     last_name: &str,
@@ -173,12 +254,11 @@ pub async fn create_or_update_user_settings(
     username: &str,
     source: &str,
     allowed_langs: SmallVec<[SmartString; 3]>,
-    default_search: Option<DefaultSearchType>,
-    file_name_lang: FileNameLang,
+    preferences: UserPreferences,
 ) -> anyhow::Result<UserSettings> {
     USER_SETTINGS_CACHE.invalidate(&user_id).await;
 
-    let default_search_json = match &default_search {
+    let default_search_json = match &preferences.default_search {
         Some(t) => serde_json::Value::String(t.as_api_str().to_string()),
         None => serde_json::Value::Null,
     };
@@ -190,7 +270,11 @@ pub async fn create_or_update_user_settings(
         "source": source,
         "allowed_langs": allowed_langs.into_vec(),
         "default_search": default_search_json,
-        "file_name_lang": file_name_lang.as_api_str(),
+        "file_name_lang": preferences.file_name_lang.as_api_str(),
+        "preferred_formats": preferences.preferred_formats.into_vec(),
+        "series_suggestions": preferences.series_suggestions,
+        "page_size": preferences.page_size,
+        "utc_offset": preferences.utc_offset,
     });
 
     let url = build_url(&config::CONFIG.user_settings_url, ["users", ""])?;
@@ -216,8 +300,7 @@ pub async fn save_user_settings(
     user: &teloxide::types::User,
     me: &teloxide::types::Me,
     allowed_langs: SmallVec<[SmartString; 3]>,
-    default_search: Option<DefaultSearchType>,
-    file_name_lang: FileNameLang,
+    preferences: UserPreferences,
 ) -> anyhow::Result<UserSettings> {
     create_or_update_user_settings(
         user.id,
@@ -226,8 +309,7 @@ pub async fn save_user_settings(
         user.username.as_deref().unwrap_or(""),
         me.username.as_deref().unwrap_or_default(),
         allowed_langs,
-        default_search,
-        file_name_lang,
+        preferences,
     )
     .await
 }
@@ -259,6 +341,73 @@ pub async fn get_user_file_name_lang_for(user_id: Option<u64>) -> FileNameLang {
     }
 }
 
+/// Returns the user's preferred download formats, most wanted first.
+/// Empty if not set, the user has no settings, or the request failed.
+pub async fn get_user_preferred_formats(user_id: UserId) -> SmallVec<[SmartString; 3]> {
+    get_cached_user_settings(user_id)
+        .await
+        .map(|settings| settings.preferred_formats)
+        .unwrap_or_default()
+}
+
+/// Whether to offer the next book of the series after a download. On by
+/// default, also when the settings could not be loaded.
+pub async fn get_user_series_suggestions(user_id: UserId) -> bool {
+    get_cached_user_settings(user_id)
+        .await
+        .map(|settings| settings.series_suggestions)
+        .unwrap_or_else(default_series_suggestions)
+}
+
+/// Items per listing page, within `1..=MAX_PAGE_SIZE` whatever the stored
+/// value is.
+pub async fn get_user_page_size(user_id: UserId) -> u32 {
+    get_cached_user_settings(user_id)
+        .await
+        .map(|settings| settings.page_size)
+        .unwrap_or_else(default_page_size)
+        .clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Time zone offsets offered in `/settings`, in minutes.
+pub const UTC_OFFSETS: RangeInclusive<i32> = -12 * 60..=14 * 60;
+
+/// The user's time zone; UTC if not set or out of range.
+pub async fn get_user_timezone(user_id: UserId) -> FixedOffset {
+    let utc_offset = get_cached_user_settings(user_id)
+        .await
+        .map(|settings| settings.utc_offset)
+        .unwrap_or_default();
+
+    if !UTC_OFFSETS.contains(&utc_offset) {
+        return FixedOffset::east_opt(0).unwrap();
+    }
+
+    FixedOffset::east_opt(utc_offset * 60).unwrap()
+}
+
+/// The date in `timezone` at `now`.
+pub fn local_date(timezone: FixedOffset, now: DateTime<Utc>) -> NaiveDate {
+    now.with_timezone(&timezone).date_naive()
+}
+
+/// The user's current date.
+pub async fn get_user_today(user_id: UserId) -> NaiveDate {
+    local_date(get_user_timezone(user_id).await, Utc::now())
+}
+
+/// First of `preferred` the book is available in.
+pub fn pick_preferred_format<'a, T: AsRef<str>>(
+    preferred: &[SmartString],
+    available: &'a [T],
+) -> Option<&'a T> {
+    preferred.iter().find_map(|format| {
+        available
+            .iter()
+            .find(|available| available.as_ref() == format.as_str())
+    })
+}
+
 pub async fn get_langs() -> anyhow::Result<Vec<Lang>> {
     let url = build_url(&config::CONFIG.user_settings_url, ["languages", ""])?;
 
@@ -364,6 +513,61 @@ mod tests {
         assert_eq!(result.into_vec(), vec!["ru", "be", "uk"]);
     }
 
+    #[test]
+    fn pick_preferred_format_follows_preference_order() {
+        let available = vec!["epub".to_string(), "fb2".to_string(), "mobi".to_string()];
+
+        let preferred: SmallVec<[SmartString; 3]> = smallvec!["pdf".into(), "fb2".into()];
+        assert_eq!(
+            pick_preferred_format(&preferred, &available).map(String::as_str),
+            Some("fb2")
+        );
+
+        let preferred: SmallVec<[SmartString; 3]> = smallvec!["djvu".into()];
+        assert_eq!(pick_preferred_format(&preferred, &available), None);
+        assert_eq!(pick_preferred_format::<String>(&[], &available), None);
+    }
+
+    #[test]
+    fn settings_without_preferred_formats_still_deserialize() {
+        let settings: UserSettings = serde_json::from_value(json!({
+            "allowed_langs": [{"label": "Русский", "code": "ru"}],
+            "default_search": "author",
+        }))
+        .unwrap();
+
+        let preferences = UserPreferences::from_settings(Some(&settings));
+        assert_eq!(preferences.default_search, Some(DefaultSearchType::Author));
+        assert!(preferences.preferred_formats.is_empty());
+        assert!(preferences.series_suggestions);
+        assert!(UserPreferences::default().series_suggestions);
+        assert_eq!(preferences.page_size, DEFAULT_PAGE_SIZE);
+    }
+
+    #[test]
+    fn local_date_follows_the_time_zone() {
+        use chrono::TimeZone;
+
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 22, 30, 0).unwrap();
+        let moscow = FixedOffset::east_opt(3 * 3600).unwrap();
+        let new_york = FixedOffset::west_opt(4 * 3600).unwrap();
+
+        assert_eq!(
+            local_date(moscow, now),
+            NaiveDate::from_ymd_opt(2024, 5, 2).unwrap()
+        );
+        assert_eq!(
+            local_date(new_york, now),
+            NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn offered_page_sizes_fit_a_message() {
+        assert!(PAGE_SIZES.contains(&DEFAULT_PAGE_SIZE));
+        assert!(PAGE_SIZES.iter().all(|size| *size <= MAX_PAGE_SIZE));
+    }
+
     #[tokio::test]
     async fn try_get_with_never_caches_an_error() {
         let cache: Cache<u32, Option<u32>> = Cache::builder().build();
@@ -1,6 +1,8 @@
 mod approved_bot;
 pub mod registration;
 
+use std::collections::HashMap;
+
 use teloxide::prelude::*;
 
 pub type BotHandlerInternal = anyhow::Result<()>;
@@ -32,3 +34,15 @@ fn ignore_chat_join_request() -> crate::bots::BotHandler {
 pub fn get_bot_handler() -> (BotHandler, BotCommands) {
     approved_bot::get_approved_handler()
 }
+
+/// Sends new-upload notifications to subscribers; `bots` maps Telegram bot
+/// ids onto their tokens.
+pub async fn notify_subscribers(bots: HashMap<u64, String>) -> anyhow::Result<()> {
+    approved_bot::services::subscriptions::notifier::notify_subscribers(bots).await
+}
+
+/// Sends new-arrivals digests that are due; `bots` maps Telegram bot ids
+/// onto their tokens.
+pub async fn send_digests(bots: HashMap<u64, String>) -> anyhow::Result<()> {
+    approved_bot::services::digests::sender::send_digests(bots).await
+}
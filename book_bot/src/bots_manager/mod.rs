@@ -6,12 +6,14 @@ pub mod error_classification;
 pub mod internal;
 pub mod utils;
 
+use std::collections::HashMap;
 use std::sync::LazyLock;
 use teloxide::adaptors::throttle::Limits;
 use teloxide::stop::{StopFlag, StopToken};
 use tokio::task::JoinSet;
 use tracing::log;
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::watch;
 
@@ -31,6 +33,7 @@ use self::bot_manager_client::get_bots;
 pub use self::bot_manager_client::{BotCache, BotData};
 use self::closable_sender::ClosableSender;
 use self::internal::set_webhook;
+use self::utils::telegram_bot_id;
 
 pub static USER_ACTIVITY_CACHE: LazyLock<Cache<UserId, ()>> = LazyLock::new(|| {
     Cache::builder()
@@ -82,6 +85,41 @@ pub static INITED_BOTS_IDS: LazyLock<Cache<u32, ()>> = LazyLock::new(|| Cache::b
 pub static COMMANDS_SET_BOT_IDS: LazyLock<Cache<u32, ()>> =
     LazyLock::new(|| Cache::builder().build());
 
+/// Set while a subscriptions check is in flight; a run can take longer
+/// than the tick interval on busy days.
+static SUBSCRIPTIONS_CHECK_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Set while digests are being sent.
+static DIGESTS_CHECK_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Clears a running flag when dropped, so a panicking run does not block
+/// the later ones.
+struct RunningGuard(&'static AtomicBool);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Spawns `fut` unless a previous run guarded by `flag` is still going, in
+/// which case `None` is returned.
+fn run_exclusive<F>(flag: &'static AtomicBool, fut: F) -> Option<tokio::task::JoinHandle<()>>
+where
+    F: std::future::Future<Output = ()> + Send + 'static,
+{
+    if flag.swap(true, Ordering::SeqCst) {
+        return None;
+    }
+
+    let guard = RunningGuard(flag);
+
+    Some(tokio::spawn(async move {
+        let _guard = guard;
+        fut.await;
+    }))
+}
+
 async fn record_webhook_check_success(bot_id: u32) {
     WEBHOOK_CHECK_ERRORS_COUNT.insert(bot_id, 0).await;
 }
@@ -300,6 +338,42 @@ impl BotsManager {
         }
     }
 
+    /// Bot tokens keyed by their Telegram bot id.
+    fn get_bot_tokens() -> HashMap<u64, String> {
+        BOTS_DATA
+            .iter()
+            .filter_map(|(token, _)| Some((telegram_bot_id(&token)?, token.as_str().to_string())))
+            .collect()
+    }
+
+    fn check_subscriptions() {
+        let bots = Self::get_bot_tokens();
+
+        let started = run_exclusive(&SUBSCRIPTIONS_CHECK_RUNNING, async move {
+            if let Err(err) = crate::bots::notify_subscribers(bots).await {
+                log::error!("Subscriptions check failed: {err:?}");
+            }
+        });
+
+        if started.is_none() {
+            log::warn!("Previous subscriptions check is still running; skipping");
+        }
+    }
+
+    fn check_digests() {
+        let bots = Self::get_bot_tokens();
+
+        let started = run_exclusive(&DIGESTS_CHECK_RUNNING, async move {
+            if let Err(err) = crate::bots::send_digests(bots).await {
+                log::error!("Digests check failed: {err:?}");
+            }
+        });
+
+        if started.is_none() {
+            log::warn!("Previous digests check is still running; skipping");
+        }
+    }
+
     async fn wait_for_telegram_api() {
         let client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(5))
@@ -374,6 +448,14 @@ impl BotsManager {
                 BotsManager::check_pending_updates().await;
             }
 
+            if BotsManager::should_run_subscriptions_check(tick_number) {
+                BotsManager::check_subscriptions();
+            }
+
+            if BotsManager::should_run_digests_check(tick_number) {
+                BotsManager::check_digests();
+            }
+
             tick_number = (tick_number + 1) % 1800;
         }
     }
@@ -385,6 +467,15 @@ impl BotsManager {
     fn should_run_pending_updates_check(tick_number: i32) -> bool {
         tick_number % 1800 == 600
     }
+
+    fn should_run_subscriptions_check(tick_number: i32) -> bool {
+        tick_number % 1800 == 1200
+    }
+
+    /// Every 10 minutes, so a digest comes shortly after its hour.
+    fn should_run_digests_check(tick_number: i32) -> bool {
+        tick_number % 600 == 300
+    }
 }
 
 #[cfg(test)]
@@ -392,6 +483,26 @@ mod tests {
     use super::*;
     use teloxide::stop::mk_stop_token;
 
+    #[tokio::test]
+    async fn exclusive_run_clears_its_flag_even_after_a_panic() {
+        static FLAG: AtomicBool = AtomicBool::new(false);
+
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let handle = run_exclusive(&FLAG, async move {
+            let _ = rx.await;
+            panic!("check failed");
+        })
+        .unwrap();
+
+        assert!(run_exclusive(&FLAG, async {}).is_none());
+
+        tx.send(()).unwrap();
+        assert!(handle.await.is_err());
+
+        assert!(!FLAG.load(Ordering::SeqCst));
+        run_exclusive(&FLAG, async {}).unwrap().await.unwrap();
+    }
+
     #[tokio::test]
     async fn invalidating_a_route_stops_its_token_and_closes_its_sender() {
         let (stop_token, stop_flag) = mk_stop_token();
@@ -630,6 +741,23 @@ mod tests {
         assert!(!BotsManager::should_run_pending_updates_check(1799));
     }
 
+    #[test]
+    fn subscriptions_check_runs_once_per_1800_tick_cycle() {
+        assert!(!BotsManager::should_run_subscriptions_check(0));
+        assert!(!BotsManager::should_run_subscriptions_check(600));
+        assert!(BotsManager::should_run_subscriptions_check(1200));
+        assert!(!BotsManager::should_run_subscriptions_check(1201));
+    }
+
+    #[test]
+    fn digests_check_runs_three_times_per_1800_tick_cycle() {
+        assert!(!BotsManager::should_run_digests_check(0));
+        assert!(BotsManager::should_run_digests_check(300));
+        assert!(BotsManager::should_run_digests_check(900));
+        assert!(BotsManager::should_run_digests_check(1500));
+        assert!(!BotsManager::should_run_digests_check(1200));
+    }
+
     #[tokio::test]
     async fn wait_for_handles_returns_zero_once_all_tasks_finish() {
         let handles = vec![
@@ -21,6 +21,11 @@ pub fn mask_uri_path(path: &str) -> String {
     path.to_string()
 }
 
+/// Telegram id of the bot, i.e. the numeric part of its token before `:`.
+pub fn telegram_bot_id(token: &str) -> Option<u64> {
+    token.split_once(':')?.0.parse().ok()
+}
+
 /// Truncates `s` to at most `max_chars` characters for safe logging,
 /// appending `…` when truncated. Char-based (not byte-based) so it never
 /// panics on multi-byte UTF-8 input, unlike `mask_token`'s byte slicing
@@ -64,6 +69,13 @@ mod tests {
         assert_eq!(mask_uri_path("/health"), "/health");
     }
 
+    #[test]
+    fn telegram_bot_id_from_token() {
+        assert_eq!(telegram_bot_id("123456789:ABC-secret"), Some(123456789));
+        assert_eq!(telegram_bot_id("not-a-token"), None);
+        assert_eq!(telegram_bot_id("abc:def"), None);
+    }
+
     #[test]
     fn truncate_for_log_leaves_short_strings_untouched() {
         assert_eq!(truncate_for_log("hello", 10), "hello");
@@ -20,6 +20,8 @@ pub struct Config {
     // or the appended API paths will be nested under it.
     pub user_settings_url: reqwest::Url,
     pub user_settings_api_key: String,
+    /// Keep bookshelves in the user-settings service instead of the local db.
+    pub user_settings_shelf: bool,
 
     pub book_server_url: reqwest::Url,
     pub book_server_api_key: String,
@@ -31,7 +33,14 @@ pub struct Config {
     pub public_batch_downloader_url: reqwest::Url,
     pub batch_downloader_api_key: String,
 
+    /// Without a token long annotations are only paged through.
+    pub telegraph_api_url: reqwest::Url,
+    pub telegraph_access_token: Option<String>,
+
     pub sentry_dsn: Option<String>,
+
+    /// SQLite file for bot-local state (subscriptions, bookshelves etc.).
+    pub local_db_path: String,
 }
 
 fn get_env(env: &'static str) -> String {
@@ -58,6 +67,9 @@ impl Config {
             user_settings_url: reqwest::Url::parse(&get_env("USER_SETTINGS_URL"))
                 .unwrap_or_else(|_| panic!("Cannot parse url from USER_SETTINGS_URL env variable")),
             user_settings_api_key: get_env("USER_SETTINGS_API_KEY"),
+            user_settings_shelf: std::env::var("USER_SETTINGS_SHELF")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
 
             book_server_url: reqwest::Url::parse(&get_env("BOOK_SERVER_URL"))
                 .unwrap_or_else(|_| panic!("Cannot parse url from BOOK_SERVER_URL env variable")),
@@ -79,7 +91,17 @@ impl Config {
             }),
             batch_downloader_api_key: get_env("BATCH_DOWNLOADER_API_KEY"),
 
+            telegraph_api_url: reqwest::Url::parse(
+                &std::env::var("TELEGRAPH_API_URL")
+                    .unwrap_or_else(|_| "https://api.telegra.ph".to_string()),
+            )
+            .unwrap_or_else(|_| panic!("Cannot parse url from TELEGRAPH_API_URL env variable")),
+            telegraph_access_token: std::env::var("TELEGRAPH_ACCESS_TOKEN").ok(),
+
             sentry_dsn: std::env::var("SENTRY_DSN").ok(),
+
+            local_db_path: std::env::var("LOCAL_DB_PATH")
+                .unwrap_or_else(|_| "book_bot.sqlite3".to_string()),
         }
     }
 }
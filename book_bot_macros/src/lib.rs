@@ -64,7 +64,7 @@ fn generate_log_stmt(input_fn: &ItemFn, handler_name: &LitStr) -> proc_macro2::T
                         );
                     };
                 }
-                "CallbackQuery" => {
+                "CallbackQuery" | "InlineQuery" => {
                     return quote! {
                         tracing::info!(
                             handler = #handler_name,